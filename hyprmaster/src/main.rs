@@ -5,6 +5,8 @@ use std::error::Error;
 mod theme;
 mod utils;
 mod tablet;
+mod screen;
+mod wallpaper;
 mod services;
 
 pub mod ui {
@@ -14,6 +16,13 @@ pub mod ui {
 
 use ui::*;
 use tablet::TabletController;
+use screen::{
+  ScreenController,
+  WorkspaceEditor,
+  NightLightController,
+  BrightnessController
+};
+use wallpaper::WallpaperController;
 
 
 #[tokio::main]
@@ -26,6 +35,31 @@ async fn main() -> Result<(), Box<dyn Error>> {
     services.clone()
   ).await;
 
+  _ = ScreenController::new(
+    &main_window,
+    services.clone()
+  ).await;
+
+  _ = WorkspaceEditor::new(
+    &main_window,
+    services.clone()
+  ).await;
+
+  _ = NightLightController::new(
+    &main_window,
+    services.clone()
+  ).await;
+
+  _ = BrightnessController::new(
+    &main_window,
+    services.clone()
+  ).await;
+
+  _ = WallpaperController::new(
+    &main_window,
+    services.clone()
+  ).await;
+
   main_window.run()
     .map_err(|e| Box::new(e) as Box<dyn Error>)
 }
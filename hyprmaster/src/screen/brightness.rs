@@ -0,0 +1,113 @@
+use std::rc::Rc;
+
+use futures_util::StreamExt;
+
+use slint::{ComponentHandle, Model, ModelRc, VecModel};
+
+use crate::ui::{MainWindow, MonitorBrightness, ScreenUIState};
+use crate::services::Services;
+
+
+type BrightnessRows = Rc<VecModel<MonitorBrightness>>;
+
+/// Brightness sliders of the screen section
+pub struct BrightnessController {
+  rows: BrightnessRows,
+  services: Services<'static>,
+}
+
+impl BrightnessController {
+  pub async fn new(
+    window: &MainWindow,
+    services: Services<'static>
+  ) -> Self {
+    let rows = BrightnessRows::default();
+
+    let state = window.global::<ScreenUIState>();
+    state.set_brightness(ModelRc::from(rows.clone()));
+
+    reload(&rows, &services).await;
+
+    let (rows_s, services_s) = (rows.clone(), services.clone());
+    state.on_set_brightness(move |name, percent| {
+      // Moves right away, the daemon's change follows
+      let row = rows_s
+        .iter()
+        .position(|r| r.name == name);
+
+      if let Some(i) = row {
+        rows_s.set_row_data(i, MonitorBrightness {
+          name: name.clone(),
+          percent
+        });
+      }
+
+      let (rows, services) = (rows_s.clone(), services_s.clone());
+      _ = slint::spawn_local(async move {
+        let res = services.screen()
+          .set_brightness(&name, percent as f64).await;
+
+        if let Err(e) = res {
+          eprintln!("Failed to set brightness: {:#?}", e);
+          reload(&rows, &services).await;
+        }
+      });
+    });
+
+    let (rows_w, services_w) = (rows.clone(), services.clone());
+    _ = slint::spawn_local(async move {
+      watch_brightness(&rows_w, &services_w).await;
+    });
+
+    Self {
+      rows,
+      services
+    }
+  }
+}
+
+
+/// Follows monitors coming and going, and brightness set
+/// by other clients
+async fn watch_brightness(
+  rows: &BrightnessRows,
+  services: &Services<'static>
+) {
+  let screen = services.screen();
+  let proxy = match screen.proxy() {
+    Some(p) => p,
+    None => return
+  };
+
+  let mut changes = proxy.receive_brightness_changed().await;
+
+  while changes.next().await.is_some() {
+    reload(rows, services).await;
+  }
+}
+
+async fn reload(
+  rows: &BrightnessRows,
+  services: &Services<'static>
+) {
+  let brightness = match services.screen()
+    .brightness().await
+  {
+    Ok(b) => b,
+    Err(e) => {
+      eprintln!("Failed to load brightness: {:#?}", e);
+      return
+    }
+  };
+
+  let mut list: Vec<MonitorBrightness> = brightness
+    .into_iter()
+    .map(|(name, percent)| MonitorBrightness {
+      name: name.into(),
+      percent: percent as f32
+    })
+    .collect();
+
+  list.sort_by(|a, b| a.name.cmp(&b.name));
+  rows.set_vec(list);
+}
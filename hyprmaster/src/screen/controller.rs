@@ -0,0 +1,453 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use std::time::Duration;
+
+use slint::{
+  ComponentHandle,
+  ModelRc,
+  VecModel,
+  Timer,
+  TimerMode
+};
+
+use crate::ui::{
+  MainWindow,
+  MonitorRect,
+  ScalingChangeRow,
+  ScreenUIState
+};
+use crate::services::Services;
+
+use super::MonitorLayout;
+
+
+type LayoutRc = Rc<RefCell<MonitorLayout>>;
+type MonitorRects = Rc<VecModel<MonitorRect>>;
+
+/// Seconds an applied change has to be kept before the
+/// daemon reverts it
+const CONFIRM_TIMEOUT: u32 = 15;
+
+pub struct ScreenController {
+  layout: LayoutRc,
+  services: Services<'static>,
+}
+
+impl ScreenController {
+  pub async fn new(
+    window: &MainWindow,
+    services: Services<'static>
+  ) -> Self {
+    if let Err(e) = services.init_screen().await {
+      eprintln!("Failed to load monitors: {:#?}", e);
+    }
+
+    let layout = LayoutRc::new(RefCell::new(
+      current_layout(&services)));
+    let rects = MonitorRects::default();
+
+    let state = window.global::<ScreenUIState>();
+    state.set_monitors(ModelRc::from(rects.clone()));
+    sync_state(&state, &layout.borrow(), &rects);
+
+    let (weak, layout_m, rects_m) = (
+      window.as_weak(), layout.clone(), rects.clone());
+    state.on_move_monitor(move |name, x, y| {
+      let window = match weak.upgrade() {
+        Some(w) => w,
+        None => return
+      };
+
+      layout_m.borrow_mut()
+        .move_monitor(&name, x as f64, y as f64);
+
+      sync_state(
+        &window.global::<ScreenUIState>(),
+        &layout_m.borrow(),
+        &rects_m);
+    });
+
+    let (weak, layout_r, rects_r, services_r) = (
+      window.as_weak(),
+      layout.clone(),
+      rects.clone(),
+      services.clone());
+    state.on_revert(move || {
+      let window = match weak.upgrade() {
+        Some(w) => w,
+        None => return
+      };
+
+      *layout_r.borrow_mut() = current_layout(&services_r);
+      sync_state(
+        &window.global::<ScreenUIState>(),
+        &layout_r.borrow(),
+        &rects_r);
+    });
+
+    let countdown = Rc::new(Timer::default());
+
+    let (weak, layout_a, rects_a, services_a, countdown_a) = (
+      window.as_weak(),
+      layout.clone(),
+      rects.clone(),
+      services.clone(),
+      countdown.clone());
+    state.on_apply(move || {
+      let configs = layout_a.borrow().changed();
+      if configs.is_empty() { return }
+
+      let (weak, layout, rects, services, countdown) = (
+        weak.clone(),
+        layout_a.clone(),
+        rects_a.clone(),
+        services_a.clone(),
+        countdown_a.clone());
+
+      _ = slint::spawn_local(async move {
+        let res = services.screen()
+          .apply(configs, CONFIRM_TIMEOUT).await;
+
+        *layout.borrow_mut() = current_layout(&services);
+
+        let window = match weak.upgrade() {
+          Some(w) => w,
+          None => return
+        };
+
+        let state = window.global::<ScreenUIState>();
+        sync_state(&state, &layout.borrow(), &rects);
+
+        match res {
+          Ok(_) => start_countdown(
+            &countdown, &window, &layout, &rects, &services),
+          Err(e) => eprintln!(
+            "Failed to apply monitors: {:#?}", e)
+        }
+      });
+    });
+
+    let (weak, services_k, countdown_k) = (
+      window.as_weak(), services.clone(), countdown.clone());
+    state.on_keep_changes(move || {
+      countdown_k.stop();
+
+      if let Some(window) = weak.upgrade() {
+        window.global::<ScreenUIState>().set_confirming(false);
+      }
+
+      let services = services_k.clone();
+      _ = slint::spawn_local(async move {
+        if let Err(e) = services.screen().confirm().await {
+          eprintln!("Failed to keep monitors: {:#?}", e);
+        }
+      });
+    });
+
+    let (weak, layout_rc, rects_rc, services_rc, countdown_rc) = (
+      window.as_weak(),
+      layout.clone(),
+      rects.clone(),
+      services.clone(),
+      countdown.clone());
+    state.on_revert_changes(move || {
+      countdown_rc.stop();
+
+      let (weak, layout, rects, services) = (
+        weak.clone(),
+        layout_rc.clone(),
+        rects_rc.clone(),
+        services_rc.clone());
+
+      _ = slint::spawn_local(async move {
+        if let Err(e) = services.screen().revert().await {
+          eprintln!("Failed to revert monitors: {:#?}", e);
+        }
+
+        reload(&weak, &layout, &rects, &services);
+      });
+    });
+
+    let focused = services.screen().monitors()
+      .read()
+      .unwrap()
+      .iter()
+      .find(|m| m.focused)
+      .map(|m| (m.name.clone(), m.scale));
+
+    if let Some((name, scale)) = focused {
+      state.set_scaling_monitor(name.into());
+      state.set_scaling_scale(format!("{scale}").into());
+    }
+
+    let (weak, services_p) = (window.as_weak(), services.clone());
+    _ = slint::spawn_local(async move {
+      preview_scaling(&weak, &services_p).await;
+    });
+
+    let (weak, services_sm) = (window.as_weak(), services.clone());
+    state.on_select_scaling_monitor(move |name| {
+      let window = match weak.upgrade() {
+        Some(w) => w,
+        None => return
+      };
+
+      // Starts from the monitor's own scale
+      let scale = services_sm.screen().monitors()
+        .read()
+        .unwrap()
+        .iter()
+        .find(|m| name == m.name)
+        .map(|m| m.scale)
+        .unwrap_or(1.0);
+
+      let state = window.global::<ScreenUIState>();
+      state.set_scaling_monitor(name);
+      state.set_scaling_scale(format!("{scale}").into());
+
+      let (weak, services) = (weak.clone(), services_sm.clone());
+      _ = slint::spawn_local(async move {
+        preview_scaling(&weak, &services).await;
+      });
+    });
+
+    let (weak, services_ss) = (window.as_weak(), services.clone());
+    state.on_select_scaling_scale(move |scale| {
+      if let Some(window) = weak.upgrade() {
+        window.global::<ScreenUIState>()
+          .set_scaling_scale(scale);
+      }
+
+      let (weak, services) = (weak.clone(), services_ss.clone());
+      _ = slint::spawn_local(async move {
+        preview_scaling(&weak, &services).await;
+      });
+    });
+
+    let (weak, layout_s, rects_s, services_s, countdown_s) = (
+      window.as_weak(),
+      layout.clone(),
+      rects.clone(),
+      services.clone(),
+      countdown.clone());
+    state.on_apply_scaling(move || {
+      let (weak, layout, rects, services, countdown) = (
+        weak.clone(),
+        layout_s.clone(),
+        rects_s.clone(),
+        services_s.clone(),
+        countdown_s.clone());
+
+      _ = slint::spawn_local(async move {
+        let (monitor, scale) = match scaling_target(&weak) {
+          Some(t) => t,
+          None => return
+        };
+
+        let res = services.screen()
+          .apply_scaling(&monitor, scale, CONFIRM_TIMEOUT).await;
+
+        *layout.borrow_mut() = current_layout(&services);
+        preview_scaling(&weak, &services).await;
+
+        let window = match weak.upgrade() {
+          Some(w) => w,
+          None => return
+        };
+
+        let state = window.global::<ScreenUIState>();
+        sync_state(&state, &layout.borrow(), &rects);
+
+        match res {
+          Ok(_) => start_countdown(
+            &countdown, &window, &layout, &rects, &services),
+          Err(e) => eprintln!(
+            "Failed to apply scaling: {:#?}", e)
+        }
+      });
+    });
+
+    Self {
+      layout,
+      services
+    }
+  }
+}
+
+
+/// Monitor and scale picked for scaling
+fn scaling_target(
+  weak: &slint::Weak<MainWindow>
+) -> Option<(String, f64)> {
+  let window = weak.upgrade()?;
+  let state = window.global::<ScreenUIState>();
+
+  let monitor = state.get_scaling_monitor().to_string();
+  let scale = state.get_scaling_scale().parse().ok()?;
+
+  match monitor.is_empty() {
+    true => None,
+    false => Some((monitor, scale))
+  }
+}
+
+/// Shows what scaling the picked monitor would change
+async fn preview_scaling(
+  weak: &slint::Weak<MainWindow>,
+  services: &Services<'static>
+) {
+  let (monitor, scale) = match scaling_target(weak) {
+    Some(t) => t,
+    None => return
+  };
+
+  let changes = match services.screen()
+    .preview_scaling(&monitor, scale).await
+  {
+    Ok(c) => c,
+    Err(e) => {
+      eprintln!("Failed to preview scaling: {:#?}", e);
+      return
+    }
+  };
+
+  let rows: Vec<ScalingChangeRow> = changes
+    .into_iter()
+    .map(|c| ScalingChangeRow {
+      setting: c.setting.into(),
+      current: c.current.into(),
+      planned: c.planned.into()
+    })
+    .collect();
+
+  if let Some(window) = weak.upgrade() {
+    window.global::<ScreenUIState>().set_scaling_preview(
+      ModelRc::new(VecModel::from(rows)));
+  }
+}
+
+
+/// Shows the confirmation dialog, counting down to the
+/// daemon's own revert
+fn start_countdown(
+  countdown: &Rc<Timer>,
+  window: &MainWindow,
+  layout: &LayoutRc,
+  rects: &MonitorRects,
+  services: &Services<'static>
+) {
+  let state = window.global::<ScreenUIState>();
+  state.set_confirm_seconds(CONFIRM_TIMEOUT as i32);
+  state.set_confirming(true);
+
+  let (weak, layout, rects, services) = (
+    window.as_weak(),
+    layout.clone(),
+    rects.clone(),
+    services.clone());
+  let timer = Rc::downgrade(countdown);
+
+  countdown.start(
+    TimerMode::Repeated,
+    Duration::from_secs(1),
+    move || {
+      let window = match weak.upgrade() {
+        Some(w) => w,
+        None => return
+      };
+
+      let state = window.global::<ScreenUIState>();
+      let left = state.get_confirm_seconds() - 1;
+      state.set_confirm_seconds(left.max(0));
+
+      if left > 0 { return }
+
+      if let Some(timer) = timer.upgrade() {
+        timer.stop();
+      }
+
+      // The daemon reverted by now, catch up with it
+      let (weak, layout, rects, services) = (
+        weak.clone(),
+        layout.clone(),
+        rects.clone(),
+        services.clone());
+
+      _ = slint::spawn_local(async move {
+        _ = services.init_screen().await;
+        reload(&weak, &layout, &rects, &services);
+      });
+    });
+}
+
+/// Resets the layout to the monitors last fetched and
+/// closes the confirmation dialog
+fn reload(
+  weak: &slint::Weak<MainWindow>,
+  layout: &LayoutRc,
+  rects: &MonitorRects,
+  services: &Services
+) {
+  *layout.borrow_mut() = current_layout(services);
+
+  if let Some(window) = weak.upgrade() {
+    let state = window.global::<ScreenUIState>();
+    state.set_confirming(false);
+    sync_state(&state, &layout.borrow(), rects);
+  }
+}
+
+fn current_layout(services: &Services) -> MonitorLayout {
+  let monitors = services.screen().monitors();
+  let monitors = monitors.read().unwrap().clone();
+
+  MonitorLayout::new(monitors)
+}
+
+/// Pushes `layout` to the arrangement canvas, with the
+/// layout's top left corner at 0,0
+fn sync_state(
+  state: &ScreenUIState,
+  layout: &MonitorLayout,
+  rects: &VecModel<MonitorRect>
+) {
+  let placed = layout.placed();
+
+  let min_x = placed.iter().map(|(_, r)| r.x)
+    .fold(f64::INFINITY, f64::min);
+  let min_y = placed.iter().map(|(_, r)| r.y)
+    .fold(f64::INFINITY, f64::min);
+
+  let mut width: f64 = 1.0;
+  let mut height: f64 = 1.0;
+  let mut list = vec![];
+
+  for (config, rect) in &placed {
+    let monitor = layout.monitors()
+      .iter()
+      .find(|m| m.name == config.name);
+
+    let (x, y) = (rect.x - min_x, rect.y - min_y);
+    width = width.max(x + rect.width);
+    height = height.max(y + rect.height);
+
+    list.push(MonitorRect {
+      name: config.name.clone().into(),
+      description: monitor
+        .map(|m| m.description.clone())
+        .unwrap_or_default()
+        .into(),
+      x: x as f32,
+      y: y as f32,
+      width: rect.width as f32,
+      height: rect.height as f32,
+      focused: monitor.is_some_and(|m| m.focused),
+    });
+  }
+
+  rects.set_vec(list);
+  state.set_layout_width(width as f32);
+  state.set_layout_height(height as f32);
+  state.set_changed(!layout.changed().is_empty());
+}
@@ -0,0 +1,357 @@
+use crate::services::screen::{
+  HyprMonitor,
+  MonitorConfig,
+  MonitorMode
+};
+
+
+/// How close, in logical pixels, an edge has to be
+/// dragged to another monitor's to snap to it
+const SNAP_DISTANCE: f64 = 64.0;
+
+
+/// A monitor's place in the layout, in logical pixels
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct LogicalRect {
+  pub x: f64,
+  pub y: f64,
+  pub width: f64,
+  pub height: f64,
+}
+
+impl LogicalRect {
+  /// Area `config` covers: its mode, rotated by its
+  /// transform and divided by its scale
+  pub fn of(config: &MonitorConfig) -> Self {
+    let (w, h) = (
+      config.mode.width as f64,
+      config.mode.height as f64
+    );
+
+    // Odd transforms rotate by 90 or 270 degrees
+    let (w, h) = match config.transform % 2 {
+      1 => (h, w),
+      _ => (w, h)
+    };
+
+    let scale = match config.scale > 0.0 {
+      true => config.scale,
+      false => 1.0
+    };
+
+    Self {
+      x: config.x as f64,
+      y: config.y as f64,
+      width: (w / scale).round(),
+      height: (h / scale).round(),
+    }
+  }
+
+  fn right(&self) -> f64 { self.x + self.width }
+  fn bottom(&self) -> f64 { self.y + self.height }
+
+  fn overlaps(&self, o: &Self) -> bool {
+    self.x < o.right() && o.x < self.right() &&
+    self.y < o.bottom() && o.y < self.bottom()
+  }
+
+  /// Whether the rects share a stretch of edge
+  fn touches(&self, o: &Self) -> bool {
+    let side = (self.x == o.right() || self.right() == o.x)
+      && self.y < o.bottom() && o.y < self.bottom();
+    let stacked =
+      (self.y == o.bottom() || self.bottom() == o.y)
+      && self.x < o.right() && o.x < self.right();
+
+    side || stacked
+  }
+}
+
+
+/// Pending monitor settings, edited from the screen
+/// section before being applied in one go
+#[derive(Default, Debug, Clone)]
+pub struct MonitorLayout {
+  monitors: Vec<HyprMonitor>,
+  configs: Vec<MonitorConfig>,
+}
+
+impl MonitorLayout {
+  pub fn new(monitors: Vec<HyprMonitor>) -> Self {
+    let configs = monitors
+      .iter()
+      .map(MonitorConfig::from)
+      .collect();
+
+    Self { monitors, configs }
+  }
+
+  pub fn monitors(&self) -> &[HyprMonitor] {
+    &self.monitors
+  }
+
+  pub fn configs(&self) -> &[MonitorConfig] {
+    &self.configs
+  }
+
+  /// Configs that differ from what's currently applied
+  pub fn changed(&self) -> Vec<MonitorConfig> {
+    self.configs
+      .iter()
+      .zip(&self.monitors)
+      .filter(|(c, m)| **c != MonitorConfig::from(*m))
+      .map(|(c, _)| c.clone())
+      .collect()
+  }
+
+  /// Modes a monitor advertises, highest resolution and
+  /// refresh rate first
+  pub fn modes(&self, name: &str) -> Vec<MonitorMode> {
+    let mut modes: Vec<MonitorMode> = self.monitors
+      .iter()
+      .find(|m| m.name == name)
+      .map(|m| m.available_modes
+        .iter()
+        .filter_map(|m| MonitorMode::parse(m))
+        .collect())
+      .unwrap_or_default();
+
+    modes.sort_by(|a, b| (b.width * b.height)
+      .cmp(&(a.width * a.height))
+      .then(b.refresh_rate.total_cmp(&a.refresh_rate)));
+
+    modes
+  }
+
+  /// Monitors taking up room in the layout, that is the
+  /// enabled ones that don't mirror another
+  pub fn placed(&self) ->
+    Vec<(&MonitorConfig, LogicalRect)>
+  {
+    self.configs
+      .iter()
+      .filter(|c| c.enabled && c.mirror.is_empty())
+      .map(|c| (c, LogicalRect::of(c)))
+      .collect()
+  }
+
+  /// Moves `name` towards logical position `x`,`y`,
+  /// snapping its edges to nearby monitors, pushing it
+  /// out of any overlap and keeping it attached to the
+  /// others. The layout is then shifted to start at 0,0.
+  pub fn move_monitor(
+    &mut self,
+    name: &str,
+    x: f64,
+    y: f64
+  ) {
+    let placed = self.placed();
+    let mut rect = match placed
+      .iter()
+      .find(|(c, _)| c.name == name)
+    {
+      Some((_, r)) => LogicalRect { x, y, ..*r },
+      None => return
+    };
+
+    let others: Vec<LogicalRect> = placed
+      .iter()
+      .filter(|(c, _)| c.name != name)
+      .map(|(_, r)| *r)
+      .collect();
+
+    if !others.is_empty() {
+      snap(&mut rect, &others);
+      unoverlap(&mut rect, &others);
+
+      if !others.iter().any(|o| rect.touches(o)) {
+        attach(&mut rect, &others);
+      }
+    }
+
+    let x = rect.x.round() as i32;
+    let y = rect.y.round() as i32;
+
+    self.set_position(name, x, y);
+    self.normalize();
+  }
+
+  /// Shifts every placed monitor so the layout's top left
+  /// corner sits at 0,0
+  fn normalize(&mut self) {
+    let placed = self.placed();
+    let min_x = placed.iter().map(|(c, _)| c.x).min();
+    let min_y = placed.iter().map(|(c, _)| c.y).min();
+
+    let (dx, dy) = match (min_x, min_y) {
+      (Some(x), Some(y)) => (x, y),
+      _ => return
+    };
+
+    for c in self.configs.iter_mut() {
+      if c.enabled && c.mirror.is_empty() {
+        c.x -= dx;
+        c.y -= dy;
+      }
+    }
+  }
+
+  pub fn set_enabled(&mut self, name: &str, enabled: bool) {
+    self.edit(name, |c| c.enabled = enabled);
+  }
+
+  pub fn set_mode(&mut self, name: &str, mode: MonitorMode) {
+    self.edit(name, |c| c.mode = mode);
+  }
+
+  pub fn set_position(&mut self, name: &str, x: i32, y: i32) {
+    self.edit(name, |c| { c.x = x; c.y = y; });
+  }
+
+  pub fn set_scale(&mut self, name: &str, scale: f64) {
+    self.edit(name, |c| c.scale = scale);
+  }
+
+  pub fn set_transform(&mut self, name: &str, transform: i32) {
+    self.edit(name, |c| c.transform = transform);
+  }
+
+  pub fn set_vrr(&mut self, name: &str, vrr: i32) {
+    self.edit(name, |c| c.vrr = vrr);
+  }
+
+  /// Mirrors `name` onto `target`, or stops mirroring
+  /// when `target` is empty
+  pub fn set_mirror(&mut self, name: &str, target: &str) {
+    let target = match target == name {
+      true => "",
+      false => target
+    };
+
+    self.edit(name, |c| c.mirror = target.to_string());
+  }
+
+  fn edit(
+    &mut self,
+    name: &str,
+    f: impl FnOnce(&mut MonitorConfig)
+  ) {
+    if let Some(c) = self.configs
+      .iter_mut()
+      .find(|c| c.name == name)
+    {
+      f(c)
+    }
+  }
+}
+
+
+/// Nearest value among `candidates` within snapping
+/// distance of `value`
+fn nearest(value: f64, candidates: &[f64]) -> Option<f64> {
+  candidates
+    .iter()
+    .copied()
+    .filter(|c| (c - value).abs() <= SNAP_DISTANCE)
+    .min_by(|a, b| (a - value).abs()
+      .total_cmp(&(b - value).abs()))
+}
+
+/// Aligns the edges of `rect` with the closest edges of
+/// `others`, each axis on its own
+fn snap(rect: &mut LogicalRect, others: &[LogicalRect]) {
+  let xs: Vec<f64> = others
+    .iter()
+    .flat_map(|o| [
+      o.right(),
+      o.x - rect.width,
+      o.x,
+      o.right() - rect.width
+    ])
+    .collect();
+
+  let ys: Vec<f64> = others
+    .iter()
+    .flat_map(|o| [
+      o.bottom(),
+      o.y - rect.height,
+      o.y,
+      o.bottom() - rect.height
+    ])
+    .collect();
+
+  if let Some(x) = nearest(rect.x, &xs) { rect.x = x }
+  if let Some(y) = nearest(rect.y, &ys) { rect.y = y }
+}
+
+/// Pushes `rect` out of every monitor it overlaps, the
+/// shortest way out each time
+fn unoverlap(
+  rect: &mut LogicalRect,
+  others: &[LogicalRect]
+) {
+  for _ in 0..=others.len() {
+    let o = match others.iter().find(|o| rect.overlaps(o)) {
+      Some(o) => *o,
+      None => return
+    };
+
+    let moves = [
+      (o.x - rect.width, rect.y),
+      (o.right(), rect.y),
+      (rect.x, o.y - rect.height),
+      (rect.x, o.bottom()),
+    ];
+
+    let (x, y) = moves
+      .into_iter()
+      .min_by(|a, b| {
+        let dist = |(x, y): &(f64, f64)|
+          (x - rect.x).abs() + (y - rect.y).abs();
+
+        dist(a).total_cmp(&dist(b))
+      })
+      .unwrap();
+
+    rect.x = x;
+    rect.y = y;
+  }
+}
+
+/// Moves a detached `rect` against the closest side of
+/// the nearest monitor, so the cursor can cross over
+fn attach(rect: &mut LogicalRect, others: &[LogicalRect]) {
+  let mut best: Option<(f64, f64, f64)> = None;
+
+  for o in others {
+    // Clamp along each side so the rects share an edge
+    let clamp_x = rect.x
+      .clamp(o.x - rect.width + 1.0, o.right() - 1.0);
+    let clamp_y = rect.y
+      .clamp(o.y - rect.height + 1.0, o.bottom() - 1.0);
+
+    let spots = [
+      (o.x - rect.width, clamp_y),
+      (o.right(), clamp_y),
+      (clamp_x, o.y - rect.height),
+      (clamp_x, o.bottom()),
+    ];
+
+    for (x, y) in spots {
+      let candidate = LogicalRect { x, y, ..*rect };
+      if others.iter().any(|o| candidate.overlaps(o)) {
+        continue
+      }
+
+      let dist = (x - rect.x).abs() + (y - rect.y).abs();
+      if best.is_none_or(|(d, _, _)| dist < d) {
+        best = Some((dist, x, y));
+      }
+    }
+  }
+
+  if let Some((_, x, y)) = best {
+    rect.x = x;
+    rect.y = y;
+  }
+}
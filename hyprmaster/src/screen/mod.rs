@@ -0,0 +1,11 @@
+mod layout;
+mod controller;
+mod workspaces;
+mod nightlight;
+mod brightness;
+
+pub use layout::*;
+pub use controller::*;
+pub use workspaces::*;
+pub use nightlight::*;
+pub use brightness::*;
@@ -0,0 +1,140 @@
+use futures_util::{stream, StreamExt};
+
+use slint::ComponentHandle;
+
+use crate::ui::{MainWindow, ScreenUIState};
+use crate::services::Services;
+
+
+/// Shows the night light's state and toggles it from the
+/// screen section
+pub struct NightLightController {
+  services: Services<'static>,
+}
+
+impl NightLightController {
+  pub async fn new(
+    window: &MainWindow,
+    services: Services<'static>
+  ) -> Self {
+    sync_state(&window.as_weak(), &services).await;
+
+    let state = window.global::<ScreenUIState>();
+
+    let (weak, services_t) = (window.as_weak(), services.clone());
+    state.on_toggle_night_light(move || {
+      let (weak, services) = (weak.clone(), services_t.clone());
+
+      _ = slint::spawn_local(async move {
+        let enabled = match weak.upgrade() {
+          Some(w) => w.global::<ScreenUIState>()
+            .get_night_light_enabled(),
+          None => return
+        };
+
+        let res = services.night_light()
+          .set_enabled(!enabled).await;
+
+        if let Err(e) = res {
+          eprintln!("Failed to toggle night light: {:#?}", e);
+        }
+
+        sync_state(&weak, &services).await;
+      });
+    });
+
+    let (weak, services_o) = (window.as_weak(), services.clone());
+    state.on_toggle_night_light_override(move || {
+      let (weak, services) = (weak.clone(), services_o.clone());
+
+      _ = slint::spawn_local(async move {
+        let overridden = match weak.upgrade() {
+          Some(w) => w.global::<ScreenUIState>()
+            .get_night_light_overridden(),
+          None => return
+        };
+
+        let res = services.night_light()
+          .set_overridden(!overridden).await;
+
+        if let Err(e) = res {
+          eprintln!("Failed to override night light: {:#?}", e);
+        }
+
+        sync_state(&weak, &services).await;
+      });
+    });
+
+    let (weak, services_w) = (window.as_weak(), services.clone());
+    _ = slint::spawn_local(async move {
+      watch_night_light(&weak, &services_w).await;
+    });
+
+    Self {
+      services
+    }
+  }
+}
+
+
+/// Follows the schedule's changes, the daemon moves the
+/// temperature on its own
+async fn watch_night_light(
+  weak: &slint::Weak<MainWindow>,
+  services: &Services<'static>
+) {
+  let night_light = services.night_light();
+  let proxy = match night_light.proxy() {
+    Some(p) => p,
+    None => return
+  };
+
+  let mut changes = stream::select(
+    stream::select(
+      proxy.receive_temperature_changed().await.map(|_| ()),
+      proxy.receive_active_changed().await.map(|_| ())
+    ),
+    proxy.receive_overridden_changed().await.map(|_| ())
+  );
+
+  while changes.next().await.is_some() {
+    sync_state(weak, services).await;
+  }
+}
+
+async fn sync_state(
+  weak: &slint::Weak<MainWindow>,
+  services: &Services<'static>
+) {
+  let night_light = services.night_light();
+  let proxy = match night_light.proxy() {
+    Some(p) => p,
+    None => return
+  };
+
+  let (config, temperature, active, overridden) = (
+    night_light.config().await,
+    proxy.temperature().await,
+    proxy.active().await,
+    proxy.overridden().await
+  );
+
+  let window = match weak.upgrade() {
+    Some(w) => w,
+    None => return
+  };
+
+  let state = window.global::<ScreenUIState>();
+
+  match config {
+    Ok(c) => state.set_night_light_enabled(c.enabled),
+    Err(e) => eprintln!(
+      "Failed to load night light: {:#?}", e)
+  }
+
+  state.set_night_light_temperature(
+    temperature.unwrap_or_default() as i32);
+  state.set_night_light_active(active.unwrap_or_default());
+  state.set_night_light_overridden(
+    overridden.unwrap_or_default());
+}
@@ -0,0 +1,252 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use futures_util::StreamExt;
+
+use slint::{
+  ComponentHandle,
+  Model,
+  ModelRc,
+  SharedString,
+  VecModel
+};
+
+use crate::ui::{MainWindow, ScreenUIState, WorkspaceRuleRow};
+use crate::services::Services;
+use crate::services::screen::{HyprWorkspace, WorkspaceRule};
+
+
+type RuleRows = Rc<VecModel<WorkspaceRuleRow>>;
+type WorkspacesRc = Rc<RefCell<Vec<HyprWorkspace>>>;
+
+/// Edits the `workspace = ...` rules of the screen
+/// section
+pub struct WorkspaceEditor {
+  rows: RuleRows,
+  services: Services<'static>,
+}
+
+impl WorkspaceEditor {
+  pub async fn new(
+    window: &MainWindow,
+    services: Services<'static>
+  ) -> Self {
+    let rows = RuleRows::default();
+    let workspaces = WorkspacesRc::default();
+
+    let state = window.global::<ScreenUIState>();
+    state.set_workspace_rules(ModelRc::from(rows.clone()));
+
+    reload(&window.as_weak(), &rows, &workspaces, &services)
+      .await;
+
+    let (weak, rows_a) = (window.as_weak(), rows.clone());
+    state.on_add_workspace_rule(move || {
+      rows_a.push(WorkspaceRuleRow::default());
+      set_changed(&weak);
+    });
+
+    let (weak, rows_rm) = (window.as_weak(), rows.clone());
+    state.on_remove_workspace_rule(move |index| {
+      let index = index as usize;
+      if index >= rows_rm.row_count() { return }
+
+      rows_rm.remove(index);
+      set_changed(&weak);
+    });
+
+    let (weak, rows_e, workspaces_e) = (
+      window.as_weak(), rows.clone(), workspaces.clone());
+    state.on_edit_workspace_rule(move |index, mut row| {
+      let index = index as usize;
+      if index >= rows_e.row_count() { return }
+
+      // Another selector may name an open workspace
+      row.current = current_monitor(
+        &rule_of(&row), &workspaces_e.borrow());
+
+      rows_e.set_row_data(index, row);
+      set_changed(&weak);
+    });
+
+    let (weak, rows_ap, workspaces_ap, services_ap) = (
+      window.as_weak(),
+      rows.clone(),
+      workspaces.clone(),
+      services.clone());
+    state.on_apply_workspace_rules(move || {
+      let rules: Vec<WorkspaceRule> = rows_ap
+        .iter()
+        .map(|r| rule_of(&r))
+        .collect();
+
+      let (weak, rows, workspaces, services) = (
+        weak.clone(),
+        rows_ap.clone(),
+        workspaces_ap.clone(),
+        services_ap.clone());
+
+      _ = slint::spawn_local(async move {
+        let res = services.screen()
+          .set_workspace_rules(rules).await;
+
+        match res {
+          Ok(_) => reload(
+            &weak, &rows, &workspaces, &services).await,
+          Err(e) => eprintln!(
+            "Failed to apply workspace rules: {:#?}", e)
+        }
+      });
+    });
+
+    let (weak, rows_rs, workspaces_rs, services_rs) = (
+      window.as_weak(),
+      rows.clone(),
+      workspaces.clone(),
+      services.clone());
+    state.on_reset_workspace_rules(move || {
+      let (weak, rows, workspaces, services) = (
+        weak.clone(),
+        rows_rs.clone(),
+        workspaces_rs.clone(),
+        services_rs.clone());
+
+      _ = slint::spawn_local(async move {
+        reload(&weak, &rows, &workspaces, &services).await;
+      });
+    });
+
+    let (rows_w, services_w) = (rows.clone(), services.clone());
+    _ = slint::spawn_local(async move {
+      watch_workspaces(&rows_w, &workspaces, &services_w)
+        .await;
+    });
+
+    Self {
+      rows,
+      services
+    }
+  }
+}
+
+
+/// Keeps the rules' current monitors up to date as
+/// workspaces get moved, made and destroyed
+async fn watch_workspaces(
+  rows: &RuleRows,
+  workspaces: &WorkspacesRc,
+  services: &Services<'static>
+) {
+  let screen = services.screen();
+  let proxy = match screen.proxy() {
+    Some(p) => p,
+    None => return
+  };
+
+  let mut changes = match proxy
+    .receive_workspaces_changed().await
+  {
+    Ok(s) => s,
+    Err(e) => {
+      eprintln!("Failed to watch workspaces: {:#?}", e);
+      return
+    }
+  };
+
+  while changes.next().await.is_some() {
+    let list = match screen.workspaces().await {
+      Ok(l) => l,
+      Err(e) => {
+        eprintln!("Failed to load workspaces: {:#?}", e);
+        continue
+      }
+    };
+
+    // Only where workspaces are, edits stay
+    for (i, mut row) in rows.iter().enumerate() {
+      let current = current_monitor(&rule_of(&row), &list);
+      if row.current == current { continue }
+
+      row.current = current;
+      rows.set_row_data(i, row);
+    }
+
+    *workspaces.borrow_mut() = list;
+  }
+}
+
+/// Replaces the rows with the rules the daemon has
+async fn reload(
+  weak: &slint::Weak<MainWindow>,
+  rows: &RuleRows,
+  workspaces: &WorkspacesRc,
+  services: &Services<'static>
+) {
+  let screen = services.screen();
+
+  let (rules, list) = match (
+    screen.workspace_rules().await,
+    screen.workspaces().await
+  ) {
+    (Ok(r), Ok(l)) => (r, l),
+    (Err(e), _) | (_, Err(e)) => {
+      eprintln!("Failed to load workspace rules: {:#?}", e);
+      return
+    }
+  };
+
+  rows.set_vec(rules
+    .iter()
+    .map(|r| WorkspaceRuleRow {
+      workspace: r.workspace.clone().into(),
+      monitor: r.monitor.clone().into(),
+      default: r.default,
+      persistent: r.persistent,
+      current: current_monitor(r, &list)
+    })
+    .collect::<Vec<_>>());
+
+  *workspaces.borrow_mut() = list;
+
+  let names: Vec<SharedString> = screen.monitors()
+    .read()
+    .unwrap()
+    .iter()
+    .map(|m| m.name.clone().into())
+    .collect();
+
+  if let Some(window) = weak.upgrade() {
+    let state = window.global::<ScreenUIState>();
+    state.set_monitor_names(ModelRc::new(
+      VecModel::from(names)));
+    state.set_rules_changed(false);
+  }
+}
+
+fn set_changed(weak: &slint::Weak<MainWindow>) {
+  if let Some(window) = weak.upgrade() {
+    window.global::<ScreenUIState>().set_rules_changed(true);
+  }
+}
+
+fn rule_of(row: &WorkspaceRuleRow) -> WorkspaceRule {
+  WorkspaceRule {
+    workspace: row.workspace.trim().to_string(),
+    monitor: row.monitor.to_string(),
+    default: row.default,
+    persistent: row.persistent
+  }
+}
+
+/// Monitor the rule's workspace is open on, empty when
+/// it isn't open
+fn current_monitor(
+  rule: &WorkspaceRule,
+  workspaces: &[HyprWorkspace]
+) -> SharedString {
+  workspaces
+    .iter()
+    .find(|ws| rule.targets(ws))
+    .map(|ws| ws.monitor.clone().into())
+    .unwrap_or_default()
+}
@@ -13,8 +13,8 @@ use slint::{
 };
 
 use crate::utils::matches;
-pub use zaemon::apps::DesktopEntry;
-use zaemon::apps::get_apps;
+pub use zaemon::apps::{DesktopEntry, AppMatch};
+use zaemon::apps::{get_apps, current_locale};
 
 use crate::ui::AppEntry;
 
@@ -35,7 +35,11 @@ pub type SharedAppEntriesArc = Arc<Mutex<SharedAppEntries>>;
   gen_async = true
 )]
 pub trait Apps {
-  async fn all_apps(&self) -> zbus::Result<DesktopEntries>;
+  async fn all_apps_for_locale(&self, locale: &str) ->
+    zbus::Result<DesktopEntries>;
+
+  async fn search(&self, query: &str, limit: u32) ->
+    zbus::Result<Vec<AppMatch>>;
 }
 
 
@@ -57,16 +61,20 @@ impl<'a> AppService<'a> {
   pub async fn init(&self) ->
     Result<(), Box<dyn Error>>
   {
+    let locale = current_locale();
     let all_apps = match &self.proxy {
-      Some(p) => p.all_apps().await?,
+      Some(p) => p.all_apps_for_locale(&locale).await?,
       None => get_apps().await
+        .iter()
+        .map(|e| e.localized(&locale))
+        .collect()
     };
 
     let mut entries = self.app_list.write().unwrap();
 
     *entries = all_apps
       .iter()
-      .filter(|e| !e.terminal && !e.no_display)
+      .filter(|e| !e.terminal && e.should_show())
       .cloned()
       .collect();
 
@@ -97,6 +105,28 @@ impl<'a> AppService<'a> {
     self.app_map.clone()
   }
 
+  /// Desktop IDs of the apps matching `query`, best first,
+  /// or `None` when the daemon can't be reached
+  pub async fn search(&self, query: &str) ->
+    Option<Vec<String>>
+  {
+    let hits = match &self.proxy {
+      Some(p) => p.search(query, 0).await,
+      None => return None
+    };
+
+    match hits {
+      Ok(hits) => Some(hits
+        .into_iter()
+        .map(|h| h.desktop_id)
+        .collect()),
+      Err(e) => {
+        eprintln!("Failed to search apps: {e}");
+        None
+      }
+    }
+  }
+
   pub fn filter_entries(&self, query: String) {
     let entries = self.app_list.clone();
 
@@ -3,31 +3,52 @@ use std::error::Error;
 
 pub mod apps;
 pub mod tablet;
+pub mod screen;
+pub mod nightlight;
+pub mod wallpaper;
 
 use apps::AppService;
 use tablet::TabletService;
+use screen::ScreenService;
+use nightlight::NightLightService;
+use wallpaper::WallpaperService;
 
 
 #[derive(Clone)]
 pub struct Services<'a> {
   connection: zbus::Connection,
   tablet: Arc<TabletService<'a>>,
-  apps: Arc<AppService<'a>>
+  apps: Arc<AppService<'a>>,
+  screen: Arc<ScreenService<'a>>,
+  night_light: Arc<NightLightService<'a>>,
+  wallpaper: Arc<WallpaperService<'a>>
 }
 
 impl<'a> Services<'a> {
   pub async fn new() -> Result<Self, Box<dyn Error>> {
     let conn = zbus::Connection::session().await?;
 
-    let (tablet_srv, apps_srv) = tokio::join!(
+    let (
+      tablet_srv,
+      apps_srv,
+      screen_srv,
+      night_light_srv,
+      wallpaper_srv
+    ) = tokio::join!(
       TabletService::new(&conn),
-      AppService::new(&conn)
+      AppService::new(&conn),
+      ScreenService::new(&conn),
+      NightLightService::new(&conn),
+      WallpaperService::new(&conn)
     );
 
     Ok(Self {
       connection: conn,
       tablet: Arc::new(tablet_srv),
-      apps: Arc::new(apps_srv)
+      apps: Arc::new(apps_srv),
+      screen: Arc::new(screen_srv),
+      night_light: Arc::new(night_light_srv),
+      wallpaper: Arc::new(wallpaper_srv)
     })
   }
 
@@ -43,6 +64,12 @@ impl<'a> Services<'a> {
     self.apps.init().await
   }
 
+  pub async fn init_screen(&self) ->
+    Result<(), Box<dyn Error>>
+  {
+    self.screen.init().await
+  }
+
   pub fn tablet(&self) -> Arc<TabletService<'a>> {
     self.tablet.clone()
   }
@@ -50,4 +77,16 @@ impl<'a> Services<'a> {
   pub fn apps(&self) -> Arc<AppService<'a>> {
     self.apps.clone()
   }
+
+  pub fn screen(&self) -> Arc<ScreenService<'a>> {
+    self.screen.clone()
+  }
+
+  pub fn night_light(&self) -> Arc<NightLightService<'a>> {
+    self.night_light.clone()
+  }
+
+  pub fn wallpaper(&self) -> Arc<WallpaperService<'a>> {
+    self.wallpaper.clone()
+  }
 }
@@ -0,0 +1,85 @@
+use std::error::Error;
+
+use zbus::{Connection, proxy};
+pub use zaemon::nightlight::{
+  NightLightConfig,
+  ScheduleMode
+};
+
+
+#[proxy(
+  interface = "org.hypr.Hyprmaster.NightLight",
+  default_service = "org.hypr.Hyprmaster",
+  default_path = "/nightlight",
+  gen_async = true
+)]
+pub trait NightLight {
+  fn config(&self) -> zbus::Result<NightLightConfig>;
+
+  fn set_config(
+    &self,
+    config: NightLightConfig
+  ) -> zbus::Result<()>;
+
+  #[zbus(property)]
+  fn temperature(&self) -> zbus::Result<u32>;
+
+  #[zbus(property)]
+  fn active(&self) -> zbus::Result<bool>;
+
+  #[zbus(property)]
+  fn overridden(&self) -> zbus::Result<bool>;
+
+  #[zbus(property)]
+  fn set_overridden(&self, value: bool) -> zbus::Result<()>;
+}
+
+
+pub struct NightLightService<'a> {
+  proxy: Option<NightLightProxy<'a>>,
+}
+
+impl<'a> NightLightService<'a> {
+  pub async fn new(conn: &Connection) -> Self {
+    Self {
+      proxy: NightLightProxy::new(conn).await.ok()
+    }
+  }
+
+  pub fn proxy(&self) -> Option<&NightLightProxy<'a>> {
+    self.proxy.as_ref()
+  }
+
+  pub async fn config(&self) ->
+    Result<NightLightConfig, Box<dyn Error>>
+  {
+    Ok(self.daemon()?.config().await?)
+  }
+
+  pub async fn set_enabled(&self, enabled: bool) ->
+    Result<(), Box<dyn Error>>
+  {
+    let daemon = self.daemon()?;
+
+    let mut config = daemon.config().await?;
+    config.enabled = enabled;
+
+    daemon.set_config(config).await?;
+    Ok(())
+  }
+
+  pub async fn set_overridden(&self, value: bool) ->
+    Result<(), Box<dyn Error>>
+  {
+    self.daemon()?.set_overridden(value).await?;
+    Ok(())
+  }
+
+  fn daemon(&self) ->
+    Result<&NightLightProxy<'a>, Box<dyn Error>>
+  {
+    self.proxy
+      .as_ref()
+      .ok_or("Daemon unavailable".into())
+  }
+}
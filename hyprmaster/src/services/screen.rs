@@ -0,0 +1,219 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::{Arc, RwLock};
+
+use zbus::{Connection, proxy};
+pub use zaemon::screen::{
+  HyprMonitor,
+  HyprWorkspace,
+  MonitorConfig,
+  MonitorMode,
+  ScalingChange,
+  WorkspaceRule
+};
+
+
+#[proxy(
+  interface = "org.hypr.Hyprmaster.Screen",
+  default_service = "org.hypr.Hyprmaster",
+  default_path = "/screen",
+  gen_async = true
+)]
+pub trait Screen {
+  #[zbus(property)]
+  fn include_path(&self) -> zbus::Result<String>;
+
+  fn monitors(&self) -> zbus::Result<Vec<HyprMonitor>>;
+
+  #[zbus(property)]
+  fn pending(&self) -> zbus::Result<bool>;
+
+  fn apply(
+    &self,
+    configs: Vec<MonitorConfig>,
+    timeout: u32
+  ) -> zbus::Result<()>;
+
+  fn confirm(&self) -> zbus::Result<()>;
+
+  fn revert(&self) -> zbus::Result<()>;
+
+  #[zbus(property)]
+  fn profiles(&self) -> zbus::Result<Vec<String>>;
+
+  #[zbus(property)]
+  fn active_profile(&self) -> zbus::Result<String>;
+
+  fn save_profile(&self, name: &str) -> zbus::Result<()>;
+
+  fn remove_profile(&self, name: &str) -> zbus::Result<()>;
+
+  fn apply_profile(&self, name: &str) -> zbus::Result<()>;
+
+  fn preview_scaling(
+    &self,
+    monitor: &str,
+    scale: f64
+  ) -> zbus::Result<Vec<ScalingChange>>;
+
+  fn apply_scaling(
+    &self,
+    monitor: &str,
+    scale: f64,
+    timeout: u32
+  ) -> zbus::Result<()>;
+
+  #[zbus(property)]
+  fn brightness(&self) -> zbus::Result<HashMap<String, f64>>;
+
+  fn set_brightness(
+    &self,
+    monitor: &str,
+    percent: f64
+  ) -> zbus::Result<()>;
+
+  fn workspaces(&self) -> zbus::Result<Vec<HyprWorkspace>>;
+
+  fn workspace_rules(&self) ->
+    zbus::Result<Vec<WorkspaceRule>>;
+
+  fn set_workspace_rules(
+    &self,
+    rules: Vec<WorkspaceRule>
+  ) -> zbus::Result<()>;
+
+  #[zbus(signal)]
+  fn workspaces_changed(&self) -> zbus::Result<()>;
+
+  #[zbus(signal)]
+  fn monitors_changed(&self) -> zbus::Result<()>;
+
+  #[zbus(signal)]
+  fn confirmation_requested(&self, timeout: u32) ->
+    zbus::Result<()>;
+
+  #[zbus(signal)]
+  fn reverted(&self) -> zbus::Result<()>;
+}
+
+
+pub type MonitorsArc = Arc<RwLock<Vec<HyprMonitor>>>;
+
+pub struct ScreenService<'a> {
+  proxy: Option<ScreenProxy<'a>>,
+  monitors: MonitorsArc,
+}
+
+impl<'a> ScreenService<'a> {
+  pub async fn new(conn: &Connection) -> Self {
+    Self {
+      proxy: ScreenProxy::new(conn).await.ok(),
+      monitors: MonitorsArc::default()
+    }
+  }
+
+  pub async fn init(&self) -> Result<(), Box<dyn Error>> {
+    let proxy = match &self.proxy {
+      Some(p) => p,
+      None => return Ok(())
+    };
+
+    let monitors = proxy.monitors().await?;
+    *self.monitors.write().unwrap() = monitors;
+
+    Ok(())
+  }
+
+  pub fn monitors(&self) -> MonitorsArc {
+    self.monitors.clone()
+  }
+
+  pub fn proxy(&self) -> Option<&ScreenProxy<'a>> {
+    self.proxy.as_ref()
+  }
+
+  /// Applies `configs`, to be reverted by the daemon
+  /// unless confirmed within `timeout` seconds
+  pub async fn apply(
+    &self,
+    configs: Vec<MonitorConfig>,
+    timeout: u32
+  ) -> Result<(), Box<dyn Error>> {
+    self.daemon()?.apply(configs, timeout).await?;
+    self.init().await
+  }
+
+  pub async fn confirm(&self) -> Result<(), Box<dyn Error>> {
+    self.daemon()?.confirm().await?;
+    Ok(())
+  }
+
+  pub async fn revert(&self) -> Result<(), Box<dyn Error>> {
+    self.daemon()?.revert().await?;
+    self.init().await
+  }
+
+  /// Settings `apply_scaling` would change
+  pub async fn preview_scaling(
+    &self,
+    monitor: &str,
+    scale: f64
+  ) -> Result<Vec<ScalingChange>, Box<dyn Error>> {
+    Ok(self.daemon()?.preview_scaling(monitor, scale).await?)
+  }
+
+  /// Scales `monitor` and everything else along with it,
+  /// the monitor being reverted like with `apply`
+  pub async fn apply_scaling(
+    &self,
+    monitor: &str,
+    scale: f64,
+    timeout: u32
+  ) -> Result<(), Box<dyn Error>> {
+    self.daemon()?
+      .apply_scaling(monitor, scale, timeout).await?;
+    self.init().await
+  }
+
+  /// Brightness by monitor, from 0 to 100
+  pub async fn brightness(&self) ->
+    Result<HashMap<String, f64>, Box<dyn Error>>
+  {
+    Ok(self.daemon()?.brightness().await?)
+  }
+
+  pub async fn set_brightness(
+    &self,
+    monitor: &str,
+    percent: f64
+  ) -> Result<(), Box<dyn Error>> {
+    self.daemon()?.set_brightness(monitor, percent).await?;
+    Ok(())
+  }
+
+  pub async fn workspaces(&self) ->
+    Result<Vec<HyprWorkspace>, Box<dyn Error>>
+  {
+    Ok(self.daemon()?.workspaces().await?)
+  }
+
+  pub async fn workspace_rules(&self) ->
+    Result<Vec<WorkspaceRule>, Box<dyn Error>>
+  {
+    Ok(self.daemon()?.workspace_rules().await?)
+  }
+
+  pub async fn set_workspace_rules(
+    &self,
+    rules: Vec<WorkspaceRule>
+  ) -> Result<(), Box<dyn Error>> {
+    self.daemon()?.set_workspace_rules(rules).await?;
+    Ok(())
+  }
+
+  fn daemon(&self) -> Result<&ScreenProxy<'a>, Box<dyn Error>> {
+    self.proxy
+      .as_ref()
+      .ok_or("Daemon unavailable".into())
+  }
+}
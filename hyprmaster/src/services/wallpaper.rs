@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+use std::error::Error;
+
+use zbus::{Connection, proxy};
+pub use zaemon::wallpaper::{
+  ImageSource,
+  RotationConfig,
+  WallpaperMode
+};
+
+
+#[proxy(
+  interface = "org.hypr.Hyprmaster.Wallpaper",
+  default_service = "org.hypr.Hyprmaster",
+  default_path = "/wallpaper",
+  gen_async = true
+)]
+pub trait Wallpaper {
+  #[zbus(property)]
+  fn wallpapers(&self) -> zbus::Result<HashMap<String, String>>;
+
+  fn set_wallpaper(
+    &self,
+    monitor: &str,
+    path: &str,
+    mode: WallpaperMode
+  ) -> zbus::Result<()>;
+
+  fn preview_wallpaper(
+    &self,
+    monitor: &str,
+    path: &str,
+    mode: WallpaperMode
+  ) -> zbus::Result<()>;
+
+  fn end_preview(&self, monitor: &str) -> zbus::Result<()>;
+
+  #[zbus(property)]
+  fn folders(&self) -> zbus::Result<Vec<String>>;
+
+  #[zbus(property)]
+  fn set_folders(&self, folders: Vec<String>) ->
+    zbus::Result<()>;
+
+  fn rotations(&self) -> zbus::Result<Vec<RotationConfig>>;
+
+  fn set_rotation(&self, config: RotationConfig) ->
+    zbus::Result<()>;
+
+  fn remove_rotation(&self, monitor: &str) -> zbus::Result<()>;
+
+  fn next(&self, monitor: &str) -> zbus::Result<()>;
+
+  fn previous(&self, monitor: &str) -> zbus::Result<()>;
+
+  fn pause(&self, monitor: &str, paused: bool) ->
+    zbus::Result<()>;
+
+  #[zbus(property)]
+  fn paused(&self) -> zbus::Result<Vec<String>>;
+
+  fn palette(&self, monitor: &str) -> zbus::Result<Vec<String>>;
+
+  fn accent(&self, monitor: &str) -> zbus::Result<String>;
+
+  #[zbus(property)]
+  fn themed_borders(&self) -> zbus::Result<bool>;
+
+  #[zbus(property)]
+  fn set_themed_borders(&self, themed: bool) ->
+    zbus::Result<()>;
+}
+
+
+pub struct WallpaperService<'a> {
+  proxy: Option<WallpaperProxy<'a>>,
+}
+
+impl<'a> WallpaperService<'a> {
+  pub async fn new(conn: &Connection) -> Self {
+    Self {
+      proxy: WallpaperProxy::new(conn).await.ok()
+    }
+  }
+
+  pub fn proxy(&self) -> Option<&WallpaperProxy<'a>> {
+    self.proxy.as_ref()
+  }
+
+  /// Image path by monitor, empty for monitors without
+  /// their own
+  pub async fn wallpapers(&self) ->
+    Result<HashMap<String, String>, Box<dyn Error>>
+  {
+    Ok(self.daemon()?.wallpapers().await?)
+  }
+
+  pub async fn set_wallpaper(
+    &self,
+    monitor: &str,
+    path: &str,
+    mode: WallpaperMode
+  ) -> Result<(), Box<dyn Error>> {
+    self.daemon()?.set_wallpaper(monitor, path, mode).await?;
+    Ok(())
+  }
+
+  /// Shows `path` on `monitor` until `end_preview`, without
+  /// the daemon remembering it
+  pub async fn preview_wallpaper(
+    &self,
+    monitor: &str,
+    path: &str,
+    mode: WallpaperMode
+  ) -> Result<(), Box<dyn Error>> {
+    self.daemon()?
+      .preview_wallpaper(monitor, path, mode).await?;
+    Ok(())
+  }
+
+  pub async fn end_preview(&self, monitor: &str) ->
+    Result<(), Box<dyn Error>>
+  {
+    self.daemon()?.end_preview(monitor).await?;
+    Ok(())
+  }
+
+  pub async fn folders(&self) ->
+    Result<Vec<String>, Box<dyn Error>>
+  {
+    Ok(self.daemon()?.folders().await?)
+  }
+
+  pub async fn set_folders(&self, folders: Vec<String>) ->
+    Result<(), Box<dyn Error>>
+  {
+    self.daemon()?.set_folders(folders).await?;
+    Ok(())
+  }
+
+  pub async fn rotations(&self) ->
+    Result<Vec<RotationConfig>, Box<dyn Error>>
+  {
+    Ok(self.daemon()?.rotations().await?)
+  }
+
+  pub async fn set_rotation(&self, config: RotationConfig) ->
+    Result<(), Box<dyn Error>>
+  {
+    self.daemon()?.set_rotation(config).await?;
+    Ok(())
+  }
+
+  pub async fn remove_rotation(&self, monitor: &str) ->
+    Result<(), Box<dyn Error>>
+  {
+    self.daemon()?.remove_rotation(monitor).await?;
+    Ok(())
+  }
+
+  pub async fn next(&self, monitor: &str) ->
+    Result<(), Box<dyn Error>>
+  {
+    self.daemon()?.next(monitor).await?;
+    Ok(())
+  }
+
+  pub async fn previous(&self, monitor: &str) ->
+    Result<(), Box<dyn Error>>
+  {
+    self.daemon()?.previous(monitor).await?;
+    Ok(())
+  }
+
+  /// Pauses or resumes `monitor`'s rotation, every one
+  /// when empty
+  pub async fn pause(&self, monitor: &str, paused: bool) ->
+    Result<(), Box<dyn Error>>
+  {
+    self.daemon()?.pause(monitor, paused).await?;
+    Ok(())
+  }
+
+  /// Monitors whose rotation is paused
+  pub async fn paused(&self) ->
+    Result<Vec<String>, Box<dyn Error>>
+  {
+    Ok(self.daemon()?.paused().await?)
+  }
+
+  /// Dominant colors of `monitor`'s wallpaper as
+  /// `#rrggbb`, the most common first
+  pub async fn palette(&self, monitor: &str) ->
+    Result<Vec<String>, Box<dyn Error>>
+  {
+    Ok(self.daemon()?.palette(monitor).await?)
+  }
+
+  pub async fn accent(&self, monitor: &str) ->
+    Result<String, Box<dyn Error>>
+  {
+    Ok(self.daemon()?.accent(monitor).await?)
+  }
+
+  /// Whether window borders take the wallpaper's colors
+  pub async fn themed_borders(&self) ->
+    Result<bool, Box<dyn Error>>
+  {
+    Ok(self.daemon()?.themed_borders().await?)
+  }
+
+  pub async fn set_themed_borders(&self, themed: bool) ->
+    Result<(), Box<dyn Error>>
+  {
+    self.daemon()?.set_themed_borders(themed).await?;
+    Ok(())
+  }
+
+  fn daemon(&self) ->
+    Result<&WallpaperProxy<'a>, Box<dyn Error>>
+  {
+    self.proxy
+      .as_ref()
+      .ok_or("Daemon unavailable".into())
+  }
+}
@@ -1,3 +1,6 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
 use slint::{
   Image,
   Model,
@@ -20,7 +23,10 @@ use crate::services::{
 pub struct AppEntries {
   entries: DesktopEntriesArc,
   services: Services<'static>,
-  notify: ModelNotify
+  notify: ModelNotify,
+  /// Bumped on every query, so a slow search doesn't
+  /// override a newer one
+  generation: Cell<u64>,
 }
 
 impl AppEntries {
@@ -28,21 +34,40 @@ impl AppEntries {
     Self {
       entries: services.apps().app_list(),
       notify: ModelNotify::default(),
+      generation: Cell::new(0),
       services,
     }
   }
 
-  pub fn filter_entries(&self, query: &str) {
+  /// Fades the entries the daemon's search doesn't match,
+  /// or by name alone when it can't be reached
+  pub fn filter_entries(self: &Rc<Self>, query: &str) {
+    let generation = self.generation.get() + 1;
+    self.generation.set(generation);
+
+    let (this, query) = (self.clone(), query.to_string());
+
+    _ = slint::spawn_local(async move {
+      let hits = match query.trim().is_empty() {
+        true => None,
+        false => this.services.apps().search(&query).await
+      };
+
+      if this.generation.get() == generation {
+        this.fade(&query, hits);
+      }
+    });
+  }
+
+  fn fade(&self, query: &str, hits: Option<Vec<String>>) {
     let mut entries = self.entries.write().unwrap();
 
     for entry in entries.iter_mut() {
-      if query.trim() == "" && entry.fade {
-        entry.fade = false;
-      } else if !matches(entry.name.as_str(), &query) {
-        entry.fade = true;
-      } else {
-        entry.fade = false;
-      }
+      entry.fade = match (query.trim(), &hits) {
+        ("", _) => false,
+        (_, Some(ids)) => !ids.contains(&entry.desktop_id),
+        (_, None) => !matches(entry.name.as_str(), query)
+      };
     }
 
     self.notify.reset();
@@ -0,0 +1,499 @@
+use std::cell::{Cell, RefCell};
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use futures_util::StreamExt;
+
+use slint::{
+  Color,
+  ComponentHandle,
+  Image,
+  Model,
+  ModelRc,
+  Rgba8Pixel,
+  SharedPixelBuffer,
+  SharedString,
+  VecModel
+};
+
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+
+use crate::ui::{
+  GalleryImage,
+  MainWindow,
+  Theme,
+  WallpaperUIState
+};
+use crate::services::Services;
+use crate::services::wallpaper::WallpaperMode;
+
+use super::gallery::{self, FolderWatcher};
+use super::thumbnails;
+
+
+type GalleryRc = Rc<Gallery>;
+
+/// What the gallery's callbacks share
+struct Gallery {
+  window: slint::Weak<MainWindow>,
+  images: Rc<VecModel<GalleryImage>>,
+  services: Services<'static>,
+  folders: RefCell<Vec<String>>,
+  /// Bumped on every scan, stopping thumbnails of the
+  /// last one
+  scan: Cell<u32>,
+  watcher: RefCell<Option<FolderWatcher>>,
+  changes: UnboundedSender<()>,
+}
+
+/// Browses the wallpaper folders and previews images on
+/// a monitor
+pub struct WallpaperController {
+  gallery: GalleryRc,
+}
+
+impl WallpaperController {
+  pub async fn new(
+    window: &MainWindow,
+    services: Services<'static>
+  ) -> Self {
+    let images = Rc::new(VecModel::<GalleryImage>::default());
+    let (changes, mut changes_rx) = unbounded_channel();
+
+    let state = window.global::<WallpaperUIState>();
+    state.set_images(ModelRc::from(images.clone()));
+
+    let monitors = services.screen().monitors();
+    let monitors = monitors.read().unwrap().clone();
+
+    let names: Vec<SharedString> = monitors
+      .iter()
+      .map(|m| m.name.clone().into())
+      .collect();
+    state.set_monitor_names(ModelRc::new(
+      VecModel::from(names)));
+
+    if let Some(focused) = monitors.iter().find(|m| m.focused) {
+      state.set_monitor(focused.name.clone().into());
+    }
+
+    let gallery = GalleryRc::new(Gallery {
+      window: window.as_weak(),
+      images,
+      services,
+      folders: RefCell::default(),
+      scan: Cell::new(0),
+      watcher: RefCell::default(),
+      changes
+    });
+
+    gallery.reload().await;
+    gallery.load_palette().await;
+
+    let themed = gallery.services.wallpaper()
+      .themed_borders().await
+      .unwrap_or_default();
+    state.set_themed_borders(themed);
+
+    let gallery_m = gallery.clone();
+    state.on_select_monitor(move |name| {
+      let gallery = gallery_m.clone();
+
+      _ = slint::spawn_local(async move {
+        // A preview stays with the monitor it's on
+        gallery.end_preview().await;
+
+        if let Some(window) = gallery.window.upgrade() {
+          window.global::<WallpaperUIState>().set_monitor(name);
+        }
+
+        gallery.mark_current().await;
+        gallery.load_palette().await;
+      });
+    });
+
+    let gallery_p = gallery.clone();
+    state.on_preview(move |path| {
+      let gallery = gallery_p.clone();
+
+      _ = slint::spawn_local(async move {
+        let (monitor, mode) = match gallery.target() {
+          Some(t) => t,
+          None => return
+        };
+
+        let res = gallery.services.wallpaper()
+          .preview_wallpaper(&monitor, &path, mode).await;
+
+        match res {
+          Ok(_) => gallery.set_previewing(path),
+          Err(e) => {
+            eprintln!("Failed to preview wallpaper: {:#?}", e);
+            return
+          }
+        }
+
+        gallery.load_palette().await;
+      });
+    });
+
+    let gallery_k = gallery.clone();
+    state.on_keep_preview(move || {
+      let gallery = gallery_k.clone();
+
+      _ = slint::spawn_local(async move {
+        let (monitor, mode) = match gallery.target() {
+          Some(t) => t,
+          None => return
+        };
+
+        let path = gallery.previewing();
+        let res = gallery.services.wallpaper()
+          .set_wallpaper(&monitor, &path, mode).await;
+
+        match res {
+          Ok(_) => gallery.set_previewing("".into()),
+          Err(e) => eprintln!(
+            "Failed to set wallpaper: {:#?}", e)
+        }
+      });
+    });
+
+    let gallery_c = gallery.clone();
+    state.on_cancel_preview(move || {
+      let gallery = gallery_c.clone();
+
+      _ = slint::spawn_local(async move {
+        gallery.end_preview().await;
+        gallery.load_palette().await;
+      });
+    });
+
+    let gallery_t = gallery.clone();
+    state.on_toggle_themed_borders(move || {
+      let gallery = gallery_t.clone();
+
+      _ = slint::spawn_local(async move {
+        let window = match gallery.window.upgrade() {
+          Some(w) => w,
+          None => return
+        };
+
+        let state = window.global::<WallpaperUIState>();
+        let themed = !state.get_themed_borders();
+
+        let res = gallery.services.wallpaper()
+          .set_themed_borders(themed).await;
+
+        match res {
+          Ok(_) => state.set_themed_borders(themed),
+          Err(e) => eprintln!(
+            "Failed to theme borders: {:#?}", e)
+        }
+      });
+    });
+
+    let gallery_a = gallery.clone();
+    state.on_add_folder(move |path| {
+      let path = path.trim().to_string();
+      if path.is_empty() { return }
+
+      let mut folders = gallery_a.folders.borrow().clone();
+      if folders.contains(&path) { return }
+      folders.push(path);
+
+      let gallery = gallery_a.clone();
+      _ = slint::spawn_local(async move {
+        gallery.set_folders(folders).await;
+      });
+    });
+
+    let gallery_r = gallery.clone();
+    state.on_remove_folder(move |index| {
+      let mut folders = gallery_r.folders.borrow().clone();
+      if index < 0 || index as usize >= folders.len() {
+        return
+      }
+      folders.remove(index as usize);
+
+      let gallery = gallery_r.clone();
+      _ = slint::spawn_local(async move {
+        gallery.set_folders(folders).await;
+      });
+    });
+
+    let gallery_w = gallery.clone();
+    _ = slint::spawn_local(async move {
+      while changes_rx.recv().await.is_some() {
+        gallery_w.rescan().await;
+      }
+    });
+
+    let gallery_c = gallery.clone();
+    _ = slint::spawn_local(async move {
+      watch_wallpapers(&gallery_c).await;
+    });
+
+    Self {
+      gallery
+    }
+  }
+}
+
+
+impl Gallery {
+  /// Fetches the folders, watches them and scans them
+  async fn reload(self: &Rc<Self>) {
+    let folders = match self.services.wallpaper()
+      .folders().await
+    {
+      Ok(f) => f,
+      Err(e) => {
+        eprintln!("Failed to load wallpaper folders: {:#?}", e);
+        return
+      }
+    };
+
+    let watcher = gallery::watch(&folders, self.changes.clone());
+    match watcher {
+      Ok(w) => *self.watcher.borrow_mut() = Some(w),
+      Err(e) => eprintln!("Failed to watch folders: {:#?}", e)
+    }
+
+    if let Some(window) = self.window.upgrade() {
+      let list: Vec<SharedString> = folders
+        .iter()
+        .map(|f| f.into())
+        .collect();
+
+      window.global::<WallpaperUIState>().set_folders(
+        ModelRc::new(VecModel::from(list)));
+    }
+
+    *self.folders.borrow_mut() = folders;
+    self.rescan().await;
+  }
+
+  async fn set_folders(self: &Rc<Self>, folders: Vec<String>) {
+    let res = self.services.wallpaper()
+      .set_folders(folders).await;
+
+    if let Err(e) = res {
+      eprintln!("Failed to set wallpaper folders: {:#?}", e);
+    }
+
+    self.reload().await;
+  }
+
+  /// Lists the images again, with their thumbnails coming
+  /// in one by one
+  async fn rescan(self: &Rc<Self>) {
+    let scan = self.scan.get() + 1;
+    self.scan.set(scan);
+
+    let folders = self.folders.borrow().clone();
+    let paths = tokio::task::spawn_blocking(
+      move || gallery::scan(&folders)).await
+      .unwrap_or_default();
+
+    if self.scan.get() != scan { return }
+
+    let rows: Vec<GalleryImage> = paths
+      .iter()
+      .map(|p| GalleryImage {
+        path: p.to_string_lossy().to_string().into(),
+        name: p
+          .file_name()
+          .map(|n| n.to_string_lossy().to_string())
+          .unwrap_or_default()
+          .into(),
+        thumbnail: Image::default(),
+        current: false
+      })
+      .collect();
+
+    self.images.set_vec(rows);
+    self.mark_current().await;
+
+    let gallery = self.clone();
+    _ = slint::spawn_local(async move {
+      gallery.load_thumbnails(scan, paths).await;
+    });
+  }
+
+  async fn load_thumbnails(&self, scan: u32, paths: Vec<PathBuf>) {
+    for path in paths {
+      let source = path.clone();
+      let res = tokio::task::spawn_blocking(
+        move || thumbnails::thumbnail(&source)).await;
+
+      // Another scan took over
+      if self.scan.get() != scan { return }
+
+      let thumb = match res {
+        Ok(Ok(t)) => t,
+        Ok(Err(e)) => {
+          eprintln!(
+            "Failed to make thumbnail of {}: {e}",
+            path.display());
+          continue
+        },
+        Err(_) => continue
+      };
+
+      let buffer = SharedPixelBuffer::<Rgba8Pixel>
+        ::clone_from_slice(
+          thumb.as_raw(), thumb.width(), thumb.height());
+
+      let key: SharedString = path.to_string_lossy()
+        .to_string()
+        .into();
+      let row = self.images
+        .iter()
+        .position(|i| i.path == key);
+
+      if let Some(i) = row {
+        let mut image = self.images.row_data(i).unwrap();
+        image.thumbnail = Image::from_rgba8(buffer);
+        self.images.set_row_data(i, image);
+      }
+    }
+  }
+
+  /// Marks the images shown on the picked monitor
+  async fn mark_current(&self) {
+    let wallpapers = self.services.wallpaper()
+      .wallpapers().await
+      .unwrap_or_default();
+
+    let monitor = match self.window.upgrade() {
+      Some(w) => w.global::<WallpaperUIState>().get_monitor(),
+      None => return
+    };
+
+    let current: SharedString = wallpapers
+      .get(monitor.as_str())
+      .or(wallpapers.get(""))
+      .cloned()
+      .unwrap_or_default()
+      .into();
+
+    for i in 0..self.images.row_count() {
+      let mut image = self.images.row_data(i).unwrap();
+      let is_current = image.path == current;
+
+      if image.current != is_current {
+        image.current = is_current;
+        self.images.set_row_data(i, image);
+      }
+    }
+  }
+
+  /// Shows the colors of the picked monitor's wallpaper,
+  /// and gives the UI its accent
+  async fn load_palette(&self) {
+    let monitor = match self.window.upgrade() {
+      Some(w) => w.global::<WallpaperUIState>().get_monitor(),
+      None => return
+    };
+
+    let wallpaper = self.services.wallpaper();
+
+    // No wallpaper yet leaves an empty palette
+    let palette = wallpaper.palette(&monitor).await
+      .unwrap_or_default();
+    let accent = wallpaper.accent(&monitor).await.ok();
+
+    let window = match self.window.upgrade() {
+      Some(w) => w,
+      None => return
+    };
+
+    let colors: Vec<Color> = palette
+      .iter()
+      .filter_map(|c| parse_color(c))
+      .collect();
+    window.global::<WallpaperUIState>().set_palette(
+      ModelRc::new(VecModel::from(colors)));
+
+    if let Some(color) = accent.and_then(|c| parse_color(&c)) {
+      window.global::<Theme>().set_accent(color);
+    }
+  }
+
+  /// Monitor and mode picked for previews
+  fn target(&self) -> Option<(String, WallpaperMode)> {
+    let window = self.window.upgrade()?;
+    let state = window.global::<WallpaperUIState>();
+
+    let monitor = state.get_monitor().to_string();
+    if monitor.is_empty() { return None }
+
+    let mode = match state.get_mode().as_str() {
+      "contain" => WallpaperMode::Contain,
+      "tile" => WallpaperMode::Tile,
+      _ => WallpaperMode::Cover
+    };
+
+    Some((monitor, mode))
+  }
+
+  fn previewing(&self) -> String {
+    self.window
+      .upgrade()
+      .map(|w| w.global::<WallpaperUIState>()
+        .get_previewing()
+        .to_string())
+      .unwrap_or_default()
+  }
+
+  fn set_previewing(&self, path: SharedString) {
+    if let Some(window) = self.window.upgrade() {
+      window.global::<WallpaperUIState>()
+        .set_previewing(path);
+    }
+  }
+
+  /// Puts the kept wallpaper back on the picked monitor
+  async fn end_preview(&self) {
+    if self.previewing().is_empty() { return }
+
+    if let Some((monitor, _)) = self.target() {
+      let res = self.services.wallpaper()
+        .end_preview(&monitor).await;
+
+      if let Err(e) = res {
+        eprintln!("Failed to end preview: {:#?}", e);
+      }
+    }
+
+    self.set_previewing("".into());
+  }
+}
+
+
+/// Follows wallpapers set by the daemon or other clients
+async fn watch_wallpapers(gallery: &GalleryRc) {
+  let wallpaper = gallery.services.wallpaper();
+  let proxy = match wallpaper.proxy() {
+    Some(p) => p,
+    None => return
+  };
+
+  let mut changes = proxy.receive_wallpapers_changed().await;
+
+  while changes.next().await.is_some() {
+    gallery.mark_current().await;
+    gallery.load_palette().await;
+  }
+}
+
+/// `#rrggbb` as a color
+fn parse_color(hex: &str) -> Option<Color> {
+  let hex = hex.strip_prefix('#')?;
+  if hex.len() != 6 { return None }
+
+  let rgb = u32::from_str_radix(hex, 16).ok()?;
+  Some(Color::from_rgb_u8(
+    (rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8))
+}
@@ -0,0 +1,87 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use notify_debouncer_full::{
+  new_debouncer,
+  DebounceEventResult,
+  Debouncer,
+  RecommendedCache,
+  notify::{
+    EventKind as EvKind,
+    RecommendedWatcher,
+    RecursiveMode,
+    event::ModifyKind
+  }
+};
+
+use tokio::sync::mpsc::UnboundedSender;
+use walkdir::WalkDir;
+
+
+/// Formats both hyprpaper and the thumbnailer read
+const IMAGE_EXTENSIONS: [&str; 4] = ["png", "jpg", "jpeg", "webp"];
+
+pub type FolderWatcher =
+  Debouncer<RecommendedWatcher, RecommendedCache>;
+
+
+/// Images in `folders` and below, sorted by path
+pub fn scan(folders: &[String]) -> Vec<PathBuf> {
+  let mut images: Vec<PathBuf> = folders
+    .iter()
+    .flat_map(|f| WalkDir::new(f).follow_links(true))
+    .flatten()
+    .filter(|e| e.file_type().is_file())
+    .map(|e| e.into_path())
+    .filter(|p| is_image(p))
+    .collect();
+
+  images.sort();
+  images.dedup();
+  images
+}
+
+/// Sends on `changes` once changes to images in `folders`
+/// settle
+pub fn watch(
+  folders: &[String],
+  changes: UnboundedSender<()>
+) -> notify_debouncer_full::notify::Result<FolderWatcher> {
+  let timeframe = Duration::from_secs(1);
+
+  let mut debouncer = new_debouncer(
+    timeframe,
+    None,
+    move |res: DebounceEventResult| {
+      // Reading images for thumbnails touches metadata
+      let changed = res.is_ok_and(|events| events
+        .iter()
+        .any(|db| match db.event.kind {
+          EvKind::Modify(ModifyKind::Metadata(_)) => false,
+          EvKind::Create(_) |
+          EvKind::Modify(_) |
+          EvKind::Remove(_) => true,
+          _ => false
+        }));
+
+      if changed {
+        _ = changes.send(());
+      }
+    })?;
+
+  for folder in folders {
+    // Missing folders just show nothing
+    _ = debouncer.watch(
+      Path::new(folder), RecursiveMode::Recursive);
+  }
+
+  Ok(debouncer)
+}
+
+fn is_image(path: &Path) -> bool {
+  path
+    .extension()
+    .and_then(|e| e.to_str())
+    .is_some_and(|e| IMAGE_EXTENSIONS
+      .contains(&e.to_lowercase().as_str()))
+}
@@ -0,0 +1,5 @@
+mod thumbnails;
+mod gallery;
+mod controller;
+
+pub use controller::*;
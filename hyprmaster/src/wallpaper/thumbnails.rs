@@ -0,0 +1,153 @@
+/* -
+ * -> Thumbnails after the freedesktop thumbnail spec, kept
+ *    in $XDG_CACHE_HOME/thumbnails with file managers' ones
+ * -
+*/
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use std::env::var;
+
+use image::RgbaImage;
+
+
+/// Edge of the spec's `large` size, sharp on gallery tiles
+const LARGE_SIZE: u32 = 256;
+
+
+/// Thumbnail of the image at `path`, from the cache while
+/// the image hasn't changed, else made and cached
+pub fn thumbnail(path: &Path) -> io::Result<RgbaImage> {
+  let uri = file_uri(path);
+  let mtime = mtime(path)?;
+  let cached = thumbnail_path(&uri);
+
+  if is_fresh(&cached, &uri, mtime) {
+    if let Ok(thumb) = image::open(&cached) {
+      return Ok(thumb.to_rgba8())
+    }
+  }
+
+  let image = image::open(path).map_err(io::Error::other)?;
+
+  // Never bigger than the image itself
+  let thumb = match image.width().max(image.height()) {
+    edge if edge <= LARGE_SIZE => image.to_rgba8(),
+    _ => image.thumbnail(LARGE_SIZE, LARGE_SIZE).to_rgba8()
+  };
+
+  // Without the cache it only takes longer next time
+  if let Err(e) = save(&cached, &thumb, &uri, mtime) {
+    eprintln!("Failed to cache thumbnail of {uri}: {e}");
+  }
+
+  Ok(thumb)
+}
+
+/// `file://` URI of `path`, escaped like GLib does so
+/// hashes match other thumbnailers' ones
+pub fn file_uri(path: &Path) -> String {
+  let mut uri = String::from("file://");
+
+  for &byte in path.as_os_str().as_bytes() {
+    match byte {
+      b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' |
+      b'!' | b'$' | b'&' | b'\'' | b'(' | b')' |
+      b'*' | b'+' | b',' | b'-' | b'.' | b'/' |
+      b':' | b'=' | b'@' | b'_' | b'~' =>
+        uri.push(byte as char),
+      _ => uri.push_str(&format!("%{byte:02X}"))
+    }
+  }
+
+  uri
+}
+
+/// Where the `large` thumbnail of the image at `uri` goes
+fn thumbnail_path(uri: &str) -> PathBuf {
+  let home_dir = var("HOME").unwrap_or_default();
+  let cache_dir = var("XDG_CACHE_HOME")
+    .unwrap_or(format!("{home_dir}/.cache"));
+
+  let hash = md5::compute(uri);
+  PathBuf::from(format!(
+    "{cache_dir}/thumbnails/large/{hash:x}.png"))
+}
+
+fn mtime(path: &Path) -> io::Result<u64> {
+  let modified = fs::metadata(path)?.modified()?;
+
+  Ok(modified
+    .duration_since(UNIX_EPOCH)
+    .map_err(io::Error::other)?
+    .as_secs())
+}
+
+/// Whether the thumbnail at `cached` was made from the
+/// image as it is now
+fn is_fresh(cached: &Path, uri: &str, mtime: u64) -> bool {
+  let reader = match File::open(cached)
+    .map(png::Decoder::new)
+    .map(|d| d.read_info())
+  {
+    Ok(Ok(r)) => r,
+    _ => return false
+  };
+
+  let text = |key: &str| reader
+    .info()
+    .uncompressed_latin1_text
+    .iter()
+    .find(|t| t.keyword == key)
+    .map(|t| t.text.clone());
+
+  text("Thumb::URI").as_deref() == Some(uri) &&
+    text("Thumb::MTime") == Some(mtime.to_string())
+}
+
+fn save(
+  cached: &Path,
+  thumb: &RgbaImage,
+  uri: &str,
+  mtime: u64
+) -> io::Result<()> {
+  if let Some(dir) = cached.parent().filter(|d| !d.exists()) {
+    fs::create_dir_all(dir)?;
+    fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+  }
+
+  // Written aside and moved in, so nobody reads half of it
+  let partial = cached.with_extension(
+    format!("{}.part", std::process::id()));
+
+  let file = File::create(&partial)?;
+  file.set_permissions(fs::Permissions::from_mode(0o600))?;
+
+  let mut encoder = png::Encoder::new(
+    BufWriter::new(file), thumb.width(), thumb.height());
+  encoder.set_color(png::ColorType::Rgba);
+  encoder.set_depth(png::BitDepth::Eight);
+
+  let texts = [
+    ("Thumb::URI", uri.to_string()),
+    ("Thumb::MTime", mtime.to_string()),
+    ("Software", "hyprmaster".to_string()),
+  ];
+
+  for (key, value) in texts {
+    encoder.add_text_chunk(key.to_string(), value)
+      .map_err(io::Error::other)?;
+  }
+
+  let mut writer = encoder.write_header()
+    .map_err(io::Error::other)?;
+  writer.write_image_data(thumb.as_raw())
+    .map_err(io::Error::other)?;
+  writer.finish().map_err(io::Error::other)?;
+
+  fs::rename(partial, cached)
+}
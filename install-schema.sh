@@ -1,4 +1,6 @@
 cp -f ./data/schema/org.hypr.Hyprmaster.tablet.gschema.xml \
   /usr/share/glib-2.0/schemas
+cp -f ./data/schema/org.hypr.Hyprmaster.apps.gschema.xml \
+  /usr/share/glib-2.0/schemas
 
 glib-compile-schemas /usr/share/glib-2.0/schemas
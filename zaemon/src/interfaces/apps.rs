@@ -1,7 +1,10 @@
 use std::fmt::{self, Display, Formatter};
-use std::time::Duration;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
 use std::error::Error;
+use std::io;
 use std::boxed::Box;
 use std::sync::Arc;
 
@@ -19,6 +22,7 @@ use notify_debouncer_full::{
 use tokio::{
   task::JoinSet,
   sync::mpsc::unbounded_channel as channel,
+  sync::broadcast::error::RecvError,
   sync::RwLock
 };
 
@@ -34,6 +38,7 @@ use i_slint_core::graphics::{
 };
 
 use zbus::{
+  fdo,
   interface,
   zvariant,
   ObjectServer,
@@ -41,6 +46,7 @@ use zbus::{
   names::MemberName,
   object_server::{
     Interface,
+    InterfaceRef,
     SignalEmitter,
     DispatchResult
   },
@@ -49,10 +55,30 @@ use zbus::{
 use walkdir::WalkDir;
 use async_stream::stream;
 use futures_core::stream::Stream;
-use futures_util::{stream::StreamExt, pin_mut};
+use futures_util::stream::StreamExt;
 
 use crate::utils::notify::DebouncedSender;
+use crate::utils::cache;
+use crate::utils::search::{search_words, score_fields};
+use crate::utils::usage::{self, UsageDb, AppUsage};
+use crate::utils::matching::{
+  WindowKeys,
+  match_entry,
+  exec_names
+};
+use crate::utils::hyprctl::{self, HyprClient};
+use crate::utils::exec::{
+  self,
+  ExecContext,
+  shell_quote
+};
+use crate::utils::locale::locale_variants;
+use crate::dconf;
+
+pub use crate::utils::locale::current_locale;
 use super::IconsObject;
+use super::{HyprReceiver, HyprlandEvent};
+use super::icons::get_first_icon;
 
 
 
@@ -97,6 +123,54 @@ impl From<SerialPixelBuffer> for Rgba8Buffer {
 }
 
 
+/// Where an app got installed from
+#[derive(
+  serde::Deserialize,
+  serde::Serialize,
+  zvariant::Type,
+  Default,
+  Debug,
+  Clone,
+  Copy,
+  PartialEq,
+  Eq,
+)]
+#[serde(rename_all = "lowercase")]
+#[zvariant(signature = "s")]
+pub enum AppSource {
+  #[default]
+  Native,
+  Flatpak,
+  Snap,
+}
+
+impl AppSource {
+  /// Source of the entry at `path`, by the export dir it
+  /// lives in, or by the keys Flatpak and snapd add to the
+  /// entries they export
+  fn of(path: &Path, entry: &EntryFile) -> Self {
+    let canonical = path.canonicalize()
+      .unwrap_or(path.to_path_buf());
+    let in_dir = |dir: &Path| path.starts_with(dir) ||
+      canonical.starts_with(dir);
+
+    if flatpak_export_dirs().iter().any(|d| in_dir(d)) ||
+      !entry.get_str("X-Flatpak").is_empty()
+    {
+      return Self::Flatpak
+    }
+
+    if in_dir(Path::new(SNAP_EXPORT_DIR)) ||
+      !entry.get_str("X-SnapInstanceName").is_empty()
+    {
+      return Self::Snap
+    }
+
+    Self::Native
+  }
+}
+
+
 #[derive(
   serde::Deserialize,
   serde::Serialize,
@@ -108,21 +182,171 @@ impl From<SerialPixelBuffer> for Rgba8Buffer {
   Eq,
 )]
 pub struct DesktopEntry {
-  pub entry_path  :String,
-  pub exec        :String,
-  pub name        :String,
+  pub entry_path     :String,
+  pub desktop_id     :String,
+  pub source         :AppSource,
+  pub exec           :String,
+  pub try_exec       :String,
+  pub name           :String,
+  pub generic_name   :String,
+
+  pub icon_name      :String,
+  pub icon_path      :String,
+  pub cached_icn     :SerialPixelBuffer,
+  pub no_icon        :bool,
+
+  pub wm_class       :String,
+  pub description    :String,
+
+  /// Lowercased StartupWMClass, empty when unset
+  pub startup_wm_class :String,
+  /// Names the Exec line runs under, see
+  /// [`exec_names`](crate::utils::matching::exec_names)
+  pub exec_names       :Vec<String>,
+
+  pub actions        :Vec<DesktopAction>,
+  pub categories     :Vec<String>,
+  pub keywords       :Vec<String>,
+  pub mime_types     :Vec<String>,
+  pub only_show_in   :Vec<String>,
+  pub not_show_in    :Vec<String>,
+
+  pub no_display     :bool,
+  pub hidden         :bool,
+  pub terminal       :bool,
+  pub startup_notify :bool,
+  pub fade           :bool,
+
+  /// Locale-suffixed keys (`Name[pt_BR]`) of the entry,
+  /// resolved by [`DesktopEntry::localized`]
+  pub translations   :HashMap<String, String>,
+}
+
+impl DesktopEntry {
+  /// Copy of the entry with its translatable keys
+  /// resolved for `locale`, without the translations
+  pub fn localized(&self, locale: &str) -> DesktopEntry {
+    let variants = locale_variants(locale);
+    let pick = |map: &HashMap<String, String>, key: &str|
+      variants
+        .iter()
+        .find_map(|v| map.get(&format!("{key}[{v}]")))
+        .cloned();
+
+    let mut entry = self.clone();
+    let tr = std::mem::take(&mut entry.translations);
+
+    if let Some(name) = pick(&tr, "Name") {
+      entry.name = name;
+    }
+
+    if let Some(name) = pick(&tr, "GenericName") {
+      entry.generic_name = name;
+    }
+
+    if let Some(comment) = pick(&tr, "Comment") {
+      entry.description = comment;
+    }
+
+    if let Some(keywords) = pick(&tr, "Keywords") {
+      entry.keywords = parse_list(&keywords);
+    }
+
+    for action in entry.actions.iter_mut() {
+      let tr = std::mem::take(&mut action.translations);
+
+      if let Some(name) = pick(&tr, "Name") {
+        action.name = name;
+      }
+    }
+
+    entry
+  }
+
+  /// Whether the entry belongs in menus and app lists
+  /// for the current desktop, per `NoDisplay`, `Hidden`
+  /// and `OnlyShowIn`/`NotShowIn`.
+  pub fn should_show(&self) -> bool {
+    if self.no_display || self.hidden { return false }
+
+    let desktops: Vec<String> =
+      std::env::var("XDG_CURRENT_DESKTOP")
+      .unwrap_or_default()
+      .split(':')
+      .map(|d| d.to_string())
+      .collect();
+
+    let listed = |list: &Vec<String>| list
+      .iter()
+      .any(|d| desktops.contains(d));
+
+    if !self.only_show_in.is_empty() {
+      return listed(&self.only_show_in)
+    }
+
+    !listed(&self.not_show_in)
+  }
+}
+
+
+/// A `[Desktop Action <id>]` group of an entry
+#[derive(
+  serde::Deserialize,
+  serde::Serialize,
+  zvariant::Type,
+  Default,
+  Debug,
+  Clone,
+  PartialEq,
+  Eq,
+)]
+pub struct DesktopAction {
+  pub id           :String,
+  pub name         :String,
+  pub exec         :String,
+  pub icon_name    :String,
+  pub translations :HashMap<String, String>,
+}
 
-  pub icon_name   :String,
-  pub icon_path   :String,
-  pub cached_icn  :SerialPixelBuffer,
-  pub no_icon     :bool,
 
-  pub wm_class    :String,
-  pub description :String,
+/// Search hit, without pixel data so it stays cheap to
+/// send on every keystroke
+#[derive(
+  serde::Deserialize,
+  serde::Serialize,
+  zvariant::Type,
+  Default,
+  Debug,
+  Clone,
+  PartialEq,
+  Eq,
+)]
+pub struct AppMatch {
+  pub desktop_id   :String,
+  pub entry_path   :String,
+  pub name         :String,
+  pub generic_name :String,
+  pub description  :String,
+  pub icon_name    :String,
+  pub icon_path    :String,
+  pub score        :u32,
+  pub frecency     :u32,
+}
 
-  pub no_display  :bool,
-  pub terminal    :bool,
-  pub fade        :bool,
+impl From<&DesktopEntry> for AppMatch {
+  fn from(entry: &DesktopEntry) -> Self {
+    Self {
+      desktop_id   :entry.desktop_id.clone(),
+      entry_path   :entry.entry_path.clone(),
+      name         :entry.name.clone(),
+      generic_name :entry.generic_name.clone(),
+      description  :entry.description.clone(),
+      icon_name    :entry.icon_name.clone(),
+      icon_path    :entry.icon_path.clone(),
+      score        :0,
+      frecency     :0,
+    }
+  }
 }
 
 
@@ -141,14 +365,209 @@ impl Ord for DesktopEntry {
 }
 
 
+/// Bumped whenever `DesktopEntry` or the icon size change,
+/// invalidating caches written by older daemons
+const APP_CACHE_VERSION: u32 = 4;
+const APP_CACHE_FILE: &str = "apps.bin";
+
+/// Size cached icons are scaled down to
+const APP_ICON_SIZE: u32 = 64;
+
+/// Frecency worth one point of search score
+const FRECENCY_PER_POINT: u32 = 25;
+
+/// Most points usage adds to a search score. It's less
+/// than a whole-word hit on a name, so usage only breaks
+/// near-ties between matches.
+const MAX_USAGE_POINTS: u32 = 20;
+
+/// On-disk app catalog, with the mtime of every entry file
+/// it was parsed from, including hidden ones. Rejected
+/// files are left out, so they get another look on load.
+#[derive(
+  serde::Deserialize,
+  serde::Serialize,
+  zvariant::Type,
+  Default,
+  Debug
+)]
+pub struct AppCache {
+  pub version :u32,
+  pub mtimes  :HashMap<String, u64>,
+  pub entries :Vec<DesktopEntry>,
+}
+
+/// How a listed app changed after an update
+#[derive(Debug, Clone, PartialEq)]
+pub enum AppUpdate {
+  Added(String),
+  Changed(String),
+  Removed(String),
+}
+
+impl AppCache {
+  /// Reads the cache at `path`, starting over when it's
+  /// missing or was written by another version
+  pub fn load_from(path: &Path) -> Self {
+    cache::read_from::<AppCache>(path)
+      .filter(|c| c.version == APP_CACHE_VERSION)
+      .unwrap_or_default()
+  }
+
+  pub fn save_to(&self, path: &Path) -> io::Result<()> {
+    cache::write_to(path, self)
+  }
+
+  /// Every entry listed under `dirs`, reusing the ones
+  /// from `self` whose file didn't change since. Also
+  /// returns the IDs it had to parse again.
+  pub async fn rescan(self, dirs: &[PathBuf]) ->
+    (Self, Vec<String>)
+  {
+    let mut stored_entries: HashMap<String, DesktopEntry> =
+      self.entries
+        .into_iter()
+        .map(|e| (e.entry_path.clone(), e))
+        .collect();
+
+    let mut scan = AppCache {
+      version: APP_CACHE_VERSION,
+      ..Default::default()
+    };
+
+    let mut parsed = vec![];
+    let mut set = JoinSet::new();
+
+    for (id, path) in entry_paths(dirs) {
+      let key = path.to_string_lossy().to_string();
+      let mtime = cache::mtime(&path);
+
+      let fresh = mtime != 0 &&
+        self.mtimes.get(&key) == Some(&mtime);
+
+      if fresh {
+        scan.mtimes.insert(key.clone(), mtime);
+      }
+
+      match (fresh, stored_entries.remove(&key)) {
+        // Unchanged, listed entry
+        (true, Some(entry)) => scan.entries.push(entry),
+        // Unchanged, but hidden
+        (true, None) => (),
+        _ => {
+          set.spawn_blocking(move || {
+            (key, mtime, make_entry(path, id))
+          });
+        }
+      }
+    }
+
+    while let Some(res) = set.join_next().await {
+      let (key, mtime, entry) = match res {
+        Ok(v) => v,
+        _ => continue
+      };
+
+      // A missing TryExec binary may get installed later
+      if entry.is_some() {
+        scan.mtimes.insert(key, mtime);
+      }
+
+      match entry {
+        Some(app) if !app.hidden => {
+          parsed.push(app.desktop_id.clone());
+          scan.entries.push(app);
+        },
+        _ => continue
+      }
+    }
+
+    scan.entries.sort();
+    (scan, parsed)
+  }
+
+  /// Re-reads only the desktop IDs under `dirs` affected
+  /// by changes to `paths`
+  pub async fn update_paths(
+    &mut self,
+    dirs: &[PathBuf],
+    paths: &[PathBuf]
+  ) -> Vec<AppUpdate> {
+    let winners: HashMap<String, PathBuf> = entry_paths(dirs)
+      .into_iter()
+      .collect();
+
+    let ids = affected_ids(dirs, paths, &winners, &self.entries);
+    let mut updates = vec![];
+
+    for id in ids {
+      let entry = match winners.get(&id) {
+        None => None,
+        Some(path) => {
+          let key = path.to_string_lossy().to_string();
+          let mtime = cache::mtime(path);
+
+          let (path, entry_id) = (path.clone(), id.clone());
+          let entry = tokio::task::spawn_blocking(
+            move || make_entry(path, entry_id))
+            .await
+            .ok()
+            .flatten();
+
+          match entry.is_some() {
+            true => self.mtimes.insert(key, mtime),
+            false => self.mtimes.remove(&key)
+          };
+
+          entry.filter(|e| !e.hidden)
+        }
+      };
+
+      let pos = self.entries
+        .iter()
+        .position(|e| e.desktop_id == id);
+
+      match (entry, pos) {
+        (Some(entry), Some(pos)) => {
+          self.entries[pos] = entry;
+          updates.push(AppUpdate::Changed(id));
+        },
+        (Some(entry), None) => {
+          self.entries.push(entry);
+          updates.push(AppUpdate::Added(id));
+        },
+        (None, Some(pos)) => {
+          self.entries.remove(pos);
+          updates.push(AppUpdate::Removed(id));
+        },
+        (None, None) => ()
+      }
+    }
+
+    let live: HashSet<String> = winners
+      .values()
+      .map(|p| p.to_string_lossy().to_string())
+      .collect();
+
+    self.entries.sort();
+    self.mtimes.retain(|p, _| live.contains(p));
+    updates
+  }
+}
+
+
 pub(crate) struct AppsObject {
   cache: Arc<RwLock<Vec<DesktopEntry>>>,
+  mtimes: HashMap<String, u64>,
+  usage: Arc<RwLock<UsageDb>>,
 }
 
 impl AppsObject {
   pub fn new() -> Self {
     Self {
       cache: Arc::new(RwLock::new(vec![])),
+      mtimes: HashMap::new(),
+      usage: Arc::new(RwLock::new(UsageDb::load())),
     }
   }
 
@@ -186,11 +605,36 @@ impl AppsObject {
     }
   }
 
-  pub async fn listen(conn: &zbus::Connection) ->
+  pub async fn listen(
+    conn: &zbus::Connection,
+    hrx: HyprReceiver
+  ) -> Result<(), Box<dyn Error>> {
+    _ = tokio::join!(
+      Self::watch_entries(conn),
+      Self::watch_focus(conn, hrx)
+    );
+
+    Ok(())
+  }
+
+  async fn watch_entries(conn: &zbus::Connection) ->
     Result<(), Box<dyn Error>>
   {
-    // Initial cache reset
-    Self::trigger_cache_reset(conn).await?;
+    let iface = conn.object_server()
+      .interface::<_, AppsObject>("/apps").await?;
+
+    // Start from the persistent cache, re-parsing only
+    // the entries that changed since it was written
+    {
+      let stored = AppCache::load_from(
+        &cache::cache_dir().join(APP_CACHE_FILE));
+      let scan = Self::rescan(
+        conn.object_server(), stored).await?;
+
+      iface.get_mut().await.swap_in(&scan).await;
+      save_app_cache(scan).await;
+      iface.signal_emitter().app_list_changed().await?;
+    }
 
     let (sx, mut rx) = channel();
     let sender = DebouncedSender(sx);
@@ -205,7 +649,7 @@ impl AppsObject {
         if let Ok(_) = p.try_exists() {
           let res = debouncer.watch(
             &p,
-            RecursiveMode::NonRecursive
+            RecursiveMode::Recursive
           );
 
           if res.is_err() {
@@ -221,14 +665,16 @@ impl AppsObject {
           EvKind::Create(_) |
           EvKind::Modify(_) |
           EvKind::Remove(_) => {
-            println!("Event received: {:#?}", ev);
-            println!("Rebuilding cache: {:#?}", ev);
+            println!("Updating entries for: {:#?}", ev.paths);
 
-            let res = Self::trigger_cache_reset(conn)
-              .await;
+            let res = Self::update_paths(
+              conn,
+              &iface,
+              &ev.paths
+            ).await;
 
             if res.is_err() {
-              println!("ResetCache error: {:#?}", res);
+              println!("Cache update error: {:#?}", res);
             }
           }
           _ => ()
@@ -237,18 +683,236 @@ impl AppsObject {
 
     Ok(())
   }
+
+  /// Adds up how long each app stays focused, going by
+  /// the `ActiveWindow` events of Hyprland
+  async fn watch_focus(
+    conn: &zbus::Connection,
+    mut hrx: HyprReceiver
+  ) -> Result<(), Box<dyn Error>> {
+    let iface = conn.object_server()
+      .interface::<_, AppsObject>("/apps").await?;
+
+    let save_interval = Duration::from_secs(60);
+    let mut last_save = Instant::now();
+    let mut focused: Option<(String, Instant)> = None;
+
+    loop {
+      let class = match hrx.recv().await {
+        Ok(HyprlandEvent::ActiveWindow {
+          window_class: class,
+          ..
+        }) => class,
+        Ok(_) | Err(RecvError::Lagged(_)) => continue,
+        Err(RecvError::Closed) => break
+      };
+
+      let iref = iface.get().await;
+      let id = {
+        let cache = iref.cache.read().await;
+        match_entry(&cache, &WindowKeys::from_class(&class))
+          .map(|e| e.desktop_id.clone())
+      };
+
+      let same_app = match (&focused, &id) {
+        (Some((prev, _)), Some(id)) => prev == id,
+        _ => false
+      };
+
+      if same_app { continue }
+
+      let mut usage = iref.usage.write().await;
+
+      if let Some((prev, since)) = focused.take() {
+        usage.record_focus(&prev, since.elapsed().as_secs());
+      }
+
+      focused = id.map(|id| (id, Instant::now()));
+
+      if last_save.elapsed() >= save_interval {
+        last_save = Instant::now();
+
+        if let Err(e) = usage.save() {
+          println!("Failed to save app usage: {:#?}", e);
+        }
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Re-reads only the desktop IDs affected by changes
+  /// to `paths`, emitting `AppAdded`, `AppChanged` or
+  /// `AppRemoved` for each of them. The object is only
+  /// locked to swap the result in.
+  async fn update_paths(
+    conn: &zbus::Connection,
+    iface: &InterfaceRef<AppsObject>,
+    paths: &[PathBuf]
+  ) -> zbus::Result<()> {
+    let mut stored = iface.get().await.stored().await;
+    let updates = stored
+      .update_paths(&app_lookup_dirs(), paths)
+      .await;
+
+    let loaded: Vec<String> = updates
+      .iter()
+      .filter_map(|u| match u {
+        AppUpdate::Added(id) |
+        AppUpdate::Changed(id) => Some(id.clone()),
+        AppUpdate::Removed(_) => None
+      })
+      .collect();
+
+    load_icons(
+      conn.object_server(),
+      &mut stored.entries,
+      &loaded
+    ).await?;
+
+    iface.get_mut().await.swap_in(&stored).await;
+    save_app_cache(stored).await;
+
+    let emitter = iface.signal_emitter();
+
+    for update in updates {
+      match update {
+        AppUpdate::Added(id) => {
+          Self::app_added(emitter, &id).await?
+        },
+        AppUpdate::Changed(id) => {
+          Self::app_changed(emitter, &id).await?
+        },
+        AppUpdate::Removed(id) => {
+          Self::app_removed(emitter, &id).await?
+        }
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Entry matching a window, localized for the daemon's
+  /// locale
+  async fn matching_entry(&self, keys: &WindowKeys) ->
+    Option<AppMatch>
+  {
+    self.with_matching_entry(keys, |e| AppMatch::from(
+      &e.localized(&current_locale()))).await
+  }
+
+  /// Runs `f` on the entry matching a window
+  pub async fn with_matching_entry<T>(
+    &self,
+    keys: &WindowKeys,
+    f: impl FnOnce(&DesktopEntry) -> T
+  ) -> Option<T> {
+    let cache = self.cache.read().await;
+    match_entry(&cache, keys).map(f)
+  }
+
+  async fn record_launch(&self, entry_path: &str) {
+    let id = desktop_id_for(Path::new(entry_path));
+    let mut usage = self.usage.write().await;
+
+    usage.record_launch(&id);
+
+    if let Err(e) = usage.save() {
+      println!("Failed to save app usage: {:#?}", e);
+    }
+  }
+
+  /// Listed apps with any recorded usage, localized for
+  /// the daemon's locale
+  async fn used_apps(&self, usage: &UsageDb) ->
+    Vec<(AppMatch, AppUsage)>
+  {
+    let locale = current_locale();
+    let cache = self.cache.read().await;
+    let now = usage::now();
+
+    usage.iter()
+      .filter_map(|(id, u)| {
+        let entry = cache
+          .iter()
+          .find(|e| &e.desktop_id == id)?
+          .localized(&locale);
+
+        let app = AppMatch {
+          frecency: u.frecency(now),
+          ..AppMatch::from(&entry)
+        };
+
+        Some((app, u.clone()))
+      })
+      .collect()
+  }
+
+  /// Every listed entry with its icon, reusing the ones
+  /// from `stored` whose file didn't change since
+  async fn rescan(
+    srv: &ObjectServer,
+    stored: AppCache
+  ) -> zbus::Result<AppCache> {
+    let (mut scan, parsed) = stored
+      .rescan(&app_lookup_dirs())
+      .await;
+
+    load_icons(srv, &mut scan.entries, &parsed).await?;
+    Ok(scan)
+  }
+
+  /// Replaces the listed entries with those of `stored`
+  async fn swap_in(&mut self, stored: &AppCache) {
+    self.mtimes = stored.mtimes.clone();
+    *self.cache.write().await = stored.entries.clone();
+  }
+
+  /// The listed entries as they get written to disk
+  async fn stored(&self) -> AppCache {
+    AppCache {
+      version: APP_CACHE_VERSION,
+      mtimes: self.mtimes.clone(),
+      entries: self.cache.read().await.clone()
+    }
+  }
+
+  async fn localized_apps(&self, locale: &str) ->
+    Vec<DesktopEntry>
+  {
+    let cache = self.cache.read().await;
+    let mut apps: Vec<DesktopEntry> = cache
+      .iter()
+      .map(|e| e.localized(locale))
+      .collect();
+
+    apps.sort();
+    apps
+  }
+
 }
 
 // TODO:
-// - Filter method
 // - Proper icon_path resolution
-// - persistent caching
 #[interface(name = "org.hypr.Hyprmaster.Apps")]
 impl AppsObject {
+  /// Lists every app, in the daemon's locale
   async fn all_apps(&self) -> Vec<DesktopEntry> {
     println!("App list requested!");
-    let cache = self.cache.read().await;
-    cache.clone()
+    self.localized_apps(&current_locale()).await
+  }
+
+  /// Lists every app, with names and descriptions in
+  /// `locale`, or in the daemon's locale when empty
+  async fn all_apps_for_locale(&self, locale: &str) ->
+    Vec<DesktopEntry>
+  {
+    let locale = match locale.trim().is_empty() {
+      true => current_locale(),
+      false => locale.to_string()
+    };
+
+    self.localized_apps(&locale).await
   }
 
 
@@ -261,44 +925,143 @@ impl AppsObject {
   ) ->
     zbus::fdo::Result<()>
   {
-    let icns_intr = srv
-      .interface::<_, IconsObject>("/icons").await?;
-    let mut icns_intr = icns_intr.get_mut().await;
-
-    let mut cache = self.cache.write().await;
-    cache.clear();
-
-    let app_stream = get_apps_stream();
-    pin_mut!(app_stream);
-
-    while let Some(mut app) = app_stream.next().await {
-      match app.icon_name.trim().is_empty() {
-        true => cache.push(app),
-        false => {
-          app.icon_path = icns_intr
-            .get_icon(app.icon_name.as_str()).await;
-
-          let img = Image::load_from_path(
-            PathBuf::from(&app.icon_path).as_path());
-
-          if img.is_err() || app.icon_path.is_empty() {
-            app.no_icon = true;
-          } else {
-            app.cached_icn = img.unwrap()
-              .to_rgba8_premultiplied()
-              .unwrap()
-              .into()
-          }
+    // Nothing from the persistent cache is reused
+    let scan = Self::rescan(srv, AppCache::default()).await?;
+    self.swap_in(&scan).await;
+    save_app_cache(scan).await;
+    sig_emitter.app_list_changed().await?;
 
-          cache.push(app);
-        }
-      };
+    println!("App cache rebuilt!");
+    Ok(())
+  }
+
+  /// Ranks the shown apps against `query` by name, generic
+  /// name, keywords, categories and executable, ignoring
+  /// case and accents. An empty query ranks them by usage.
+  /// A `limit` of 0 returns every hit.
+  async fn search(&self, query: &str, limit: u32) ->
+    Vec<AppMatch>
+  {
+    let words = search_words(query);
+    let locale = current_locale();
+    let cache = self.cache.read().await;
+    let usage = self.usage.read().await;
+
+    let entries = cache
+      .iter()
+      .filter(|e| e.should_show())
+      .map(|e| e.localized(&locale));
+
+    let mut hits = rank_entries(entries, &words,
+      |id| usage.frecency(id));
+
+    if limit > 0 {
+      hits.truncate(limit as usize);
     }
 
-    cache.sort();
-    sig_emitter.app_list_changed().await?;
+    hits
+  }
 
-    println!("App cache rebuilt!");
+  /// Apps launched or focused so far, most recently used
+  /// first
+  async fn recent_apps(&self) -> Vec<AppMatch> {
+    let usage = self.usage.read().await;
+
+    let mut apps = self.used_apps(&usage).await;
+    apps.sort_by_key(|(_, u)| std::cmp::Reverse(u.last_used()));
+
+    apps.into_iter().map(|(app, _)| app).collect()
+  }
+
+  /// Apps ranked by frecency, how often and how recently
+  /// they were launched, plus time spent focused
+  async fn frequent_apps(&self) -> Vec<AppMatch> {
+    let usage = self.usage.read().await;
+
+    let mut apps = self.used_apps(&usage).await;
+    apps.sort_by(|(a, _), (b, _)| b.frecency.cmp(&a.frecency)
+      .then_with(|| a.name.cmp(&b.name)));
+
+    apps.into_iter().map(|(app, _)| app).collect()
+  }
+
+  /// Desktop entry of the windows of class `class`
+  async fn entry_for_class(&self, class: &str) ->
+    fdo::Result<AppMatch>
+  {
+    self.matching_entry(&WindowKeys::from_class(class))
+      .await
+      .ok_or(fdo::Error::Failed(format!(
+        "No desktop entry for class {class}")))
+  }
+
+  /// Desktop entry of a Hyprland window, also going by
+  /// its process (executable, Flatpak or Snap app)
+  async fn entry_for_window(&self, address: &str) ->
+    fdo::Result<AppMatch>
+  {
+    let client = hyprctl::client(address).await
+      .map_err(|e| fdo::Error::Failed(e.to_string()))?
+      .ok_or(fdo::Error::InvalidArgs(format!(
+        "No window with address {address}")))?;
+
+    self.matching_entry(&WindowKeys::from_client(&client))
+      .await
+      .ok_or(fdo::Error::Failed(format!(
+        "No desktop entry for window {address}")))
+  }
+
+  /// Resolves an icon for a Hyprland window, including
+  /// the ones that don't map cleanly to a desktop entry
+  /// (games, Electron apps, XWayland clients...)
+  async fn icon_for_window(&self, address: &str) ->
+    fdo::Result<String>
+  {
+    let client = hyprctl::client(address).await
+      .map_err(|e| fdo::Error::Failed(e.to_string()))?
+      .ok_or(fdo::Error::InvalidArgs(format!(
+        "No window with address {address}")))?;
+
+    let names = {
+      let cache = self.cache.read().await;
+      window_icon_names(&client, &cache)
+    };
+
+    Ok(get_first_icon(&names).await.unwrap_or_default())
+  }
+
+  /// Launches the desktop entry at `entry_path`, handing
+  /// it `uris`. A non-empty `workspace` spawns it
+  /// silently on that workspace.
+  async fn launch(
+    &self,
+    #[zbus(connection)]
+    conn: &zbus::Connection,
+    entry_path: &str,
+    uris: Vec<String>,
+    workspace: &str
+  ) -> fdo::Result<()> {
+    let spec = LaunchSpec::read(entry_path)?;
+    launch_entry(conn, &spec, entry_path, &uris, workspace)
+      .await?;
+
+    self.record_launch(entry_path).await;
+    Ok(())
+  }
+
+  /// Launches one of the `[Desktop Action]`s of the entry
+  /// at `entry_path`, such as "New Private Window"
+  async fn launch_action(
+    &self,
+    #[zbus(connection)]
+    conn: &zbus::Connection,
+    entry_path: &str,
+    action: &str
+  ) -> fdo::Result<()> {
+    let spec = LaunchSpec::read_action(entry_path, action)?;
+    launch_entry(conn, &spec, entry_path, &[], "").await?;
+
+    self.record_launch(entry_path).await;
     Ok(())
   }
 
@@ -306,117 +1069,453 @@ impl AppsObject {
   async fn app_list_changed(
     emitter: &SignalEmitter<'_>
   ) -> zbus::Result<()>;
+
+  #[zbus(signal)]
+  async fn app_added(
+    emitter: &SignalEmitter<'_>,
+    desktop_id: &str
+  ) -> zbus::Result<()>;
+
+  #[zbus(signal)]
+  async fn app_changed(
+    emitter: &SignalEmitter<'_>,
+    desktop_id: &str
+  ) -> zbus::Result<()>;
+
+  #[zbus(signal)]
+  async fn app_removed(
+    emitter: &SignalEmitter<'_>,
+    desktop_id: &str
+  ) -> zbus::Result<()>;
 }
 
 
 
 
-pub fn app_lookup_dirs() -> Vec<PathBuf> {
-  let mut lookup_dirs: Vec<String> =
-    std::env::var("XDG_DATA_DIRS")
-    .unwrap_or("/usr/share".to_string())
-    .split(':')
-    .map(|s| match s.ends_with("/") {
-      true => format!("{s}applications"),
-      false => format!("{s}/applications"),
+/// Hits among `entries` for the words of a query, best
+/// first. Without words they're ranked by usage alone.
+fn rank_entries(
+  entries: impl Iterator<Item = DesktopEntry>,
+  words: &[String],
+  frecency: impl Fn(&str) -> u32
+) -> Vec<AppMatch> {
+  let mut hits: Vec<AppMatch> = entries
+    .filter_map(|e| {
+      let frecency = frecency(&e.desktop_id);
+      let usage = frecency.min(
+        MAX_USAGE_POINTS * FRECENCY_PER_POINT);
+
+      let score = match words.is_empty() {
+        true => frecency,
+        false => search_score(&e, words)? +
+          usage / FRECENCY_PER_POINT
+      };
+
+      Some(AppMatch {
+        score,
+        frecency,
+        ..AppMatch::from(&e)
+      })
     })
     .collect();
 
-  lookup_dirs.push("/usr/share/applications".to_string());
-  let home_dir = std::env::var("HOME").unwrap();
-  let home_path = PathBuf::from(home_dir);
-  let local_share_apps = home_path
-    .join(".local/share/applications")
-    .into_os_string()
-    .into_string()
-    .unwrap();
+  hits.sort_by(|a, b| b.score.cmp(&a.score)
+    .then_with(|| a.name.cmp(&b.name)));
+
+  hits
+}
 
-  lookup_dirs.push(local_share_apps);
-  lookup_dirs.sort();
-  lookup_dirs.dedup();
+/// Score of `entry` for the words of a search query, or
+/// `None` when some word matches nothing
+fn search_score(
+  entry: &DesktopEntry,
+  words: &[String]
+) -> Option<u32> {
+  let keywords = entry.keywords.join(" ");
+  let categories = entry.categories.join(" ");
+
+  // Only the program name of Exec, not its arguments
+  let program = exec::split_exec(&entry.exec)
+    .and_then(|args| args.into_iter().next())
+    .and_then(|p| Path::new(&p)
+      .file_name()
+      .map(|f| f.to_string_lossy().to_string()))
+    .unwrap_or_default();
+
+  let score = score_fields(words, &[
+    (&entry.name, 10),
+    (&entry.generic_name, 6),
+    (&keywords, 4),
+    (&categories, 2),
+    (&program, 2),
+  ])?;
+
+  // Names starting with the whole query come first
+  let name = search_words(&entry.name).join(" ");
+  let bonus = match name.starts_with(&words.join(" ")) {
+    true => 15,
+    false => 0
+  };
 
+  Some(score + bonus)
+}
 
-  let lookup_dirs: Vec<PathBuf>  = lookup_dirs
-    .iter()
-    .map(|pathstr| PathBuf::from(pathstr.clone()))
-    .filter(|p| p.try_exists().unwrap())
-    .collect();
+/// Writes `stored` to the persistent cache
+async fn save_app_cache(stored: AppCache) {
+  let path = cache::cache_dir().join(APP_CACHE_FILE);
+  let res = tokio::task::spawn_blocking(move ||
+    stored.save_to(&path)).await;
 
-  lookup_dirs
+  if let Ok(Err(e)) = res {
+    println!("Failed to write app cache: {:#?}", e);
+  }
 }
 
-pub async fn get_apps() -> Vec<DesktopEntry> {
-  let lookup_dirs = app_lookup_dirs();
+/// Loads the icons of the entries with one of `ids`
+async fn load_icons(
+  srv: &ObjectServer,
+  entries: &mut [DesktopEntry],
+  ids: &[String]
+) -> zbus::Result<()> {
+  if ids.is_empty() {
+    return Ok(())
+  }
 
-  let mut apps: Vec<DesktopEntry> = Vec::new();
-  let mut set = JoinSet::new();
+  let icns_intr = srv
+    .interface::<_, IconsObject>("/icons").await?;
+  let mut icns_intr = icns_intr.get_mut().await;
 
-  for dir in lookup_dirs {
-    if !dir.exists() { continue; }
+  for entry in entries
+    .iter_mut()
+    .filter(|e| ids.contains(&e.desktop_id))
+  {
+    load_entry_icon(&mut icns_intr, entry).await;
+  }
 
-    for entry in WalkDir::new(dir.clone()) {
-      if entry.is_err() { continue; }
-      let entry = entry.unwrap().clone();
+  Ok(())
+}
 
-      set.spawn_blocking(move || {
-        let path = entry.path();
+/// Resolves and loads the icon of a freshly parsed entry
+async fn load_entry_icon(
+  icns: &mut IconsObject,
+  app: &mut DesktopEntry
+) {
+  if app.icon_name.trim().is_empty() {
+    return
+  }
 
-        if path.extension()? != "desktop" { return None }
+  app.icon_path = icns.get_icon(app.icon_name.as_str())
+    .await;
 
-        make_entry(path.to_path_buf())
-      });
+  let img = Image::load_from_path(
+    PathBuf::from(&app.icon_path).as_path());
+
+  let buffer = match img {
+    Ok(img) if !app.icon_path.is_empty() => {
+      img.to_rgba8_premultiplied()
+    },
+    _ => None
+  };
+
+  match buffer {
+    Some(buffer) => {
+      app.cached_icn = fit_icon(buffer, APP_ICON_SIZE)
+        .into()
+    },
+    None => app.no_icon = true
+  }
+}
+
+/// Box-filters `buffer` down so neither side exceeds
+/// `size`, keeping its aspect ratio
+fn fit_icon(buffer: Rgba8Buffer, size: u32) -> Rgba8Buffer {
+  let (w, h) = (buffer.width(), buffer.height());
+
+  if w <= size && h <= size || w == 0 || h == 0 {
+    return buffer
+  }
+
+  let scale = w.max(h) as f32 / size as f32;
+  let nw = ((w as f32 / scale).round() as u32).max(1);
+  let nh = ((h as f32 / scale).round() as u32).max(1);
+
+  let src = buffer.as_slice();
+  let mut out = Rgba8Buffer::new(nw, nh);
+  let dst = out.make_mut_slice();
+
+  for y in 0..nh {
+    let y0 = y * h / nh;
+    let y1 = ((y + 1) * h / nh).max(y0 + 1);
+
+    for x in 0..nw {
+      let x0 = x * w / nw;
+      let x1 = ((x + 1) * w / nw).max(x0 + 1);
+      let mut sum = [0u32; 4];
+
+      for sy in y0..y1 {
+        for sx in x0..x1 {
+          let px = src[(sy * w + sx) as usize];
+          sum[0] += px.r as u32;
+          sum[1] += px.g as u32;
+          sum[2] += px.b as u32;
+          sum[3] += px.a as u32;
+        }
+      }
+
+      let n = (x1 - x0) * (y1 - y0);
+      dst[(y * nw + x) as usize] = Rgba8Pixel {
+        r: (sum[0] / n) as u8,
+        g: (sum[1] / n) as u8,
+        b: (sum[2] / n) as u8,
+        a: (sum[3] / n) as u8,
+      };
     }
   }
 
-  while let Some(res) = set.join_next().await {
-    if res.is_err() { continue }
+  out
+}
 
-    match res.unwrap() {
-      Some(app_entry) => apps.push(app_entry),
-      _ => continue
+/// Desktop IDs touched by changes to `paths`. Changed
+/// directories (e.g. a removed `kde4/` subfolder) touch
+/// every ID listed or cached beneath them.
+fn affected_ids(
+  dirs: &[PathBuf],
+  paths: &[PathBuf],
+  winners: &HashMap<String, PathBuf>,
+  cache: &[DesktopEntry]
+) -> HashSet<String> {
+  let mut ids = HashSet::new();
+
+  for path in paths {
+    let is_entry = path
+      .extension()
+      .is_some_and(|e| e == "desktop");
+
+    if is_entry {
+      ids.extend(dirs
+        .iter()
+        .find_map(|dir| desktop_file_id(dir, path)));
+      continue
     }
+
+    ids.extend(winners
+      .iter()
+      .filter(|(_, p)| p.starts_with(path))
+      .map(|(id, _)| id.clone()));
+
+    ids.extend(cache
+      .iter()
+      .filter(|e| Path::new(&e.entry_path).starts_with(path))
+      .map(|e| e.desktop_id.clone()));
   }
 
-  apps.sort();
-  apps
+  ids
 }
 
+/// Icon names to try for a window, in order: its class,
+/// the icon of the entry matching it, its initial
+/// class, its executable and a generic fallback.
+fn window_icon_names(
+  client: &HyprClient,
+  entries: &[DesktopEntry]
+) -> Vec<String> {
+  let entry_icon = |key: &str| match_entry(
+      entries, &WindowKeys::from_class(key))
+    .filter(|e| !e.icon_name.is_empty())
+    .map(|e| e.icon_name.clone());
+
+  let exe = match client.pid > 0 {
+    true => std::fs::read_link(
+        format!("/proc/{}/exe", client.pid))
+      .ok()
+      .and_then(|p| Some(p.file_name()?
+        .to_string_lossy()
+        .to_string())),
+    false => None
+  };
+
+  let keys = [&client.class, &client.initial_class]
+    .into_iter()
+    .cloned()
+    .chain(exe)
+    .filter(|k| !k.is_empty());
 
-fn get_apps_stream() -> impl Stream<Item = DesktopEntry>
-{
-  let lookup_dirs = app_lookup_dirs();
-  let mut set = JoinSet::new();
+  let mut names: Vec<String> = vec![];
+
+  for key in keys {
+    let lower = key.to_lowercase();
+    let icon = entry_icon(&key);
+
+    for name in [Some(key), Some(lower), icon] {
+      match name {
+        Some(n) if !names.contains(&n) => names.push(n),
+        _ => ()
+      }
+    }
+  }
 
-  for dir in lookup_dirs {
-    if !dir.exists() { continue; }
+  names.push("application-x-executable".to_string());
+  names
+}
 
-    for entry in WalkDir::new(dir.clone()) {
-      if entry.is_err() { continue; }
-      let entry = entry.unwrap().clone();
+/// Data dirs Flatpak exports its apps to, user one first
+pub fn flatpak_export_dirs() -> Vec<PathBuf> {
+  let home_dir = std::env::var("HOME").unwrap();
+  let data_home = std::env::var("XDG_DATA_HOME")
+    .ok()
+    .filter(|d| !d.trim().is_empty())
+    .unwrap_or(format!("{home_dir}/.local/share"));
+
+  vec![
+    PathBuf::from(data_home).join("flatpak/exports/share"),
+    PathBuf::from("/var/lib/flatpak/exports/share")
+  ]
+}
 
-      set.spawn_blocking(move || {
-        let path = entry.path();
+/// Data dir snapd exports its apps to
+pub const SNAP_EXPORT_DIR: &str = "/var/lib/snapd/desktop";
 
-        if path.extension()? != "desktop" { return None }
+/// `applications` dirs in XDG precedence order:
+/// `XDG_DATA_HOME` first, then `XDG_DATA_DIRS` as listed,
+/// or the spec's default when it's unset.
+///
+/// The Flatpak and Snap export dirs come last, as many
+/// session launches leave them out of `XDG_DATA_DIRS`.
+pub fn app_lookup_dirs() -> Vec<PathBuf> {
+  let home_dir = std::env::var("HOME").unwrap();
 
-        make_entry(path.to_path_buf())
-      });
+  let data_home = std::env::var("XDG_DATA_HOME")
+    .ok()
+    .filter(|d| !d.trim().is_empty())
+    .unwrap_or(format!("{home_dir}/.local/share"));
+
+  let data_dirs = std::env::var("XDG_DATA_DIRS")
+    .ok()
+    .filter(|d| !d.trim().is_empty())
+    .unwrap_or("/usr/local/share:/usr/share".to_string());
+
+  let mut lookup_dirs: Vec<PathBuf> = vec![];
+  let dirs = std::iter::once(data_home.as_str())
+    .chain(data_dirs.split(':'))
+    .filter(|d| !d.trim().is_empty())
+    .map(PathBuf::from)
+    .chain(flatpak_export_dirs())
+    .chain([PathBuf::from(SNAP_EXPORT_DIR)]);
+
+  for dir in dirs {
+    let dir = dir.join("applications");
+    if !lookup_dirs.contains(&dir) {
+      lookup_dirs.push(dir);
     }
   }
 
+  lookup_dirs
+    .into_iter()
+    .filter(|p| p.try_exists().unwrap_or(false))
+    .collect()
+}
+
+/// The desktop-file ID of an entry: its path relative to
+/// the `applications` dir, with `/` replaced by `-`
+pub fn desktop_file_id(
+  apps_dir: &Path,
+  path: &Path
+) -> Option<String> {
+  let relative = path.strip_prefix(apps_dir).ok()?;
+  let parts: Vec<String> = relative
+    .components()
+    .map(|c| c.as_os_str().to_string_lossy().to_string())
+    .collect();
+
+  Some(parts.join("-"))
+}
+
+/// Desktop-file ID for any entry path, falling back to
+/// its file name outside of the lookup dirs
+pub fn desktop_id_for(path: &Path) -> String {
+  app_lookup_dirs()
+    .iter()
+    .find_map(|dir| desktop_file_id(dir, path))
+    .or(path.file_name()
+      .map(|f| f.to_string_lossy().to_string()))
+    .unwrap_or_default()
+}
+
+/// Every `.desktop` file under `dirs` to list, keyed by
+/// desktop-file ID. When IDs collide, the file from the
+/// dir that comes first wins.
+pub fn entry_paths(dirs: &[PathBuf]) -> Vec<(String, PathBuf)> {
+  let mut seen: HashSet<String> = HashSet::new();
+  let mut paths = vec![];
+
+  for dir in dirs {
+    let walker = WalkDir::new(dir)
+      .sort_by_file_name()
+      .follow_links(true);
+
+    for entry in walker {
+      let path = match entry {
+        Ok(e) => e.into_path(),
+        _ => continue
+      };
+
+      if path.extension().is_none_or(|e| e != "desktop") {
+        continue
+      }
+
+      let id = match desktop_file_id(dir, &path) {
+        Some(id) => id,
+        None => continue
+      };
+
+      if seen.insert(id.clone()) {
+        paths.push((id, path));
+      }
+    }
+  }
+
+  paths
+}
+
+pub async fn get_apps() -> Vec<DesktopEntry> {
+  let mut apps: Vec<DesktopEntry> = get_apps_stream()
+    .collect()
+    .await;
+
+  apps.sort();
+  apps
+}
+
+
+/// Streams the parsed entries of every desktop-file ID.
+/// Entries with `Hidden=true` mask the ID without being
+/// listed.
+fn get_apps_stream() -> impl Stream<Item = DesktopEntry>
+{
+  let mut set = JoinSet::new();
+
+  for (id, path) in entry_paths(&app_lookup_dirs()) {
+    set.spawn_blocking(move || make_entry(path, id));
+  }
+
   stream! {
     while let Some(res) = set.join_next().await {
       if res.is_err() { continue }
 
       match res.unwrap() {
-        Some(app_entry) => yield app_entry,
+        Some(app_entry) if !app_entry.hidden => {
+          yield app_entry
+        },
         _ => continue
       }
     }
   }
 }
 
-fn make_entry(path: PathBuf) -> Option<DesktopEntry> {
+fn make_entry(
+  path: PathBuf,
+  desktop_id: String
+) -> Option<DesktopEntry> {
   let entry: EntryFile = match parse_entry(path.to_str()?) {
     Ok(e) => e,
     _ => return None
@@ -426,23 +1525,57 @@ fn make_entry(path: PathBuf) -> Option<DesktopEntry> {
     return None;
   }
 
+  let try_exec = entry.get_str("TryExec");
+  if !try_exec_found(&try_exec) {
+    return None;
+  }
+
   Some(DesktopEntry {
     entry_path: path.clone()
       .into_os_string()
       .into_string()
       .unwrap(),
+    desktop_id,
+    source: AppSource::of(&path, &entry),
 
     exec: entry.get_str("Exec"),
     name: entry.get_str("Name"),
+    generic_name: entry.get_str("GenericName"),
+    try_exec,
 
     icon_name: entry.icon_name(),
     icon_path: String::from(""),
 
     description: entry.get_str("Comment"),
     wm_class: entry.wm_class(),
+    startup_wm_class: entry.get_str("StartupWMClass")
+      .to_lowercase(),
+    exec_names: {
+      let mut names = exec_names(&entry.get_str("Exec"));
+      names.extend([
+        entry.get_str("X-Flatpak"),
+        entry.get_str("X-SnapInstanceName")
+      ]
+        .into_iter()
+        .map(|n| n.to_lowercase())
+        .filter(|n| !n.is_empty()));
+      names
+    },
+
+    actions: entry.actions(),
+    categories: entry.get_list("Categories"),
+    keywords: entry.get_list("Keywords"),
+    mime_types: entry.get_list("MimeType"),
+    only_show_in: entry.get_list("OnlyShowIn"),
+    not_show_in: entry.get_list("NotShowIn"),
 
     no_display: entry.get_bool("NoDisplay"),
+    hidden: entry.get_bool("Hidden"),
     terminal: entry.get_bool("Terminal"),
+    startup_notify: entry.get_bool("StartupNotify"),
+    translations: entry.translations("Desktop Entry", &[
+      "Name", "GenericName", "Comment", "Keywords"
+    ]),
 
     fade: false,
     no_icon: false,
@@ -450,12 +1583,281 @@ fn make_entry(path: PathBuf) -> Option<DesktopEntry> {
   })
 }
 
+/// `TryExec` names a binary that has to be installed for
+/// the entry to be usable, either by path or in `$PATH`
+fn try_exec_found(try_exec: &str) -> bool {
+  let try_exec = try_exec.trim();
+  if try_exec.is_empty() { return true }
+
+  let is_executable = |p: &Path| p
+    .metadata()
+    .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+    .unwrap_or(false);
+
+  if try_exec.contains('/') {
+    return is_executable(Path::new(try_exec))
+  }
+
+  std::env::var("PATH")
+    .unwrap_or_default()
+    .split(':')
+    .any(|dir| is_executable(&Path::new(dir).join(try_exec)))
+}
+
+
+/* -
+ * -> Launching
+ * -
+*/
+
+/// The keys that decide how an entry, or one of its
+/// actions, gets launched
+struct LaunchSpec {
+  exec: String,
+  name: String,
+  icon: String,
+  workdir: String,
+  terminal: bool,
+  dbus_activatable: bool,
+  action: Option<String>,
+}
+
+impl LaunchSpec {
+  fn read(entry_path: &str) -> fdo::Result<Self> {
+    let entry = parse_entry(entry_path)
+      .map_err(|e| fdo::Error::FileNotFound(e.to_string()))?;
+
+    if !entry.has_section("Desktop Entry") {
+      return Err(fdo::Error::InvalidArgs(format!(
+        "{entry_path} is not a desktop entry")))
+    }
+
+    Ok(Self {
+      exec: entry.get_str("Exec"),
+      name: entry.get_str("Name"),
+      icon: entry.icon_name(),
+      workdir: entry.get_str("Path"),
+      terminal: entry.get_bool("Terminal"),
+      dbus_activatable: entry.get_bool("DBusActivatable"),
+      action: None,
+    })
+  }
+
+  /// Reads the spec of an action, which shares `Path`,
+  /// `Terminal` and activation with its entry
+  fn read_action(entry_path: &str, action: &str) ->
+    fdo::Result<Self>
+  {
+    let entry = parse_entry(entry_path)
+      .map_err(|e| fdo::Error::FileNotFound(e.to_string()))?;
+
+    let action = entry.actions()
+      .into_iter()
+      .find(|a| a.id == action)
+      .ok_or(fdo::Error::InvalidArgs(format!(
+        "{entry_path} has no action {action}")))?;
+
+    let icon = match action.icon_name.is_empty() {
+      true => entry.icon_name(),
+      false => action.icon_name
+    };
+
+    Ok(Self {
+      exec: action.exec,
+      name: action.name,
+      icon,
+      workdir: entry.get_str("Path"),
+      terminal: entry.get_bool("Terminal"),
+      dbus_activatable: entry.get_bool("DBusActivatable"),
+      action: Some(action.id),
+    })
+  }
+}
+
+/// Launches an entry through Hyprland's `exec`, or over
+/// D-Bus for `DBusActivatable` apps. Activation can't
+/// carry workspace rules, so it's skipped when a
+/// workspace is requested.
+async fn launch_entry(
+  conn: &zbus::Connection,
+  spec: &LaunchSpec,
+  entry_path: &str,
+  uris: &[String],
+  workspace: &str
+) -> fdo::Result<()> {
+  let app_id = app_id(entry_path);
+  let workspace = workspace.trim();
+
+  // Pasted into the exec rule, it can't be allowed to end
+  // it early
+  if !workspace.is_empty() &&
+    !hyprctl::is_workspace_selector(workspace)
+  {
+    return Err(fdo::Error::InvalidArgs(format!(
+      "{workspace}: not a workspace")))
+  }
+
+  if spec.dbus_activatable && workspace.is_empty() {
+    let action = spec.action.as_deref();
+
+    match activate_dbus(conn, &app_id, action, uris).await {
+      Ok(_) => return Ok(()),
+      Err(e) => println!(
+        "D-Bus activation of {app_id} failed: {:#?}", e)
+    }
+  }
+
+  if spec.exec.trim().is_empty() {
+    return Err(fdo::Error::Failed(format!(
+      "{entry_path} has no Exec key")))
+  }
+
+  let args = exec::split_exec(&spec.exec)
+    .ok_or(fdo::Error::InvalidArgs(format!(
+      "{entry_path} has a malformed Exec key")))?;
+
+  let ctx = ExecContext {
+    uris,
+    icon: &spec.icon,
+    name: &spec.name,
+    entry_path,
+  };
+
+  for mut argv in exec::expand_exec(&args, &ctx) {
+    if argv.is_empty() { continue }
+
+    if spec.terminal {
+      argv.splice(0..0, terminal_command());
+    }
+
+    let mut cmd = argv
+      .iter()
+      .map(|a| shell_quote(a))
+      .collect::<Vec<String>>()
+      .join(" ");
+
+    // `exec` keeps the app at the PID Hyprland matches
+    // the rule against, instead of a child of the shell
+    if !spec.workdir.trim().is_empty() {
+      cmd = format!("cd {} && exec {cmd}",
+        shell_quote(&spec.workdir));
+    }
+
+    if !workspace.is_empty() {
+      cmd = format!("[workspace {workspace} silent] {cmd}");
+    }
+
+    hyprctl::dispatch("exec", &cmd).await
+      .map_err(|e| fdo::Error::Failed(format!(
+        "Failed to launch {entry_path}: {e}")))?;
+  }
+
+  Ok(())
+}
+
+// The D-Bus name of an app is its desktop-file ID without
+// the `.desktop` suffix
+fn app_id(entry_path: &str) -> String {
+  let id = desktop_id_for(Path::new(entry_path));
+
+  match id.strip_suffix(".desktop") {
+    Some(id) => id.to_string(),
+    None => id
+  }
+}
+
+async fn activate_dbus(
+  conn: &zbus::Connection,
+  app_id: &str,
+  action: Option<&str>,
+  uris: &[String]
+) -> zbus::Result<()> {
+  let path = format!("/{}", app_id
+    .replace('.', "/")
+    .replace('-', "_"));
+  let platform_data: HashMap<&str, zvariant::Value> =
+    HashMap::new();
+  let iface = Some("org.freedesktop.Application");
+
+  match (action, uris.is_empty()) {
+    (Some(action), _) => conn.call_method(
+      Some(app_id), path.as_str(), iface,
+      "ActivateAction",
+      &(action, Vec::<zvariant::Value>::new(), platform_data)
+    ).await?,
+    (None, true) => conn.call_method(
+      Some(app_id), path.as_str(), iface,
+      "Activate", &(platform_data,)
+    ).await?,
+    (None, false) => conn.call_method(
+      Some(app_id), path.as_str(), iface,
+      "Open", &(uris, platform_data)
+    ).await?
+  };
+
+  Ok(())
+}
+
+fn terminal_command() -> Vec<String> {
+  let schema = "org.hypr.Hyprmaster.apps";
+
+  let terminal = dconf::get(schema, "terminal")
+    .ok()
+    .filter(|t| !t.trim().is_empty())
+    .or(std::env::var("TERMINAL").ok())
+    .unwrap_or("kitty".to_string());
+
+  let exec_arg = dconf::get(schema, "terminal-exec-arg")
+    .ok()
+    .filter(|a| !a.trim().is_empty())
+    .unwrap_or("-e".to_string());
+
+  terminal
+    .split_whitespace()
+    .map(|s| s.to_string())
+    .chain([exec_arg])
+    .collect()
+}
+
+
+// Lists are `;` separated, with `\;` escaping a literal
+// semicolon
+fn parse_list(value: &str) -> Vec<String> {
+  let mut list: Vec<String> = vec![];
+  let mut item = String::new();
+  let mut chars = value.chars();
+
+  while let Some(c) = chars.next() {
+    match c {
+      '\\' => match chars.next() {
+        Some(';') => item.push(';'),
+        Some(e) => { item.push('\\'); item.push(e) },
+        None => item.push('\\')
+      },
+      ';' => list.push(std::mem::take(&mut item)),
+      _ => item.push(c)
+    }
+  }
+
+  list.push(item);
+  list
+    .into_iter()
+    .map(|i| i.trim().to_string())
+    .filter(|i| !i.is_empty())
+    .collect()
+}
+
 
 trait EntryAttrGetters {
   fn get_bool(&self, attr: &str) -> bool;
   fn get_str(&self, attr: &str) -> String;
+  fn get_list(&self, attr: &str) -> Vec<String>;
+  fn section_str(&self, section: &str, attr: &str) -> String;
   fn wm_class(&self) -> String;
   fn icon_name(&self) -> String;
+  fn actions(&self) -> Vec<DesktopAction>;
+  fn translations(&self, section: &str, keys: &[&str]) ->
+    HashMap<String, String>;
 }
 
 impl EntryAttrGetters for EntryFile {
@@ -469,7 +1871,15 @@ impl EntryAttrGetters for EntryFile {
   }
 
   fn get_str(&self, attr: &str) -> String {
-    self.section("Desktop Entry")
+    self.section_str("Desktop Entry", attr)
+  }
+
+  fn get_list(&self, attr: &str) -> Vec<String> {
+    parse_list(&self.get_str(attr))
+  }
+
+  fn section_str(&self, section: &str, attr: &str) -> String {
+    self.section(section)
       .attr(attr)
       .unwrap_or("")
       .into()
@@ -512,6 +1922,46 @@ impl EntryAttrGetters for EntryFile {
       .replace('"', "")
       .replace('\'', "")
   }
+
+  fn actions(&self) -> Vec<DesktopAction> {
+    self.get_list("Actions")
+      .into_iter()
+      .filter_map(|id| {
+        let section = format!("Desktop Action {id}");
+        if !self.has_section(&section) { return None }
+
+        Some(DesktopAction {
+          name: self.section_str(&section, "Name"),
+          exec: self.section_str(&section, "Exec"),
+          icon_name: self.section_str(&section, "Icon")
+            .replace('"', "")
+            .replace('\'', ""),
+          translations: self.translations(&section, &["Name"]),
+          id,
+        })
+      })
+      .collect()
+  }
+
+  fn translations(&self, section: &str, keys: &[&str]) ->
+    HashMap<String, String>
+  {
+    let sec = self.section(section);
+    let mut map = HashMap::new();
+
+    for attr in sec.attrs() {
+      if !keys.contains(&attr.name) { continue }
+
+      for param in attr.params() {
+        map.insert(
+          format!("{}[{}]", attr.name, param.param_val),
+          param.value.to_string()
+        );
+      }
+    }
+
+    map
+  }
 }
 
 
@@ -535,3 +1985,132 @@ impl Display for ErrRequiresMut {
   }
 }
 
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn app(id: &str, name: &str) -> DesktopEntry {
+    DesktopEntry {
+      desktop_id: id.into(),
+      name: name.into(),
+      ..Default::default()
+    }
+  }
+
+  fn ranked(
+    entries: &[DesktopEntry],
+    query: &str,
+    usage: &[(&str, u32)]
+  ) -> Vec<String> {
+    let frecency = |id: &str| usage
+      .iter()
+      .find(|(u, _)| *u == id)
+      .map(|(_, f)| *f)
+      .unwrap_or(0);
+
+    rank_entries(
+      entries.iter().cloned(),
+      &search_words(query),
+      frecency
+    )
+      .into_iter()
+      .map(|h| h.desktop_id)
+      .collect()
+  }
+
+  #[test]
+  fn weighs_fields() {
+    let entries = [
+      DesktopEntry {
+        keywords: vec!["terminal".into()],
+        ..app("keyword", "Shell")
+      },
+      DesktopEntry {
+        generic_name: "Terminal Emulator".into(),
+        ..app("generic", "Kitty")
+      },
+      DesktopEntry {
+        exec: "/usr/bin/terminal --new".into(),
+        ..app("exec", "Console")
+      },
+      app("name", "Terminal"),
+      app("other", "Files"),
+    ];
+
+    assert_eq!(ranked(&entries, "terminal", &[]), [
+      "name", "generic", "keyword", "exec"
+    ]);
+  }
+
+  #[test]
+  fn ignores_case_and_accents() {
+    let entries = [app("cafe", "Café Menu")];
+    assert_eq!(ranked(&entries, "CAFE", &[]), ["cafe"]);
+    assert_eq!(ranked(&entries, "cafe tea", &[]),
+      Vec::<String>::new());
+  }
+
+  #[test]
+  fn usage_breaks_ties_only() {
+    let entries = [
+      app("a", "Writer"),
+      app("b", "Writer"),
+      DesktopEntry {
+        keywords: vec!["writer".into()],
+        ..app("c", "Notes")
+      },
+    ];
+
+    // Used a lot, c still can't pass a name match
+    let usage = [("b", 100), ("c", 100_000)];
+    assert_eq!(ranked(&entries, "writer", &usage), [
+      "b", "a", "c"
+    ]);
+  }
+
+  #[test]
+  fn empty_query_ranks_by_usage() {
+    let entries = [app("a", "A"), app("b", "B"), app("c", "C")];
+    let usage = [("b", 10), ("c", 300)];
+
+    assert_eq!(ranked(&entries, "", &usage), ["c", "b", "a"]);
+  }
+
+  #[test]
+  fn localizes_translatable_keys() {
+    let entry = DesktopEntry {
+      name: "Files".into(),
+      generic_name: "File Manager".into(),
+      description: "Browse files".into(),
+      keywords: vec!["folder".into()],
+      actions: vec![DesktopAction {
+        name: "New Window".into(),
+        translations: HashMap::from([
+          ("Name[pt]".into(), "Nova janela".into())
+        ]),
+        ..Default::default()
+      }],
+      translations: HashMap::from([
+        ("Name[pt_BR]".into(), "Arquivos".into()),
+        ("Name[pt]".into(), "Ficheiros".into()),
+        ("Comment[pt]".into(), "Navegar".into()),
+        ("Keywords[pt]".into(), "pasta;diretório;".into()),
+      ]),
+      ..Default::default()
+    };
+
+    let br = entry.localized("pt_BR.UTF-8");
+    assert_eq!(br.name, "Arquivos");
+    assert_eq!(br.description, "Navegar");
+    assert_eq!(br.generic_name, "File Manager");
+    assert_eq!(br.keywords, ["pasta", "diretório"]);
+    assert_eq!(br.actions[0].name, "Nova janela");
+    assert!(br.translations.is_empty());
+
+    assert_eq!(entry.localized("pt_PT").name, "Ficheiros");
+    assert_eq!(entry.localized("de_DE").name, "Files");
+    assert_eq!(entry.localized("C").name, "Files");
+  }
+}
@@ -1,15 +1,19 @@
 use std::path::{Path, PathBuf};
-use std::collections::HashSet;
-use std::cmp::Ordering;
-use std::ffi::OsStr;
 use std::env;
 
-use zbus::interface;
-use tokio::task::JoinSet;
+use zbus::{interface, fdo};
+use tokio::{sync::Semaphore, task::spawn_blocking};
 use ini::configparser::ini::Ini;
-use walkdir::{WalkDir, DirEntry};
+use walkdir::WalkDir;
+
+use i_slint_core::graphics::Image;
 
 use crate::dconf;
+use super::apps::{
+  SerialPixelBuffer,
+  SNAP_EXPORT_DIR,
+  flatpak_export_dirs
+};
 
 
 
@@ -71,7 +75,7 @@ impl IconsObject {
       return icn
     }
 
-    match get_icon(&name.to_string()).await {
+    match get_icon(name).await {
       Some(result) => {
         self.cache_set(name, result.clone());
         result
@@ -79,164 +83,468 @@ impl IconsObject {
       None => String::from("")
     }
   }
+
+  /// Looks up a symbolic icon and renders it at `size`,
+  /// recolored with the given CSS colors the same way
+  /// GTK does for `-symbolic` SVGs.
+  pub async fn get_symbolic_icon(
+    &mut self,
+    name: &str,
+    size: u32,
+    fg: &str,
+    success: &str,
+    warning: &str,
+    error: &str
+  ) -> fdo::Result<SerialPixelBuffer> {
+    let colors = SymbolicColors::new(
+      fg, success, warning, error
+    ).ok_or(fdo::Error::InvalidArgs(
+      "Invalid symbolic icon color".to_string()))?;
+
+    let name = match name.ends_with("-symbolic") {
+      true => name.to_string(),
+      false => format!("{name}-symbolic")
+    };
+
+    let path = self.get_icon(name.as_str()).await;
+    if path.is_empty() {
+      return Err(fdo::Error::FileNotFound(
+        format!("No icon found for {name}")))
+    }
+
+    let res = spawn_blocking(move || {
+      render_symbolic(&PathBuf::from(path), size, &colors)
+    }).await;
+
+    match res {
+      Ok(Some(buffer)) => Ok(buffer),
+      _ => Err(fdo::Error::Failed(
+        format!("Failed to render icon {name}")))
+    }
+  }
 }
 
 
 
-fn get_icon_themes() {
-  let home_dir = std::env::var("HOME").unwrap();
-  let xdg_data_home = std::env::var("XDG_DATA_HOME")
-    .unwrap_or(format!("{home_dir}/.local/share"));
+/// Size raster icons are picked for when the caller has
+/// none in mind
+pub const ICON_SIZE: u32 = 48;
 
-  let search_dirs: HashSet<String> = HashSet::from([
-    "/usr/share/icons".to_string(),
-    format!("{home_dir}/.icons"),
-    format!("{xdg_data_home}/icons"),
-  ]);
+pub async fn get_icon(icon_name: &str) -> Option<String> {
+  get_first_icon(&[icon_name.to_string()]).await
 }
 
-pub async fn get_icon(icon_name: &String) -> Option<String> {
-  let lookup_dirs = get_lookup_dirs();
+/// Resolves the first of `icon_names` that has an icon,
+/// looking through the whole theme before the fallback
+/// dirs. Absolute paths are taken as they are.
+pub async fn get_first_icon(
+  icon_names: &[String]
+) -> Option<String> {
+  let mut candidates: Vec<String> = vec![];
+  let mut absolute: Option<String> = None;
+
+  for name in icon_names {
+    if name.starts_with('/') {
+      match Path::new(name).is_file() {
+        true => { absolute = Some(name.clone()); break },
+        false => continue
+      }
+    }
+
+    for candidate in icon_candidates(name) {
+      if !candidates.contains(&candidate) {
+        candidates.push(candidate);
+      }
+    }
+  }
 
-  match icon_lookup(icon_name, lookup_dirs).await {
-    Some(icn) => Some(icn),
-    _ => icon_lookup(icon_name, get_backup_dirs()).await
+  if !candidates.is_empty() {
+    let found = match icon_lookup(
+      &candidates,
+      get_lookup_dirs(),
+      ICON_SIZE
+    ).await {
+      Some(icn) => Some(icn),
+      _ => icon_lookup(
+        &candidates,
+        get_backup_dirs(),
+        ICON_SIZE
+      ).await
+    };
+
+    if found.is_some() { return found }
   }
+
+  absolute
 }
 
-async fn icon_lookup(
-  icon_name: &String,
-  dirs: Vec<PathBuf>,
-) -> Option<String> {
-  let mut lookup_set = JoinSet::new();
+/// Expands an icon name into the names to try, in order.
+///
+/// Symbolic names fall back to their generic symbolic
+/// parents before any full-color name, as GTK does, so
+/// `a-b-symbolic` yields `a-b-symbolic`, `a-symbolic`,
+/// `a-b` and `a`.
+pub fn icon_candidates(icon_name: &str) -> Vec<String> {
+  let mut base = match icon_name.strip_suffix("-symbolic") {
+    Some(base) => base,
+    None => return vec![icon_name.to_string()]
+  };
 
-  let sort_fn = |a: &DirEntry, b: &DirEntry| -> Ordering {
-    let ext_a = a.path().extension()
-      .unwrap_or(OsStr::new(""));
-    let ext_b = b.path().extension()
-      .unwrap_or(OsStr::new(""));
+  let mut parents = vec![];
 
-    if ext_a == ext_b {
-      return a.file_name().cmp(b.file_name())
-    }
+  loop {
+    parents.push(base);
 
-    if ext_a == "svg" { return Ordering::Less }
-    if ext_b == "svg" { return Ordering::Greater }
+    match base.rfind('-') {
+      Some(i) => base = &base[..i],
+      None => break
+    }
+  }
 
-    a.file_name().cmp(b.file_name())
-  };
+  parents
+    .iter()
+    .map(|p| format!("{p}-symbolic"))
+    .chain(parents.iter().map(|p| p.to_string()))
+    .collect()
+}
 
+// Bounds how many directory walks can hold a blocking
+// thread at once, across every lookup in flight
+static LOOKUP_PERMITS: Semaphore = Semaphore::const_new(4);
+
+/// Looks up the first of `names` found in `dirs`, raster
+/// icons as close to `size` as there are.
+///
+/// Each directory is walked once on a blocking thread,
+/// and results are picked in the order of `names`, then
+/// `dirs`, so the answer doesn't depend on which walk
+/// finishes first.
+pub async fn icon_lookup(
+  names: &[String],
+  dirs: Vec<PathBuf>,
+  size: u32
+) -> Option<String> {
+  let mut walks = vec![];
 
   for dir in dirs {
-    let walker = WalkDir::new(dir)
-      .contents_first(true)
-      .sort_by(sort_fn)
-      .follow_links(true);
-
-    for entry in walker {
-      let path: PathBuf = match entry {
-        Ok(v) => v.into_path(),
-        _ => continue
-      };
+    let names = names.to_vec();
 
-      let icon = icon_name.clone();
+    walks.push(tokio::spawn(async move {
+      let _permit = LOOKUP_PERMITS.acquire().await.ok()?;
 
-      lookup_set.spawn_blocking(move || {
-        let stem = path.file_stem()?
-          .to_string_lossy().to_string();
+      spawn_blocking(move || {
+        scan_icon_dir(&dir, &names, size)
+      })
+        .await
+        .ok()
+    }));
+  }
 
-        match stem == icon {
-          true => Some(path.to_string_lossy().to_string()),
-          _ => None
-        }
-      });
+  let mut found: Vec<Vec<Option<PathBuf>>> = vec![];
+  for walk in walks {
+    if let Ok(Some(matches)) = walk.await {
+      found.push(matches);
     }
   }
 
-  while let Some(res) = lookup_set.join_next().await {
-    if res.is_err() { continue }
+  (0..names.len())
+    .find_map(|i| found.iter().find_map(|m| m[i].clone()))
+    .map(|p| p.to_string_lossy().to_string())
+}
+
+// Lower is better; anything else isn't an icon
+fn icon_ext_rank(path: &Path) -> Option<u8> {
+  match path.extension()?.to_str()? {
+    "svg" => Some(0),
+    "png" => Some(1),
+    "xpm" => Some(2),
+    _ => None
+  }
+}
+
+/// Nominal size of an icon from its size dir, like
+/// `48x48`, `48x48@2` or `48`
+fn icon_dir_size(dir: &Path, path: &Path) -> Option<u32> {
+  let rel = path.strip_prefix(dir).ok()?.parent()?;
+
+  rel.components().find_map(|c| {
+    let c = c.as_os_str().to_str()?;
+    let c = c.split('@').next()?;
 
-    if let Some(icon) = res.unwrap() {
-      lookup_set.abort_all();
-      return Some(icon)
+    match c.split_once('x') {
+      Some((w, h)) if w == h => w.parse().ok(),
+      Some(_) => None,
+      None => c.parse().ok()
+    }
+  })
+}
+
+/// Walks `dir` once and returns, for each of `names`, the
+/// preferred file for it: SVG before PNG before XPM, raster
+/// ones by how close their size dir is to `size` (larger
+/// first on a tie), and the first one in file name order
+/// among equals.
+fn scan_icon_dir(
+  dir: &Path,
+  names: &[String],
+  size: u32
+) -> Vec<Option<PathBuf>> {
+  // Extension, distance from `size`, and smaller than it
+  type Rank = (u8, u32, bool);
+
+  let mut best: Vec<Option<(Rank, PathBuf)>> =
+    vec![None; names.len()];
+
+  let walker = WalkDir::new(dir)
+    .sort_by_file_name()
+    .follow_links(true);
+
+  for entry in walker {
+    let path: PathBuf = match entry {
+      Ok(v) if v.file_type().is_file() => v.into_path(),
+      _ => continue
+    };
+
+    let rank = match icon_ext_rank(&path) {
+      Some(r) => r,
+      None => continue
+    };
+
+    let stem = match path.file_stem() {
+      Some(s) => s.to_string_lossy(),
+      None => continue
+    };
+
+    let i = match names.iter().position(|n| *n == stem) {
+      Some(i) => i,
+      None => continue
+    };
+
+    // Scalable ones fit any size
+    let rank = match (rank, icon_dir_size(dir, &path)) {
+      (0, _) => (0, 0, false),
+      (r, Some(s)) => (r, s.abs_diff(size), s < size),
+      (r, None) => (r, u32::MAX, true)
+    };
+
+    match &best[i] {
+      Some((r, _)) if *r <= rank => (),
+      _ => best[i] = Some((rank, path))
     }
   }
 
-  None
+  best.into_iter().map(|b| b.map(|(_, p)| p)).collect()
+}
+
+/* -
+ * -> Symbolic icons
+ * -
+*/
+
+struct SymbolicColors {
+  fg: String,
+  success: String,
+  warning: String,
+  error: String,
 }
 
-pub fn get_icon_sync(icon_name: &String) -> Option<String> {
-  let lookup_dirs = get_lookup_dirs();
+impl SymbolicColors {
+  fn new(
+    fg: &str,
+    success: &str,
+    warning: &str,
+    error: &str
+  ) -> Option<Self> {
+    Some(Self {
+      fg: css_color(fg)?,
+      success: css_color(success)?,
+      warning: css_color(warning)?,
+      error: css_color(error)?,
+    })
+  }
 
-  match icon_lookup_sync(icon_name, lookup_dirs) {
-    Some(icn) => Some(icn),
-    _ => icon_lookup_sync(icon_name, get_backup_dirs())
+  fn stylesheet(&self) -> String {
+    format!(concat!(
+      "<style type=\"text/css\">",
+      "rect,circle,path {{ fill: {} }} ",
+      ".success {{ fill: {} }} ",
+      ".warning {{ fill: {} }} ",
+      ".error {{ fill: {} }}",
+      "</style>"
+    ), self.fg, self.success, self.warning, self.error)
   }
 }
 
-fn icon_lookup_sync(
-  icon_name: &String,
-  dirs: Vec<PathBuf>
-) -> Option<String> {
-  let sort_fn = |a: &DirEntry, b: &DirEntry| -> Ordering {
-    let ext_a = a.path().extension()
-      .unwrap_or(OsStr::new(""));
-    let ext_b = b.path().extension()
-      .unwrap_or(OsStr::new(""));
-
-    if ext_a == ext_b {
-      return a.file_name().cmp(b.file_name())
-    }
+// Only accepts what can appear in a CSS color value, so
+// callers can't break out of the injected stylesheet
+fn css_color(value: &str) -> Option<String> {
+  let value = value.trim();
+  let valid = !value.is_empty() && value.chars().all(|c|
+    c.is_ascii_alphanumeric() ||
+    matches!(c, '#' | '(' | ')' | ',' | '.' | '%' | ' '));
+
+  match valid {
+    true => Some(value.to_string()),
+    false => None
+  }
+}
 
-    if ext_a == "svg" { return Ordering::Less }
-    if ext_b == "svg" { return Ordering::Greater }
+fn render_symbolic(
+  path: &Path,
+  size: u32,
+  colors: &SymbolicColors
+) -> Option<SerialPixelBuffer> {
+  // Full-color fallbacks keep their own colors
+  let symbolic = path
+    .file_stem()
+    .is_some_and(|s| s.to_string_lossy().ends_with("-symbolic"));
+
+  let img = match path.extension() {
+    Some(ext) if ext == "svg" => {
+      let svg = std::fs::read_to_string(path).ok()?;
+      let svg = match symbolic {
+        true => recolor_svg(&svg, size, colors)?,
+        false => {
+          let (root, body) = resize_svg(&svg, size)?;
+          format!("{root}{body}")
+        }
+      };
 
-    a.file_name().cmp(b.file_name())
+      Image::load_from_svg_data(svg.as_bytes()).ok()?
+    },
+    _ => Image::load_from_path(path).ok()?
   };
 
+  Some(img.to_rgba8_premultiplied()?.into())
+}
 
-  for dir in dirs {
-    let walker = WalkDir::new(dir)
-      .contents_first(true)
-      .sort_by(sort_fn)
-      .follow_links(false);
-
-    for entry in walker {
-      let path: PathBuf = match entry {
-        Ok(v) => v.into_path(),
-        _ => continue
-      };
+/// Injects the symbolic stylesheet right after the root
+/// `<svg>` tag and resizes the root to `size`.
+fn recolor_svg(
+  svg: &str,
+  size: u32,
+  colors: &SymbolicColors
+) -> Option<String> {
+  let (root, body) = resize_svg(svg, size)?;
 
-      let stem = path.file_stem()?
-        .to_string_lossy().to_string();
-      println!("checking: {}", stem);
+  Some(format!("{}{}{}",
+    root,
+    colors.stylesheet(),
+    strip_inline_fills(body)
+  ))
+}
 
-      if stem == *icon_name {
-        return Some(path.to_string_lossy().to_string())
-      }
-    }
+/// Resizes the root `<svg>` tag to `size`, splitting the
+/// document after it
+fn resize_svg(svg: &str, size: u32) -> Option<(String, &str)> {
+  let start = svg.find("<svg")?;
+  let end = start + svg[start..].find('>')? + 1;
+  let root = &svg[start..end];
+
+  if root.ends_with("/>") { return None }
+
+  let width = svg_attr(root, "width");
+  let height = svg_attr(root, "height");
+  let mut new_root = strip_svg_attr(
+    &strip_svg_attr(root, "width"), "height");
+
+  if svg_attr(root, "viewBox").is_none() {
+    let w = svg_length(width.unwrap_or("16"));
+    let h = svg_length(height.unwrap_or("16"));
+    new_root = new_root.replacen("<svg",
+      &format!("<svg viewBox=\"0 0 {w} {h}\""), 1);
   }
 
-  None
+  new_root = new_root.replacen("<svg",
+    &format!("<svg width=\"{size}\" height=\"{size}\""),
+    1);
+
+  Some((format!("{}{}", &svg[..start], new_root), &svg[end..]))
+}
+
+fn svg_length(value: &str) -> f32 {
+  value
+    .trim()
+    .trim_end_matches("px")
+    .parse()
+    .unwrap_or(16.0)
+}
+
+// Finds `attr` in `tag`, making sure it is a whole
+// attribute name (i.e: not `stroke-width`), and returns
+// where it starts along with the bounds of its value
+fn svg_attr_span(
+  tag: &str,
+  attr: &str
+) -> Option<(usize, usize, usize)> {
+  tag.match_indices(attr)
+    .map(|(i, _)| i)
+    .filter(|&i| tag[..i].ends_with(char::is_whitespace))
+    .find_map(|i| {
+      let rest = &tag[i + attr.len()..];
+      let value = rest.trim_start()
+        .strip_prefix('=')?
+        .trim_start();
+
+      let quote = value.chars()
+        .next()
+        .filter(|&q| q == '"' || q == '\'')?;
+
+      let start = tag.len() - value.len() + 1;
+      let end = start + tag[start..].find(quote)?;
+
+      Some((i, start, end))
+    })
 }
 
-// TODO: Check for supported icon types by looking
-// at the file extension
-fn file_matches_icon(
-  path: impl Into<PathBuf>,
-  icon: impl Into<String>
-) -> bool {
-  let path: PathBuf = path.into();
-  let icon: String = icon.into();
+fn svg_attr<'a>(tag: &'a str, attr: &str) -> Option<&'a str> {
+  let (_, start, end) = svg_attr_span(tag, attr)?;
+  Some(&tag[start..end])
+}
 
-  if path.exists() && path.is_file() {
-    let stem = path.file_stem().unwrap();
-    let stem_str: String = stem.to_string_lossy().into();
+fn strip_svg_attr(tag: &str, attr: &str) -> String {
+  match svg_attr_span(tag, attr) {
+    Some((i, _, end)) => format!("{}{}",
+      tag[..i].trim_end(), &tag[end + 1..]),
+    None => tag.to_string()
+  }
+}
 
-    return stem_str == icon;
+// Inline `style` declarations take precedence over the
+// injected stylesheet, so their fills have to go
+fn strip_inline_fills(svg: &str) -> String {
+  let mut out = String::with_capacity(svg.len());
+  let mut rest = svg;
+
+  while let Some(i) = rest.find("style=") {
+    let (head, tail) = rest.split_at(i + "style=".len());
+    out.push_str(head);
+
+    let quote = match tail.chars().next() {
+      Some(q @ ('"' | '\'')) => q,
+      _ => { rest = tail; continue }
+    };
+
+    let value_end = match tail[1..].find(quote) {
+      Some(j) => j + 1,
+      None => { rest = tail; continue }
+    };
+
+    let style: Vec<&str> = tail[1..value_end]
+      .split(';')
+      .filter(|decl| {
+        let name = decl.split(':').next().unwrap_or("");
+        name.trim() != "fill"
+      })
+      .collect();
+
+    out.push(quote);
+    out.push_str(&style.join(";"));
+    rest = &tail[value_end..];
   }
 
-  false
+  out.push_str(rest);
+  out
 }
 
 fn get_current_theme() -> String {
@@ -257,7 +565,14 @@ fn get_backup_dirs() -> Vec<PathBuf> {
     PathBuf::from("/usr/share/pixmaps")
   ];
 
+  // Icons exported by Flatpak and Snap apps
+  let sandboxed = flatpak_export_dirs()
+    .into_iter()
+    .map(|d| d.join("icons/hicolor"))
+    .chain([Path::new(SNAP_EXPORT_DIR).join("icons")]);
+
   dirs.into_iter()
+    .chain(sandboxed)
     .filter(|p| p.exists())
     .collect()
 }
@@ -268,8 +583,124 @@ fn get_lookup_dirs() -> Vec<PathBuf> {
 
   vec![
     PathBuf::from(
-      format!("{xdg_home}/.local/share/{icon_theme}")
+      format!("{xdg_home}/.local/share/icons/{icon_theme}")
     ),
     PathBuf::from(format!("/usr/share/icons/{icon_theme}"))
   ].into_iter().filter(|p| p.exists()).collect()
 }
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn colors() -> SymbolicColors {
+    SymbolicColors::new(
+      "#eeeeee", "rgb(0, 200, 0)", "orange", "#f00"
+    ).unwrap()
+  }
+
+  #[test]
+  fn symbolic_parents_before_full_color() {
+    assert_eq!(icon_candidates("a-b-c-symbolic"), [
+      "a-b-c-symbolic",
+      "a-b-symbolic",
+      "a-symbolic",
+      "a-b-c",
+      "a-b",
+      "a"
+    ]);
+
+    assert_eq!(icon_candidates("firefox"), ["firefox"]);
+  }
+
+  #[test]
+  fn injects_stylesheet_after_root() {
+    let svg = concat!(
+      "<?xml version=\"1.0\"?>",
+      "<svg xmlns=\"http://www.w3.org/2000/svg\" ",
+      "width=\"16\" height=\"16\">",
+      "<path d=\"M0 0h16v16H0z\"/></svg>"
+    );
+
+    let recolored = recolor_svg(svg, 32, &colors()).unwrap();
+    let root_end = recolored.find('>').unwrap() + 1;
+    let root_end = root_end + recolored[root_end..]
+      .find('>').unwrap() + 1;
+
+    let root = &recolored[..root_end];
+    assert!(root.starts_with("<?xml version=\"1.0\"?><svg "));
+    assert!(root.contains("width=\"32\" height=\"32\""));
+    assert!(root.contains("viewBox=\"0 0 16 16\""));
+    assert!(!root.contains("width=\"16\""));
+
+    assert!(recolored[root_end..].starts_with(
+      &colors().stylesheet()));
+    assert!(recolored.ends_with("<path d=\"M0 0h16v16H0z\"/></svg>"));
+  }
+
+  #[test]
+  fn keeps_view_box_and_other_widths() {
+    let svg = concat!(
+      "<svg viewBox=\"0 0 24 24\" stroke-width=\"2\" ",
+      "width=\"24\"></svg>"
+    );
+
+    let (root, body) = resize_svg(svg, 48).unwrap();
+    assert_eq!(root, concat!(
+      "<svg width=\"48\" height=\"48\" viewBox=\"0 0 24 24\" ",
+      "stroke-width=\"2\">"
+    ));
+    assert_eq!(body, "</svg>");
+  }
+
+  #[test]
+  fn rejects_self_closing_root() {
+    assert!(recolor_svg("<svg width=\"16\"/>", 16, &colors())
+      .is_none());
+    assert!(recolor_svg("<path/>", 16, &colors()).is_none());
+  }
+
+  #[test]
+  fn strips_only_inline_fills() {
+    let svg = concat!(
+      "<path style=\"fill:#123456;opacity:0.5\"/>",
+      "<rect style='stroke:red; fill : blue'/>",
+      "<circle fill-rule=\"evenodd\" style=\"fill-opacity:1\"/>"
+    );
+
+    assert_eq!(strip_inline_fills(svg), concat!(
+      "<path style=\"opacity:0.5\"/>",
+      "<rect style='stroke:red'/>",
+      "<circle fill-rule=\"evenodd\" style=\"fill-opacity:1\"/>"
+    ));
+
+    // Unquoted or unterminated values are left alone
+    assert_eq!(strip_inline_fills("<a style=x>"), "<a style=x>");
+    assert_eq!(
+      strip_inline_fills("<a style=\"fill:red>"),
+      "<a style=\"fill:red>");
+  }
+
+  #[test]
+  fn rejects_colors_escaping_the_stylesheet() {
+    assert_eq!(css_color(" #a0b1c2 "), Some("#a0b1c2".into()));
+    assert_eq!(
+      css_color("rgba(10, 20, 30, 0.5)"),
+      Some("rgba(10, 20, 30, 0.5)".into()));
+
+    for color in [
+      "",
+      "   ",
+      "red; } * { fill: blue",
+      "red</style><script>",
+      "url(\"x\")",
+      "#f\nff"
+    ] {
+      assert_eq!(css_color(color), None, "{color:?}");
+    }
+
+    assert!(SymbolicColors::new("#fff", "#0f0", "#ff0", "}")
+      .is_none());
+  }
+}
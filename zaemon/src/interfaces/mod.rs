@@ -1,11 +1,17 @@
 pub mod apps;
 pub mod icons;
 pub mod tablet;
+pub mod screen;
+pub mod nightlight;
+pub mod wallpaper;
 pub mod hyprland;
 pub mod notification;
 
 pub use apps::*;
 pub use icons::*;
 pub use tablet::*;
+pub use screen::*;
+pub use nightlight::*;
+pub use wallpaper::*;
 pub use hyprland::*;
 pub use notification::*;
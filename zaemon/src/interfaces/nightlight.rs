@@ -0,0 +1,477 @@
+use std::error::Error;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::env::var;
+use std::io::{self, ErrorKind as IOErr};
+
+use chrono::{DateTime, Datelike, Local, Timelike};
+
+use zbus::{
+  fdo,
+  interface,
+  zvariant,
+  object_server::SignalEmitter
+};
+
+use tokio::{
+  process::Command,
+  time::{sleep, Duration}
+};
+
+use crate::utils::hyprctl;
+use crate::utils::json::{load_json, save_json};
+use crate::utils::sun::{sun_times, SunTimes};
+
+
+
+/// Temperature at which colors are left untouched
+pub const NEUTRAL_TEMPERATURE: u32 = 6500;
+
+/// How often the schedule gets checked, short enough for
+/// transitions to look smooth
+const TICK: Duration = Duration::from_secs(30);
+
+/// Temperatures get rounded to steps of this many kelvins,
+/// so transitions don't hit hyprsunset every tick
+const TEMPERATURE_STEP: u32 = 50;
+
+
+#[derive(
+  serde::Deserialize,
+  serde::Serialize,
+  zvariant::Type,
+  Default,
+  Debug,
+  Clone,
+  Copy,
+  PartialEq,
+  Eq
+)]
+#[serde(rename_all = "lowercase")]
+#[zvariant(signature = "s")]
+pub enum ScheduleMode {
+  /// From sunset to sunrise at the configured location
+  #[default]
+  Sun,
+  /// Between two fixed local times
+  Fixed,
+}
+
+
+/// When the night light comes on and how warm it gets
+#[derive(
+  serde::Deserialize,
+  serde::Serialize,
+  zvariant::Type,
+  Debug,
+  Clone,
+  PartialEq
+)]
+#[serde(default)]
+pub struct NightLightConfig {
+  pub enabled: bool,
+  pub mode: ScheduleMode,
+  /// Degrees, north positive, for `Sun`
+  pub latitude: f64,
+  /// Degrees, east positive, for `Sun`
+  pub longitude: f64,
+  /// Minutes since local midnight, for `Fixed`
+  pub night_start: u32,
+  pub night_end: u32,
+  /// Night temperature in kelvins
+  pub temperature: u32,
+  /// Minutes it takes to fade in and out
+  pub transition: u32,
+}
+
+impl Default for NightLightConfig {
+  fn default() -> Self {
+    Self {
+      enabled: false,
+      mode: ScheduleMode::Sun,
+      latitude: 0.0,
+      longitude: 0.0,
+      night_start: 20 * 60,
+      night_end: 7 * 60,
+      temperature: 4000,
+      transition: 30
+    }
+  }
+}
+
+impl NightLightConfig {
+  /// How far into the night `now` is, from 0 for day to 1
+  /// for full night, and whether the last switch was to
+  /// night
+  pub fn night_level(&self, now: &DateTime<Local>) ->
+    (f64, bool)
+  {
+    let minute = (now.hour() * 60 + now.minute()) as f64
+      + now.second() as f64 / 60.0;
+
+    let (dusk, dawn) = match self.mode {
+      ScheduleMode::Fixed => (
+        self.night_start as f64,
+        self.night_end as f64
+      ),
+      ScheduleMode::Sun => {
+        let offset =
+          now.offset().local_minus_utc() as f64 / 60.0;
+
+        match sun_times(
+          self.latitude, self.longitude, now.ordinal())
+        {
+          SunTimes::Rises { sunrise, sunset } =>
+            (sunset + offset, sunrise + offset),
+          SunTimes::AlwaysUp => return (0.0, false),
+          SunTimes::AlwaysDown => return (1.0, true)
+        }
+      }
+    };
+
+    let since_dusk = (minute - dusk).rem_euclid(1440.0);
+    let since_dawn = (minute - dawn).rem_euclid(1440.0);
+
+    let faded = |since: f64| match self.transition {
+      0 => 1.0,
+      t => (since / t as f64).min(1.0)
+    };
+
+    match since_dusk < since_dawn {
+      true => (faded(since_dusk), true),
+      false => (1.0 - faded(since_dawn), false)
+    }
+  }
+
+  /// Temperature for a night level from `night_level`
+  pub fn temperature_at(&self, level: f64) -> u32 {
+    let day = NEUTRAL_TEMPERATURE as f64;
+    let night = self.temperature as f64;
+
+    let kelvins = day + (night - day) * level;
+    let steps = (kelvins / TEMPERATURE_STEP as f64).round();
+
+    steps as u32 * TEMPERATURE_STEP
+  }
+
+  fn validate(&self) -> fdo::Result<()> {
+    let invalid = |msg: &str| Err(
+      fdo::Error::InvalidArgs(msg.into()));
+
+    if !(-90.0..=90.0).contains(&self.latitude) {
+      return invalid("latitude must be within -90 and 90")
+    }
+
+    if !(-180.0..=180.0).contains(&self.longitude) {
+      return invalid("longitude must be within -180 and 180")
+    }
+
+    if self.night_start >= 1440 || self.night_end >= 1440 {
+      return invalid("times must be minutes within a day")
+    }
+
+    if !(1000..=20000).contains(&self.temperature) {
+      return invalid(
+        "temperature must be within 1000 and 20000")
+    }
+
+    if self.transition > 720 {
+      return invalid("transition can't exceed 12 hours")
+    }
+
+    Ok(())
+  }
+}
+
+
+pub(crate) struct NightLightInterface {
+  config: NightLightConfig,
+  /// Phase the override was turned on in, it ends with
+  /// that phase
+  override_phase: Option<bool>,
+  /// Whether it's night by the schedule
+  night: bool,
+  temperature: u32,
+}
+
+#[interface(name = "org.hypr.Hyprmaster.NightLight")]
+impl NightLightInterface {
+  fn config(&self) -> NightLightConfig {
+    self.config.clone()
+  }
+
+  /// Replaces and persists the schedule, applying it
+  /// right away
+  async fn set_config(
+    &mut self,
+    #[zbus(signal_emitter)]
+    emitter: SignalEmitter<'_>,
+    config: NightLightConfig
+  ) -> fdo::Result<()> {
+    config.validate()?;
+
+    save_json(&config_path(), &config)
+      .map_err(|e| fdo::Error::IOError(e.to_string()))?;
+
+    self.config = config;
+    self.update(&emitter).await
+  }
+
+  /// Current color temperature in kelvins
+  #[zbus(property)]
+  fn temperature(&self) -> u32 {
+    self.temperature
+  }
+
+  /// Whether the night light is on right now
+  #[zbus(property)]
+  fn active(&self) -> bool {
+    self.config.enabled &&
+      self.night != self.override_phase.is_some()
+  }
+
+  /// Flips the night light until the schedule switches
+  /// on its own, like turning it off until sunrise
+  #[zbus(property)]
+  fn overridden(&self) -> bool {
+    self.override_phase.is_some()
+  }
+
+  #[zbus(property)]
+  async fn set_overridden(
+    &mut self,
+    #[zbus(signal_emitter)]
+    emitter: SignalEmitter<'_>,
+    value: bool
+  ) -> fdo::Result<()> {
+    if !self.config.enabled && value {
+      return Err(fdo::Error::Failed(
+        "The night light is disabled".into()))
+    }
+
+    self.override_phase = value.then_some(self.night);
+    self.update(&emitter).await
+  }
+}
+
+impl NightLightInterface {
+  pub fn new() -> Self {
+    Self {
+      config: load_json(&config_path()),
+      override_phase: None,
+      night: false,
+      temperature: NEUTRAL_TEMPERATURE
+    }
+  }
+
+  /// Moves the temperature to where the schedule has it
+  async fn update(
+    &mut self,
+    emitter: &SignalEmitter<'_>
+  ) -> fdo::Result<()> {
+    let was_active = self.active();
+    let had_override = self.override_phase.is_some();
+
+    let (level, night) = self.config
+      .night_level(&Local::now());
+
+    self.night = night;
+    if !self.config.enabled ||
+      self.override_phase.is_some_and(|p| p != night)
+    {
+      self.override_phase = None;
+    }
+
+    let level = match self.override_phase {
+      Some(_) if night => 0.0,
+      Some(_) => 1.0,
+      None => level
+    };
+
+    let temperature = match self.config.enabled {
+      true => self.config.temperature_at(level),
+      false => NEUTRAL_TEMPERATURE
+    };
+
+    if temperature != self.temperature {
+      set_temperature(temperature).await
+        .map_err(|e| fdo::Error::Failed(format!(
+          "hyprsunset: {e}")))?;
+
+      self.temperature = temperature;
+      self.temperature_changed(emitter).await?;
+    }
+
+    if had_override != self.override_phase.is_some() {
+      self.overridden_changed(emitter).await?;
+    }
+
+    if was_active != self.active() {
+      self.active_changed(emitter).await?;
+    }
+
+    Ok(())
+  }
+
+  /// Follows the schedule for as long as the daemon runs
+  pub async fn run(conn: &zbus::Connection) ->
+    Result<(), Box<dyn Error>>
+  {
+    let iface = conn.object_server()
+      .interface::<_, NightLightInterface>("/nightlight")
+      .await?;
+
+    loop {
+      let res = iface.get_mut().await
+        .update(iface.signal_emitter()).await;
+
+      if let Err(e) = res {
+        eprintln!("Failed to update night light: {:#?}", e);
+      }
+
+      sleep(TICK).await;
+    }
+  }
+}
+
+
+
+/// `$XDG_CONFIG_HOME/hyprmaster/night-light.json`
+pub fn config_path() -> PathBuf {
+  let home_dir = var("HOME").unwrap();
+  let xdg_conf_dir = var("XDG_CONFIG_HOME")
+    .unwrap_or(format!("{home_dir}/.config"));
+
+  PathBuf::from(format!(
+    "{xdg_conf_dir}/hyprmaster/night-light.json"))
+}
+
+/// Sets the temperature through hyprsunset, starting it
+/// when it isn't running yet
+async fn set_temperature(temperature: u32) ->
+  io::Result<()>
+{
+  let cmd = match temperature {
+    NEUTRAL_TEMPERATURE => "identity".to_string(),
+    t => format!("temperature {t}")
+  };
+
+  let reply = match hyprctl::hyprsunset(&cmd).await {
+    Err(e) if matches!(
+      e.kind(),
+      IOErr::NotFound | IOErr::ConnectionRefused
+    ) => {
+      // Nothing to undo without it
+      if temperature == NEUTRAL_TEMPERATURE {
+        return Ok(())
+      }
+
+      Command::new("hyprsunset")
+        .arg("--identity")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+      sleep(Duration::from_millis(500)).await;
+      hyprctl::hyprsunset(&cmd).await?
+    },
+    res => res?
+  };
+
+  match reply.trim() {
+    "ok" => Ok(()),
+    err => Err(io::Error::other(err))
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use chrono::TimeZone;
+
+  fn at(hour: u32, minute: u32) -> DateTime<Local> {
+    Local.with_ymd_and_hms(2023, 1, 10, hour, minute, 0)
+      .unwrap()
+  }
+
+  fn fixed(transition: u32) -> NightLightConfig {
+    NightLightConfig {
+      mode: ScheduleMode::Fixed,
+      night_start: 20 * 60,
+      night_end: 7 * 60,
+      transition,
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn fades_in_and_out() {
+    let config = fixed(30);
+
+    let table = [
+      (19, 59, 0.0, false),
+      (20, 0, 0.0, true),
+      (20, 15, 0.5, true),
+      (20, 30, 1.0, true),
+      (2, 0, 1.0, true),
+      (7, 0, 1.0, false),
+      (7, 15, 0.5, false),
+      (7, 30, 0.0, false),
+      (12, 0, 0.0, false),
+    ];
+
+    for (hour, minute, level, night) in table {
+      let (l, n) = config.night_level(&at(hour, minute));
+
+      assert!((l - level).abs() < 1e-9 && n == night,
+        "{hour}:{minute:02}: got ({l}, {n})");
+    }
+  }
+
+  #[test]
+  fn no_transition_switches_at_once() {
+    let config = fixed(0);
+
+    assert_eq!(config.night_level(&at(20, 0)), (1.0, true));
+    assert_eq!(config.night_level(&at(7, 0)), (0.0, false));
+  }
+
+  #[test]
+  fn polar_day_and_night() {
+    // Tromsø, where it stays dark all through January
+    let config = NightLightConfig {
+      latitude: 69.65,
+      longitude: 18.96,
+      ..Default::default()
+    };
+
+    assert_eq!(config.night_level(&at(12, 0)), (1.0, true));
+
+    let midsummer = Local.with_ymd_and_hms(2023, 6, 21, 0, 0, 0)
+      .unwrap();
+    assert_eq!(config.night_level(&midsummer), (0.0, false));
+  }
+
+  #[test]
+  fn rounds_temperatures() {
+    let config = NightLightConfig {
+      temperature: 4000,
+      ..Default::default()
+    };
+
+    let table = [
+      (0.0, 6500),
+      (1.0, 4000),
+      (0.5, 5250),
+      // 6192.5 and 5675 kelvins
+      (0.123, 6200),
+      (0.33, 5700),
+    ];
+
+    for (level, kelvins) in table {
+      assert_eq!(config.temperature_at(level), kelvins,
+        "level {level}");
+    }
+  }
+}
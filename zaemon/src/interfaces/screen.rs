@@ -0,0 +1,1508 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::PathBuf;
+use std::env::var;
+
+use zbus::{
+  fdo,
+  interface,
+  zvariant,
+  object_server::SignalEmitter
+};
+
+use tokio::{
+  task::JoinHandle,
+  time::{sleep, Duration},
+  sync::broadcast::error::RecvError
+};
+
+use super::{HyprReceiver, HyprlandEvent};
+use crate::utils::{cache, hyprctl};
+use crate::utils::scaling::{ScalingPlan, ScalingSettings};
+use crate::utils::profiles::{
+  LayoutProfile,
+  load_profiles,
+  save_profiles
+};
+
+pub use crate::utils::hyprctl::{
+  HyprMonitor,
+  HyprWorkspace,
+  HyprWorkspaceRule
+};
+pub use crate::utils::brightness::{
+  BrightnessControl,
+  DeviceRoots
+};
+pub use crate::utils::scaling::ScalingChange;
+
+
+
+/// A display mode, as in `1920x1080@60.00Hz`
+#[derive(
+  serde::Deserialize,
+  serde::Serialize,
+  zvariant::Type,
+  Default,
+  Debug,
+  Clone,
+  Copy,
+  PartialEq
+)]
+pub struct MonitorMode {
+  pub width: i32,
+  pub height: i32,
+  pub refresh_rate: f64,
+}
+
+impl MonitorMode {
+  pub fn parse(mode: &str) -> Option<Self> {
+    let (size, rate) = mode
+      .trim()
+      .trim_end_matches("Hz")
+      .split_once('@')?;
+    let (width, height) = size.split_once('x')?;
+
+    Some(Self {
+      width: width.parse().ok()?,
+      height: height.parse().ok()?,
+      refresh_rate: rate.parse().ok()?
+    })
+  }
+}
+
+
+/// What a monitor should be set to, as one
+/// `monitor = ...` rule
+#[derive(
+  serde::Deserialize,
+  serde::Serialize,
+  zvariant::Type,
+  Default,
+  Debug,
+  Clone,
+  PartialEq
+)]
+pub struct MonitorConfig {
+  pub name: String,
+  pub enabled: bool,
+  pub mode: MonitorMode,
+  pub x: i32,
+  pub y: i32,
+  pub scale: f64,
+  /// `wl_output` transform, 0 to 7
+  pub transform: i32,
+  /// 0 off, 1 on, 2 fullscreen only
+  pub vrr: i32,
+  /// Monitor to mirror, empty for none
+  pub mirror: String,
+}
+
+impl From<&HyprMonitor> for MonitorConfig {
+  fn from(mon: &HyprMonitor) -> Self {
+    Self {
+      name: mon.name.clone(),
+      enabled: !mon.disabled,
+      mode: MonitorMode {
+        width: mon.width,
+        height: mon.height,
+        refresh_rate: mon.refresh_rate
+      },
+      x: mon.x,
+      y: mon.y,
+      scale: mon.scale,
+      transform: mon.transform,
+      // Only whether it's active, see `current_configs`
+      vrr: mon.vrr as i32,
+      mirror: match mon.mirror_of.as_str() {
+        "none" => String::new(),
+        m => m.to_string()
+      }
+    }
+  }
+}
+
+impl MonitorConfig {
+  /// Value of the `monitor` keyword for this config
+  pub fn rule(&self) -> String {
+    if !self.enabled {
+      return format!("{},disable", self.name)
+    }
+
+    let MonitorMode { width, height, refresh_rate } =
+      self.mode;
+
+    let mut rule = format!(
+      "{},{width}x{height}@{},{}x{},{},transform,{}",
+      self.name,
+      fmt_num(refresh_rate),
+      self.x,
+      self.y,
+      fmt_num(self.scale),
+      self.transform
+    );
+
+    if !self.mirror.is_empty() {
+      rule.push_str(&format!(",mirror,{}", self.mirror));
+    }
+
+    rule.push_str(&format!(",vrr,{}", self.vrr));
+    rule
+  }
+
+  fn validate(&self, names: &[String]) -> fdo::Result<()> {
+    let invalid = |msg: &str| Err(fdo::Error::InvalidArgs(
+      format!("{}: {msg}", self.name)));
+
+    if !names.contains(&self.name) {
+      return invalid("no such monitor")
+    }
+
+    if !self.enabled { return Ok(()) }
+
+    if self.mode.width <= 0 || self.mode.height <= 0 {
+      return invalid("invalid resolution")
+    }
+
+    if self.mode.refresh_rate <= 0.0 {
+      return invalid("invalid refresh rate")
+    }
+
+    if self.scale <= 0.0 {
+      return invalid("invalid scale")
+    }
+
+    if !(0..=7).contains(&self.transform) {
+      return invalid("transform must be within 0 and 7")
+    }
+
+    if !(0..=2).contains(&self.vrr) {
+      return invalid("vrr must be 0, 1 or 2")
+    }
+
+    let mirror = &self.mirror;
+    if !mirror.is_empty() &&
+      (mirror == &self.name || !names.contains(mirror))
+    {
+      return invalid("invalid mirror target")
+    }
+
+    Ok(())
+  }
+}
+
+
+/// A `workspace = ...` rule tying a workspace to a
+/// monitor
+#[derive(
+  serde::Deserialize,
+  serde::Serialize,
+  zvariant::Type,
+  Default,
+  Debug,
+  Clone,
+  PartialEq
+)]
+pub struct WorkspaceRule {
+  /// Workspace selector, like `1` or `name:web`
+  pub workspace: String,
+  /// Monitor to open it on, empty for any
+  pub monitor: String,
+  pub default: bool,
+  pub persistent: bool,
+}
+
+impl From<&HyprWorkspaceRule> for WorkspaceRule {
+  fn from(rule: &HyprWorkspaceRule) -> Self {
+    Self {
+      workspace: rule.workspace_string.clone(),
+      monitor: rule.monitor.clone(),
+      default: rule.default,
+      persistent: rule.persistent
+    }
+  }
+}
+
+impl WorkspaceRule {
+  /// Rule from a `workspace` keyword value, as `rule`
+  /// writes them
+  fn parse(value: &str) -> Self {
+    let mut parts = value.split(',').map(str::trim);
+    let mut rule = Self {
+      workspace: parts.next().unwrap_or_default().to_string(),
+      ..Default::default()
+    };
+
+    for part in parts {
+      match part.split_once(':') {
+        Some(("monitor", m)) => rule.monitor = m.to_string(),
+        Some(("default", v)) => rule.default = v == "true",
+        Some(("persistent", v)) => rule.persistent = v == "true",
+        _ => ()
+      }
+    }
+
+    rule
+  }
+
+  /// Value of the `workspace` keyword for this rule
+  pub fn rule(&self) -> String {
+    let mut rule = vec![self.workspace.clone()];
+
+    if !self.monitor.is_empty() {
+      rule.push(format!("monitor:{}", self.monitor));
+    }
+
+    if self.default { rule.push("default:true".into()) }
+    if self.persistent { rule.push("persistent:true".into()) }
+
+    rule.join(", ")
+  }
+
+  /// Whether `ws` is the workspace the rule is about
+  pub fn targets(&self, ws: &HyprWorkspace) -> bool {
+    match self.workspace.strip_prefix("name:") {
+      Some(name) => ws.name == name,
+      None => self.workspace.parse::<i64>().ok() == Some(ws.id)
+    }
+  }
+
+  fn validate(&self, names: &[String]) -> fdo::Result<()> {
+    let invalid = |msg: &str| Err(fdo::Error::InvalidArgs(
+      format!("workspace {}: {msg}", self.workspace)));
+
+    let ws = self.workspace.trim();
+    if ws.is_empty() || ws.contains([',', '\n']) {
+      return invalid("invalid workspace selector")
+    }
+
+    if self.monitor.is_empty() {
+      if self.default {
+        return invalid("a default workspace needs a monitor")
+      }
+
+      if !self.persistent {
+        return invalid("nothing to set")
+      }
+    }
+    else if !names.contains(&self.monitor) {
+      return invalid("no such monitor")
+    }
+
+    Ok(())
+  }
+}
+
+
+/// Cache file holding the monitor state to restore while
+/// a change awaits confirmation, so a crashed daemon can
+/// still revert it on its next start
+const PENDING_REVERT_FILE: &str = "monitors.pending";
+
+/// A monitor change awaiting confirmation
+struct PendingChange {
+  snapshot: Vec<MonitorConfig>,
+  applied: Vec<MonitorConfig>,
+  scaling: Option<PendingScaling>,
+  timer: JoinHandle<()>,
+}
+
+/// Scaling settings changed along with a monitor's scale
+struct PendingScaling {
+  previous: ScalingSettings,
+  applied: ScalingSettings,
+}
+
+/// What `PENDING_REVERT_FILE` holds
+#[derive(
+  serde::Deserialize,
+  serde::Serialize,
+  zvariant::Type,
+  Default,
+  Debug,
+  Clone
+)]
+#[serde(default)]
+struct PendingRevert {
+  monitors: Vec<MonitorConfig>,
+  /// Scaling settings to restore, at most one
+  scaling: Vec<ScalingSettings>,
+}
+
+
+/// How long monitor hotplug events have to settle before
+/// picking a profile, as docks plug several at once
+const HOTPLUG_SETTLE: Duration = Duration::from_millis(1500);
+
+
+pub(crate) struct ScreenInterface {
+  pending: Option<PendingChange>,
+  profiles: Vec<LayoutProfile>,
+  active_profile: String,
+  /// Percents by monitor, for monitors that have a way to
+  /// set it
+  brightness: HashMap<String, f64>,
+  /// For logind, connected on first use
+  system_bus: Option<zbus::Connection>,
+}
+
+#[interface(name = "org.hypr.Hyprmaster.Screen")]
+impl ScreenInterface {
+  /// Path of the config file holding the applied monitor
+  /// and workspace rules, for the user to `source` from
+  /// hyprland.conf
+  #[zbus(property)]
+  fn include_path(&self) -> String {
+    monitors_conf_path().to_string_lossy().to_string()
+  }
+
+  async fn monitors(&self) -> fdo::Result<Vec<HyprMonitor>> {
+    hyprctl::monitors().await
+      .map_err(|e| fdo::Error::Failed(e.to_string()))
+  }
+
+  /// Whether a change is waiting for `Confirm`
+  #[zbus(property)]
+  fn pending(&self) -> bool {
+    self.pending.is_some()
+  }
+
+  /// Applies `configs` through `keyword monitor`.
+  /// Monitors left out keep their current rule.
+  ///
+  /// With a `timeout`, the previous state gets restored
+  /// unless `Confirm` comes within that many seconds, and
+  /// only a confirmed change is persisted to the include
+  /// file. A `timeout` of 0 persists right away.
+  async fn apply(
+    &mut self,
+    #[zbus(connection)]
+    conn: &zbus::Connection,
+    #[zbus(signal_emitter)]
+    emitter: SignalEmitter<'_>,
+    configs: Vec<MonitorConfig>,
+    timeout: u32
+  ) -> fdo::Result<()> {
+    // A new change replaces the unconfirmed one, which
+    // has to be undone first for the snapshot to hold
+    if self.pending.is_some() {
+      self.revert_pending(&emitter).await?;
+    }
+
+    self.apply_change(conn, &emitter, configs, None, timeout)
+      .await
+  }
+
+  /// Keeps the pending change and persists it
+  async fn confirm(
+    &mut self,
+    #[zbus(signal_emitter)]
+    emitter: SignalEmitter<'_>
+  ) -> fdo::Result<()> {
+    let pending = self.pending.take()
+      .ok_or(fdo::Error::Failed(
+        "No monitor change to confirm".into()))?;
+
+    pending.timer.abort();
+    clear_pending_revert();
+
+    if let Some(scaling) = &pending.scaling {
+      write_scaling_conf(&scaling.applied)
+        .map_err(|e| fdo::Error::IOError(e.to_string()))?;
+    }
+
+    write_monitors_conf(&pending.applied)
+      .map_err(|e| fdo::Error::IOError(e.to_string()))?;
+
+    self.pending_changed(&emitter).await?;
+    Ok(())
+  }
+
+  /// Restores the monitors as they were before the
+  /// pending change
+  async fn revert(
+    &mut self,
+    #[zbus(signal_emitter)]
+    emitter: SignalEmitter<'_>
+  ) -> fdo::Result<()> {
+    if self.pending.is_none() {
+      return Err(fdo::Error::Failed(
+        "No monitor change to revert".into()))
+    }
+
+    self.revert_pending(&emitter).await
+  }
+
+  #[zbus(signal)]
+  async fn confirmation_requested(
+    emitter: &SignalEmitter<'_>,
+    timeout: u32
+  ) -> zbus::Result<()>;
+
+  #[zbus(signal)]
+  async fn reverted(
+    emitter: &SignalEmitter<'_>
+  ) -> zbus::Result<()>;
+
+  #[zbus(signal)]
+  async fn monitors_changed(
+    emitter: &SignalEmitter<'_>
+  ) -> zbus::Result<()>;
+
+  #[zbus(signal)]
+  async fn workspaces_changed(
+    emitter: &SignalEmitter<'_>
+  ) -> zbus::Result<()>;
+
+  async fn workspaces(&self) ->
+    fdo::Result<Vec<HyprWorkspace>>
+  {
+    hyprctl::workspaces().await
+      .map_err(|e| fdo::Error::Failed(e.to_string()))
+  }
+
+  /// Active workspace rules that place or keep workspaces,
+  /// ones from hyprland.conf included
+  async fn workspace_rules(&self) ->
+    fdo::Result<Vec<WorkspaceRule>>
+  {
+    let rules = hyprctl::workspace_rules().await
+      .map_err(|e| fdo::Error::Failed(e.to_string()))?;
+
+    Ok(rules
+      .iter()
+      .filter(|r| !r.monitor.is_empty() || r.persistent)
+      .map(WorkspaceRule::from)
+      .collect())
+  }
+
+  /// Applies `rules` through `keyword workspace`, moves the
+  /// workspaces they place to their monitor, and persists
+  /// them as the whole set of managed rules.
+  ///
+  /// Hyprland can't drop a rule at runtime, rules left out
+  /// stay in effect until the config gets reloaded.
+  async fn set_workspace_rules(
+    &self,
+    #[zbus(signal_emitter)]
+    emitter: SignalEmitter<'_>,
+    rules: Vec<WorkspaceRule>
+  ) -> fdo::Result<()> {
+    let names: Vec<String> = self.monitors().await?
+      .iter()
+      .map(|m| m.name.clone())
+      .collect();
+
+    for (i, rule) in rules.iter().enumerate() {
+      rule.validate(&names)?;
+
+      let before = &rules[..i];
+      let invalid = |msg: &str| Err(fdo::Error::InvalidArgs(
+        format!("workspace {}: {msg}", rule.workspace)));
+
+      if before.iter().any(|r| r.workspace == rule.workspace) {
+        return invalid("more than one rule")
+      }
+
+      if rule.default && before
+        .iter()
+        .any(|r| r.default && r.monitor == rule.monitor)
+      {
+        return invalid(&format!(
+          "{} already has a default workspace", rule.monitor))
+      }
+    }
+
+    // Taken before ours get applied, to tell the ones
+    // from hyprland.conf apart
+    let live: Vec<WorkspaceRule> = hyprctl::workspace_rules()
+      .await
+      .map_err(|e| fdo::Error::Failed(e.to_string()))?
+      .iter()
+      .map(WorkspaceRule::from)
+      .collect();
+
+    let managed: Vec<WorkspaceRule> = managed_values("workspace")
+      .iter()
+      .map(|v| WorkspaceRule::parse(v))
+      .collect();
+
+    for rule in &rules {
+      hyprctl::keyword("workspace", &rule.rule()).await
+        .map_err(|e| fdo::Error::Failed(format!(
+          "workspace {}: {e}", rule.workspace)))?;
+    }
+
+    // Rules only apply to workspaces made from now on
+    let workspaces = self.workspaces().await?;
+    for rule in rules.iter().filter(|r| !r.monitor.is_empty()) {
+      let misplaced = workspaces
+        .iter()
+        .any(|ws| rule.targets(ws) && ws.monitor != rule.monitor);
+
+      if !misplaced { continue }
+
+      hyprctl::dispatch(
+        "moveworkspacetomonitor",
+        &format!("{} {}", rule.workspace, rule.monitor)
+      ).await
+        .map_err(|e| fdo::Error::Failed(format!(
+          "workspace {}: {e}", rule.workspace)))?;
+    }
+
+    let values: Vec<String> = own_rules(&rules, &live, &managed)
+      .iter()
+      .map(WorkspaceRule::rule)
+      .collect();
+
+    write_managed_conf("workspace", &values)
+      .map_err(|e| fdo::Error::IOError(e.to_string()))?;
+
+    emitter.workspaces_changed().await?;
+    Ok(())
+  }
+
+  /// What `ApplyScaling` would change, settings already
+  /// right left out
+  async fn preview_scaling(
+    &self,
+    monitor: &str,
+    scale: f64
+  ) -> fdo::Result<Vec<ScalingChange>> {
+    let (plan, monitors) =
+      self.scaling_plan(monitor, scale).await?;
+
+    let current_scale = monitors
+      .iter()
+      .find(|m| m.name == monitor)
+      .map(|m| fmt_num(m.scale))
+      .unwrap_or_default();
+
+    let current = current_scaling(&plan).await;
+
+    let mut changes = vec![
+      ScalingChange {
+        setting: format!("{monitor} scale"),
+        current: current_scale,
+        planned: fmt_num(scale)
+      },
+      ScalingChange {
+        setting: "xwayland:force_zero_scaling".into(),
+        current: current.force_zero_scaling.to_string(),
+        planned: plan.force_zero_scaling.to_string()
+      }
+    ];
+
+    let planned = plan.settings();
+    for ((key, current), (_, planned)) in current.env
+      .into_iter()
+      .zip(planned.env)
+    {
+      changes.push(ScalingChange {
+        setting: format!("env {key}"),
+        current,
+        planned
+      });
+    }
+
+    changes.retain(|c| c.current != c.planned);
+    Ok(changes)
+  }
+
+  /// Sets `monitor` to `scale`, and XWayland, toolkit env
+  /// vars and the cursor size to match. With a `timeout`,
+  /// all of it goes back unless `Confirm` comes in time.
+  async fn apply_scaling(
+    &mut self,
+    #[zbus(connection)]
+    conn: &zbus::Connection,
+    #[zbus(signal_emitter)]
+    emitter: SignalEmitter<'_>,
+    monitor: &str,
+    scale: f64,
+    timeout: u32
+  ) -> fdo::Result<()> {
+    if self.pending.is_some() {
+      self.revert_pending(&emitter).await?;
+    }
+
+    let (plan, _) = self.scaling_plan(monitor, scale).await?;
+    let scaling = PendingScaling {
+      previous: current_scaling(&plan).await,
+      applied: plan.settings()
+    };
+
+    self.apply_change(
+      conn, &emitter, vec![plan.monitor], Some(scaling), timeout)
+      .await
+  }
+
+  /// Brightness from 0 to 100 of each monitor it can be
+  /// set on
+  #[zbus(property)]
+  fn brightness(&self) -> HashMap<String, f64> {
+    self.brightness.clone()
+  }
+
+  /// Sets the brightness of `monitor`, through its
+  /// backlight for built-in panels and DDC/CI otherwise
+  async fn set_brightness(
+    &mut self,
+    #[zbus(signal_emitter)]
+    emitter: SignalEmitter<'_>,
+    monitor: &str,
+    percent: f64
+  ) -> fdo::Result<()> {
+    if !(0.0..=100.0).contains(&percent) {
+      return Err(fdo::Error::InvalidArgs(
+        "Brightness must be within 0 and 100".into()))
+    }
+
+    let control = BrightnessControl::for_connector(
+      &DeviceRoots::default(), monitor)
+      .ok_or(fdo::Error::Failed(format!(
+        "{monitor}: no way to set its brightness")))?;
+
+    let system_bus = self.system_bus().await?;
+    control.set_percent(&system_bus, percent).await
+      .map_err(|e| fdo::Error::Failed(format!(
+        "{monitor}: {e}")))?;
+
+    self.brightness.insert(monitor.to_string(), percent);
+    self.brightness_changed(&emitter).await?;
+
+    Ok(())
+  }
+
+  /// Names of the saved layout profiles
+  #[zbus(property)]
+  fn profiles(&self) -> Vec<String> {
+    self.profiles.iter().map(|p| p.name.clone()).collect()
+  }
+
+  /// Profile last applied, empty when none matched
+  #[zbus(property)]
+  fn active_profile(&self) -> String {
+    self.active_profile.clone()
+  }
+
+  /// Saves the current layout as profile `name`, for the
+  /// set of monitors connected right now
+  async fn save_profile(
+    &mut self,
+    #[zbus(signal_emitter)]
+    emitter: SignalEmitter<'_>,
+    name: &str
+  ) -> fdo::Result<()> {
+    if name.trim().is_empty() {
+      return Err(fdo::Error::InvalidArgs(
+        "Profile name can't be empty".into()))
+    }
+
+    let monitors = self.monitors().await?;
+    let configs = current_configs(&monitors).await;
+    let profile = LayoutProfile::capture(
+      name, &monitors, configs);
+
+    match self.profiles.iter_mut().find(|p| p.name == name) {
+      Some(p) => *p = profile,
+      None => self.profiles.push(profile)
+    }
+
+    save_profiles(&self.profiles)
+      .map_err(|e| fdo::Error::IOError(e.to_string()))?;
+
+    self.active_profile = name.to_string();
+    self.profiles_changed(&emitter).await?;
+    self.active_profile_changed(&emitter).await?;
+
+    Ok(())
+  }
+
+  async fn remove_profile(
+    &mut self,
+    #[zbus(signal_emitter)]
+    emitter: SignalEmitter<'_>,
+    name: &str
+  ) -> fdo::Result<()> {
+    let len = self.profiles.len();
+    self.profiles.retain(|p| p.name != name);
+
+    if self.profiles.len() == len {
+      return Err(fdo::Error::InvalidArgs(format!(
+        "No profile named {name}")))
+    }
+
+    save_profiles(&self.profiles)
+      .map_err(|e| fdo::Error::IOError(e.to_string()))?;
+
+    self.profiles_changed(&emitter).await?;
+
+    if self.active_profile == name {
+      self.active_profile.clear();
+      self.active_profile_changed(&emitter).await?;
+    }
+
+    Ok(())
+  }
+
+  /// Applies profile `name`, provided its monitors are
+  /// the ones connected
+  async fn apply_profile(
+    &mut self,
+    #[zbus(signal_emitter)]
+    emitter: SignalEmitter<'_>,
+    name: &str
+  ) -> fdo::Result<()> {
+    let monitors = self.monitors().await?;
+    let profile = self.profiles
+      .iter()
+      .find(|p| p.name == name)
+      .cloned()
+      .ok_or(fdo::Error::InvalidArgs(format!(
+        "No profile named {name}")))?;
+
+    if !profile.matches(&monitors) {
+      return Err(fdo::Error::Failed(format!(
+        "The monitors of {name} aren't all connected")))
+    }
+
+    self.use_profile(&emitter, &profile, &monitors).await
+  }
+}
+
+impl ScreenInterface {
+  pub fn new() -> Self {
+    Self {
+      pending: None,
+      profiles: load_profiles(),
+      active_profile: String::new(),
+      brightness: HashMap::new(),
+      system_bus: None,
+    }
+  }
+
+  /// Applies `configs` and the `scaling` settings going
+  /// with them, waiting for `Confirm` with a `timeout`
+  async fn apply_change(
+    &mut self,
+    conn: &zbus::Connection,
+    emitter: &SignalEmitter<'_>,
+    configs: Vec<MonitorConfig>,
+    scaling: Option<PendingScaling>,
+    timeout: u32
+  ) -> fdo::Result<()> {
+    let current = self.monitors().await?;
+    let names: Vec<String> = current
+      .iter()
+      .map(|m| m.name.clone())
+      .collect();
+
+    for config in &configs {
+      config.validate(&names)?;
+    }
+
+    let snapshot = current_configs(&current).await;
+
+    // Every monitor, so the include file always describes
+    // the whole layout
+    let applied: Vec<MonitorConfig> = snapshot
+      .iter()
+      .map(|m| configs
+        .iter()
+        .find(|c| c.name == m.name)
+        .cloned()
+        .unwrap_or(m.clone()))
+      .collect();
+
+    if timeout == 0 {
+      apply_configs(&configs).await?;
+
+      if let Some(scaling) = &scaling {
+        set_scaling(&scaling.applied).await?;
+        write_scaling_conf(&scaling.applied)
+          .map_err(|e| fdo::Error::IOError(e.to_string()))?;
+      }
+
+      write_monitors_conf(&applied)
+        .map_err(|e| fdo::Error::IOError(e.to_string()))?;
+
+      emitter.monitors_changed().await?;
+      return Ok(())
+    }
+
+    // Written before touching anything, a crash from here
+    // on still gets reverted
+    let revert = PendingRevert {
+      monitors: snapshot.clone(),
+      scaling: scaling
+        .iter()
+        .map(|s| s.previous.clone())
+        .collect()
+    };
+    cache::write(PENDING_REVERT_FILE, &revert)
+      .map_err(|e| fdo::Error::IOError(e.to_string()))?;
+
+    let res = match apply_configs(&configs).await {
+      Ok(_) => match &scaling {
+        Some(s) => set_scaling(&s.applied).await,
+        None => Ok(())
+      },
+      Err(e) => Err(e)
+    };
+
+    if let Err(e) = res {
+      _ = restore(&revert).await;
+      clear_pending_revert();
+      return Err(e)
+    }
+
+    let conn = conn.clone();
+    let timer = tokio::spawn(async move {
+      sleep(Duration::from_secs(timeout as u64)).await;
+
+      let res = Self::revert_timed_out(&conn).await;
+      if let Err(e) = res {
+        eprintln!("Failed to revert monitors: {:#?}", e);
+      }
+    });
+
+    self.pending = Some(PendingChange {
+      snapshot,
+      applied,
+      scaling,
+      timer
+    });
+
+    self.pending_changed(emitter).await?;
+    emitter.monitors_changed().await?;
+    emitter.confirmation_requested(timeout).await?;
+
+    Ok(())
+  }
+
+  async fn scaling_plan(&self, monitor: &str, scale: f64) ->
+    fdo::Result<(ScalingPlan, Vec<HyprMonitor>)>
+  {
+    if !(scale > 0.0 && scale <= 10.0) {
+      return Err(fdo::Error::InvalidArgs(
+        "Scale must be within 0 and 10".into()))
+    }
+
+    let monitors = self.monitors().await?;
+    let plan = ScalingPlan::new(&monitors, monitor, scale)
+      .ok_or(fdo::Error::InvalidArgs(format!(
+        "{monitor}: no such monitor")))?;
+
+    Ok((plan, monitors))
+  }
+
+  async fn system_bus(&mut self) ->
+    fdo::Result<zbus::Connection>
+  {
+    if let Some(bus) = &self.system_bus {
+      return Ok(bus.clone())
+    }
+
+    let bus = zbus::Connection::system().await
+      .map_err(|e| fdo::Error::Failed(e.to_string()))?;
+
+    self.system_bus = Some(bus.clone());
+    Ok(bus)
+  }
+
+  /// Reads the brightness of every monitor that has a way
+  /// to set it. DDC/CI is slow, so it's read outside of
+  /// the interface's lock.
+  async fn refresh_brightness(conn: &zbus::Connection) ->
+    Result<(), Box<dyn Error>>
+  {
+    let roots = DeviceRoots::default();
+    let monitors = hyprctl::monitors().await?;
+
+    let mut brightness = HashMap::new();
+    for monitor in monitors.iter().filter(|m| !m.disabled) {
+      let control = match BrightnessControl::for_connector(
+        &roots, &monitor.name)
+      {
+        Some(c) => c,
+        None => continue
+      };
+
+      match control.percent().await {
+        Ok(p) => { brightness.insert(monitor.name.clone(), p); },
+        Err(e) => eprintln!(
+          "No brightness for {}: {e}", monitor.name)
+      }
+    }
+
+    let iface = conn.object_server()
+      .interface::<_, ScreenInterface>("/screen").await?;
+    let mut iref = iface.get_mut().await;
+
+    iref.brightness = brightness;
+    iref.brightness_changed(iface.signal_emitter()).await?;
+
+    Ok(())
+  }
+
+  async fn use_profile(
+    &mut self,
+    emitter: &SignalEmitter<'_>,
+    profile: &LayoutProfile,
+    monitors: &[HyprMonitor]
+  ) -> fdo::Result<()> {
+    let configs = profile.configs(monitors);
+
+    apply_configs(&configs).await?;
+    write_monitors_conf(&configs)
+      .map_err(|e| fdo::Error::IOError(e.to_string()))?;
+
+    self.active_profile = profile.name.clone();
+    self.active_profile_changed(emitter).await?;
+    emitter.monitors_changed().await?;
+
+    Ok(())
+  }
+
+  /// Applies the profile made for the monitors connected
+  /// now, if any. Changes awaiting confirmation are left
+  /// alone.
+  async fn switch_profile(conn: &zbus::Connection) ->
+    Result<(), Box<dyn Error>>
+  {
+    let iface = conn.object_server()
+      .interface::<_, ScreenInterface>("/screen").await?;
+    let mut iref = iface.get_mut().await;
+
+    if iref.pending.is_some() { return Ok(()) }
+
+    let monitors = hyprctl::monitors().await?;
+    let profile = iref.profiles
+      .iter()
+      .find(|p| p.matches(&monitors))
+      .cloned();
+
+    match profile {
+      Some(profile) => {
+        iref.use_profile(
+          iface.signal_emitter(), &profile, &monitors).await?;
+      },
+      None if !iref.active_profile.is_empty() => {
+        iref.active_profile.clear();
+        iref.active_profile_changed(iface.signal_emitter())
+          .await?;
+      },
+      None => ()
+    }
+
+    Ok(())
+  }
+
+  async fn revert_pending(
+    &mut self,
+    emitter: &SignalEmitter<'_>
+  ) -> fdo::Result<()> {
+    let pending = match self.pending.take() {
+      Some(p) => p,
+      None => return Ok(())
+    };
+
+    // Unless it's the timer itself reverting
+    if tokio::task::try_id() != Some(pending.timer.id()) {
+      pending.timer.abort();
+    }
+
+    apply_configs(&pending.snapshot).await?;
+
+    if let Some(scaling) = &pending.scaling {
+      set_scaling(&scaling.previous).await?;
+    }
+
+    clear_pending_revert();
+
+    self.pending_changed(emitter).await?;
+    emitter.monitors_changed().await?;
+    emitter.reverted().await?;
+
+    Ok(())
+  }
+
+  async fn revert_timed_out(conn: &zbus::Connection) ->
+    Result<(), Box<dyn Error>>
+  {
+    let iface = conn.object_server()
+      .interface::<_, ScreenInterface>("/screen").await?;
+
+    let mut iref = iface.get_mut().await;
+    iref.revert_pending(iface.signal_emitter()).await?;
+
+    Ok(())
+  }
+
+  /// Restores the snapshot a previous run left behind
+  /// when it died with a change still unconfirmed
+  async fn restore_pending_revert() {
+    let revert = match cache::read::<PendingRevert>(
+      PENDING_REVERT_FILE)
+    {
+      Some(r) => r,
+      None => return
+    };
+
+    match restore(&revert).await {
+      Ok(_) => println!("Restored unconfirmed monitor change"),
+      Err(e) => eprintln!(
+        "Failed to restore monitors: {:#?}", e)
+    }
+
+    clear_pending_revert();
+  }
+
+  pub async fn listen(
+    conn: &zbus::Connection,
+    mut hrx: HyprReceiver
+  ) -> Result<(), Box<dyn Error>> {
+    Self::restore_pending_revert().await;
+
+    let iface = conn.object_server()
+      .interface::<_, ScreenInterface>("/screen").await?;
+
+    if let Err(e) = Self::switch_profile(conn).await {
+      eprintln!("Failed to apply monitor profile: {:#?}", e);
+    }
+
+    if let Err(e) = Self::refresh_brightness(conn).await {
+      eprintln!("Failed to read brightness: {:#?}", e);
+    }
+
+    loop {
+      match hrx.recv().await {
+        Ok(HyprlandEvent::MonitorAdded { .. }) |
+        Ok(HyprlandEvent::MonitorRemoved { .. }) => {
+          let emitter = iface.signal_emitter();
+          _ = emitter.monitors_changed().await;
+          _ = emitter.workspaces_changed().await;
+
+          if !Self::settle(&mut hrx).await { break }
+
+          if let Err(e) = Self::switch_profile(conn).await {
+            eprintln!(
+              "Failed to apply monitor profile: {:#?}", e);
+          }
+
+          if let Err(e) = Self::refresh_brightness(conn).await {
+            eprintln!("Failed to read brightness: {:#?}", e);
+          }
+        },
+        Ok(HyprlandEvent::ConfigReloaded) => {
+          let emitter = iface.signal_emitter();
+          _ = emitter.monitors_changed().await;
+          _ = emitter.workspaces_changed().await;
+        },
+        Ok(HyprlandEvent::MoveWorkspace { .. }) |
+        Ok(HyprlandEvent::CreateWorkspace { .. }) |
+        Ok(HyprlandEvent::DestroyWorkspace { .. }) => {
+          _ = iface.signal_emitter().workspaces_changed().await;
+        },
+        Ok(_) | Err(RecvError::Lagged(_)) => continue,
+        Err(RecvError::Closed) => break
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Waits for hotplug events to stop coming in. Returns
+  /// false once the event stream is gone.
+  async fn settle(hrx: &mut HyprReceiver) -> bool {
+    loop {
+      tokio::select! {
+        _ = sleep(HOTPLUG_SETTLE) => return true,
+        ev = hrx.recv() => match ev {
+          Err(RecvError::Closed) => return false,
+          _ => continue
+        }
+      }
+    }
+  }
+}
+
+
+
+/// `$XDG_CONFIG_HOME/hypr/hyprmaster/monitors.conf`
+pub fn monitors_conf_path() -> PathBuf {
+  let home_dir = var("HOME").unwrap();
+  let xdg_conf_dir = var("XDG_CONFIG_HOME")
+    .unwrap_or(format!("{home_dir}/.config"));
+
+  PathBuf::from(format!(
+    "{xdg_conf_dir}/hypr/hyprmaster/monitors.conf"))
+}
+
+async fn apply_configs(configs: &[MonitorConfig]) ->
+  fdo::Result<()>
+{
+  for config in configs {
+    hyprctl::keyword("monitor", &config.rule()).await
+      .map_err(|e| fdo::Error::Failed(format!(
+        "{}: {e}", config.name)))?;
+  }
+
+  Ok(())
+}
+
+/// Puts back what `revert` describes
+async fn restore(revert: &PendingRevert) -> fdo::Result<()> {
+  apply_configs(&revert.monitors).await?;
+
+  for scaling in &revert.scaling {
+    set_scaling(scaling).await?;
+  }
+
+  Ok(())
+}
+
+/// Scaling settings as they are now for the keys `plan`
+/// sets, the env ones as set by us or else inherited
+async fn current_scaling(plan: &ScalingPlan) -> ScalingSettings {
+  let force_zero_scaling = hyprctl::option_int(
+    "xwayland:force_zero_scaling").await
+    .is_ok_and(|v| v != 0);
+
+  let managed = managed_values("env");
+  let env = plan.env()
+    .into_iter()
+    .map(|(key, _)| {
+      let value = env_value(&managed, key)
+        .or(var(key).ok())
+        .unwrap_or_default();
+
+      (key.to_string(), value)
+    })
+    .collect();
+
+  ScalingSettings { force_zero_scaling, env }
+}
+
+/// Sets `scaling` live, for apps started from now on and
+/// for the cursor
+async fn set_scaling(scaling: &ScalingSettings) ->
+  fdo::Result<()>
+{
+  let failed = |e: std::io::Error| fdo::Error::Failed(
+    e.to_string());
+
+  hyprctl::keyword(
+    "xwayland:force_zero_scaling",
+    &scaling.force_zero_scaling.to_string()).await
+    .map_err(failed)?;
+
+  for (key, value) in &scaling.env {
+    hyprctl::keyword("env", &format!("{key},{value}")).await
+      .map_err(failed)?;
+  }
+
+  // The cursor already shown only follows through here
+  if let Some(size) = scaling.cursor_size() {
+    hyprctl::set_cursor(&cursor_theme(), size).await
+      .map_err(failed)?;
+  }
+
+  Ok(())
+}
+
+/// Persists `scaling` to the include file, next to the
+/// env entries of other keys
+fn write_scaling_conf(scaling: &ScalingSettings) ->
+  std::io::Result<()>
+{
+  let mut env: Vec<String> = managed_values("env")
+    .into_iter()
+    .filter(|e| !scaling.env
+      .iter()
+      .any(|(key, _)| e.split(',').next() == Some(key)))
+    .collect();
+
+  env.extend(scaling.env
+    .iter()
+    .map(|(key, value)| format!("{key},{value}")));
+
+  write_managed_conf(
+    "xwayland:force_zero_scaling",
+    &[scaling.force_zero_scaling.to_string()])?;
+  write_managed_conf("env", &env)
+}
+
+/// Value of `key` among `env = KEY,VALUE` entries
+fn env_value(entries: &[String], key: &str) -> Option<String> {
+  entries
+    .iter()
+    .filter_map(|e| e.split_once(','))
+    .find(|(k, _)| k.trim() == key)
+    .map(|(_, v)| v.trim().to_string())
+}
+
+/// The cursor theme in use, for `setcursor` to keep
+fn cursor_theme() -> String {
+  let managed = managed_values("env");
+
+  ["HYPRCURSOR_THEME", "XCURSOR_THEME"]
+    .iter()
+    .find_map(|key| env_value(&managed, key)
+      .or(var(key).ok())
+      .filter(|t| !t.is_empty()))
+    .unwrap_or("default".into())
+}
+
+fn clear_pending_revert() {
+  let path = cache::cache_dir().join(PENDING_REVERT_FILE);
+
+  match std::fs::remove_file(path) {
+    Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+      eprintln!("Failed to clear pending revert: {:#?}", e)
+    },
+    _ => ()
+  }
+}
+
+/// Configs `monitors` are in right now. Hyprland only
+/// tells whether VRR is active, so the mode comes from the
+/// managed rule, or else from `misc:vrr`.
+async fn current_configs(monitors: &[HyprMonitor]) ->
+  Vec<MonitorConfig>
+{
+  let rules = managed_values("monitor");
+  let global = hyprctl::option_int("misc:vrr")
+    .await
+    .unwrap_or(0);
+
+  monitors
+    .iter()
+    .map(|m| {
+      let mode = rules
+        .iter()
+        .find_map(|r| rule_vrr(r, &m.name))
+        .unwrap_or(global);
+
+      MonitorConfig {
+        vrr: vrr_mode(mode, m.vrr),
+        ..MonitorConfig::from(m)
+      }
+    })
+    .collect()
+}
+
+/// VRR mode a `monitor` rule sets for `name`
+fn rule_vrr(rule: &str, name: &str) -> Option<i64> {
+  let fields: Vec<&str> = rule
+    .split(',')
+    .map(str::trim)
+    .collect();
+
+  if fields.first() != Some(&name) { return None }
+
+  fields
+    .windows(2)
+    .find(|w| w[0] == "vrr")
+    .and_then(|w| w[1].parse().ok())
+}
+
+/// `vrr` value as one of ours. 3, fullscreen with game or
+/// video content, is closest to fullscreen only.
+fn vrr_mode(mode: i64, active: bool) -> i32 {
+  match mode.clamp(0, 2) as i32 {
+    // Turned on by a rule we don't manage
+    0 if active => 1,
+    mode => mode
+  }
+}
+
+fn write_monitors_conf(configs: &[MonitorConfig]) ->
+  std::io::Result<()>
+{
+  let values: Vec<String> = configs
+    .iter()
+    .map(MonitorConfig::rule)
+    .collect();
+
+  write_managed_conf("monitor", &values)
+}
+
+/// Replaces the `keyword = ...` lines of the include file
+/// with `values`, keeping the lines of other keywords
+fn write_managed_conf(keyword: &str, values: &[String]) ->
+  std::io::Result<()>
+{
+  let path = monitors_conf_path();
+  if let Some(dir) = path.parent() {
+    std::fs::create_dir_all(dir)?;
+  }
+
+  let old = std::fs::read_to_string(&path)
+    .unwrap_or_default();
+
+  let old = match old.trim().is_empty() {
+    true => format!(
+      "# Generated by Hyprmaster, changes get overwritten.\n\
+       # Load it with `source = {}`\n\n",
+      path.to_string_lossy()),
+    false => old
+  };
+
+  cache::write_atomic(&path, merge_conf(&old, keyword, values))
+}
+
+/// Rules of `rules` to persist: `live` ones Hyprland got
+/// from outside the `managed` file are already set there,
+/// unless they were changed
+fn own_rules(
+  rules: &[WorkspaceRule],
+  live: &[WorkspaceRule],
+  managed: &[WorkspaceRule]
+) -> Vec<WorkspaceRule> {
+  let external: Vec<&WorkspaceRule> = live
+    .iter()
+    .filter(|r| !managed.contains(r))
+    .collect();
+
+  rules
+    .iter()
+    .filter(|r| !external.contains(r))
+    .cloned()
+    .collect()
+}
+
+/// `conf` with its `keyword` lines replaced by `values`,
+/// where the first of them was or else at the end. Every
+/// other line, comments and blank ones too, stays as is.
+fn merge_conf(
+  conf: &str,
+  keyword: &str,
+  values: &[String]
+) -> String {
+  let mut new = values
+    .iter()
+    .map(|v| format!("{keyword} = {v}"));
+
+  let mut lines = vec![];
+  for line in conf.lines() {
+    match conf_value(line, keyword) {
+      Some(_) => lines.extend(new.by_ref()),
+      None => lines.push(line.to_string())
+    }
+  }
+  lines.extend(new);
+
+  lines.join("\n") + "\n"
+}
+
+/// Values of the include file's `keyword = ...` lines
+fn managed_values(keyword: &str) -> Vec<String> {
+  std::fs::read_to_string(monitors_conf_path())
+    .unwrap_or_default()
+    .lines()
+    .filter_map(|line| conf_value(line, keyword))
+    .map(str::to_string)
+    .collect()
+}
+
+fn conf_value<'a>(line: &'a str, keyword: &str) ->
+  Option<&'a str>
+{
+  let (key, value) = line.split_once('=')?;
+  (key.trim() == keyword).then_some(value.trim())
+}
+
+/// Formats rates and scales without trailing zeros
+fn fmt_num(n: f64) -> String {
+  let n = format!("{n:.3}");
+  n.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn values(list: &[&str]) -> Vec<String> {
+    list.iter().map(|s| s.to_string()).collect()
+  }
+
+  #[test]
+  fn merge_keeps_other_lines() {
+    let conf = "# My monitors\n\
+      monitor = DP-1,preferred,auto,1\n\
+      \n\
+      # Laptop\n\
+      monitor = eDP-1,preferred,auto,2\n\
+      env = GDK_SCALE,2\n";
+
+    let merged = merge_conf(conf, "monitor", &values(&[
+      "DP-1,disable",
+      "eDP-1,preferred,auto,1"
+    ]));
+
+    assert_eq!(merged, "# My monitors\n\
+      monitor = DP-1,disable\n\
+      monitor = eDP-1,preferred,auto,1\n\
+      \n\
+      # Laptop\n\
+      env = GDK_SCALE,2\n");
+  }
+
+  #[test]
+  fn parses_written_rules() {
+    let rule = WorkspaceRule {
+      workspace: "name:web".into(),
+      monitor: "DP-1".into(),
+      default: true,
+      persistent: true
+    };
+
+    assert_eq!(WorkspaceRule::parse(&rule.rule()), rule);
+    assert_eq!(WorkspaceRule::parse("3"), WorkspaceRule {
+      workspace: "3".into(),
+      ..Default::default()
+    });
+  }
+
+  #[test]
+  fn persists_only_own_rules() {
+    let rule = |ws: &str, monitor: &str| WorkspaceRule {
+      workspace: ws.into(),
+      monitor: monitor.into(),
+      ..Default::default()
+    };
+
+    // 1 and 2 are from hyprland.conf, 3 was saved before
+    let live = [
+      rule("1", "DP-1"),
+      rule("2", "DP-1"),
+      rule("3", "HDMI-A-1")
+    ];
+    let managed = [rule("3", "HDMI-A-1")];
+
+    // 2 moved, 4 is new
+    let rules = [
+      rule("1", "DP-1"),
+      rule("2", "HDMI-A-1"),
+      rule("3", "HDMI-A-1"),
+      rule("4", "DP-1")
+    ];
+
+    assert_eq!(own_rules(&rules, &live, &managed), [
+      rule("2", "HDMI-A-1"),
+      rule("3", "HDMI-A-1"),
+      rule("4", "DP-1")
+    ]);
+  }
+
+  #[test]
+  fn merge_appends_new_keywords() {
+    let merged = merge_conf(
+      "# Hand-written\n", "env", &values(&["A,1"]));
+
+    assert_eq!(merged, "# Hand-written\nenv = A,1\n");
+  }
+}
@@ -19,7 +19,8 @@ use notify_debouncer_full::{
 };
 
 
-use super::{HyprReceiver, HyprlandEvent};
+use super::{HyprReceiver, HyprlandEvent, AppsObject};
+use crate::utils::matching::WindowKeys;
 use crate::utils::notify::DebouncedSender;
 use crate::dconf;
 
@@ -295,10 +296,15 @@ impl TabletInterface {
           window_class: class,
           ..
         } => {
-          let class = class.to_lowercase();
           let bindings = iface.get().await.bindings
             .clone();
-          if !bindings.contains_key(&class) { continue; }
+
+          let class = match binding_key(
+            conn, &class, &bindings).await
+          {
+            Some(k) => k,
+            None => continue
+          };
 
           if bindings[&class] == last_preset { continue }
 
@@ -336,6 +342,33 @@ impl TabletInterface {
 
 
 
+/// Binding key of a window class: the class itself, or
+/// the keys of its desktop entry, since bindings are made
+/// from the app list (`org.kde.krita` vs `krita`)
+async fn binding_key(
+  conn: &zbus::Connection,
+  class: &str,
+  bindings: &TabletBindings
+) -> Option<String> {
+  let class = class.to_lowercase();
+  if bindings.contains_key(&class) {
+    return Some(class)
+  }
+
+  let apps = conn.object_server()
+    .interface::<_, AppsObject>("/apps").await.ok()?;
+  let apps = apps.get().await;
+
+  let keys = apps
+    .with_matching_entry(&WindowKeys::from_class(&class), |e| [
+      e.wm_class.clone(),
+      e.desktop_id.trim_end_matches(".desktop").to_lowercase()
+    ])
+    .await?;
+
+  keys.into_iter().find(|k| bindings.contains_key(k))
+}
+
 fn get_presets() -> Option<HashSet<String>> {
  let presets_dir = get_presets_dir();
 
@@ -0,0 +1,824 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::env::var;
+use std::io::{self, ErrorKind as IOErr};
+
+use chrono::{Local, Timelike};
+
+use zbus::{
+  fdo,
+  interface,
+  object_server::SignalEmitter
+};
+
+use tokio::{
+  process::Command,
+  time::{sleep, Duration, Instant},
+  sync::broadcast::error::RecvError
+};
+
+use super::{HyprReceiver, HyprlandEvent};
+use crate::utils::hyprctl;
+use crate::utils::json::{load_json, save_json};
+use crate::utils::palette::{self, Swatch, PALETTE_SIZE};
+use crate::utils::slideshow::Slideshow;
+
+pub use crate::utils::hyprpaper::{Hyprpaper, WallpaperMode};
+pub use crate::utils::slideshow::RotationConfig;
+
+
+
+/// How long a freshly started hyprpaper gets to open its
+/// socket
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// How often rotations get checked
+const TICK: Duration = Duration::from_secs(5);
+
+
+/// An image assigned to a monitor
+#[derive(
+  serde::Deserialize,
+  serde::Serialize,
+  Default,
+  Debug,
+  Clone,
+  PartialEq
+)]
+#[serde(default)]
+pub struct Wallpaper {
+  pub path: String,
+  pub mode: WallpaperMode,
+}
+
+impl Wallpaper {
+  fn new(path: &str, mode: WallpaperMode) ->
+    fdo::Result<Self>
+  {
+    let file = Path::new(path);
+    if !file.is_absolute() || !file.is_file() {
+      return Err(fdo::Error::InvalidArgs(format!(
+        "{path}: not an image file")))
+    }
+
+    Ok(Self {
+      path: path.to_string(),
+      mode
+    })
+  }
+}
+
+/// Whether window borders are themed, kept across restarts
+/// with the colors to put back
+#[derive(
+  serde::Deserialize,
+  serde::Serialize,
+  Default,
+  Debug,
+  Clone
+)]
+#[serde(default)]
+struct BorderTheme {
+  enabled: bool,
+  /// Active border from before theming, as `keyword`
+  /// takes it
+  original: String,
+}
+
+
+pub struct WallpaperInterface {
+  hyprpaper: Hyprpaper,
+  /// Where assignments are kept across restarts
+  state_path: PathBuf,
+  /// By monitor name, empty for monitors without their own
+  wallpapers: HashMap<String, Wallpaper>,
+  /// Shown but not remembered, by monitor name
+  previews: HashMap<String, Wallpaper>,
+  /// Where the gallery looks for images
+  folders: Vec<String>,
+  /// Rotations by monitor name
+  slideshows: HashMap<String, Slideshow>,
+  /// Monitors whose rotation was paused
+  paused: HashSet<String>,
+  /// Monitors showing a fullscreen window, their rotation
+  /// waits for it to leave
+  fullscreen: HashSet<String>,
+  /// Palettes by image path
+  palettes: HashMap<String, Vec<Swatch>>,
+  /// Whether window borders take the focused monitor's
+  /// wallpaper colors
+  borders: BorderTheme,
+}
+
+#[interface(name = "org.hypr.Hyprmaster.Wallpaper")]
+impl WallpaperInterface {
+  /// Image path by monitor name, an empty name standing
+  /// for monitors without their own
+  #[zbus(property)]
+  fn wallpapers(&self) -> HashMap<String, String> {
+    self.wallpapers
+      .iter()
+      .map(|(m, w)| (m.clone(), w.path.clone()))
+      .collect()
+  }
+
+  /// Shows the image at `path` on `monitor`, or on every
+  /// monitor without its own when empty, and remembers it
+  async fn set_wallpaper(
+    &mut self,
+    #[zbus(signal_emitter)]
+    emitter: SignalEmitter<'_>,
+    monitor: &str,
+    path: &str,
+    mode: WallpaperMode
+  ) -> fdo::Result<()> {
+    let wallpaper = Wallpaper::new(path, mode)?;
+    self.assign(&emitter, monitor, wallpaper).await
+  }
+
+  /// Shows the image at `path` on `monitor` without
+  /// remembering it, until `EndPreview` or `SetWallpaper`
+  async fn preview_wallpaper(
+    &mut self,
+    monitor: &str,
+    path: &str,
+    mode: WallpaperMode
+  ) -> fdo::Result<()> {
+    let wallpaper = Wallpaper::new(path, mode)?;
+
+    self.show(monitor, &wallpaper).await
+      .map_err(|e| fdo::Error::Failed(format!(
+        "hyprpaper: {e}")))?;
+
+    let previous = self.previews
+      .insert(monitor.to_string(), wallpaper);
+
+    if let Some(old) = previous {
+      self.unload_unused(&old.path).await;
+    }
+
+    Ok(())
+  }
+
+  /// Puts the remembered wallpaper back on `monitor`
+  async fn end_preview(&mut self, monitor: &str) ->
+    fdo::Result<()>
+  {
+    let preview = match self.previews.remove(monitor) {
+      Some(p) => p,
+      None => return Ok(())
+    };
+
+    let remembered = self.wallpapers
+      .get(monitor)
+      .or(self.wallpapers.get(""))
+      .cloned();
+
+    if let Some(wallpaper) = remembered {
+      self.show(monitor, &wallpaper).await
+        .map_err(|e| fdo::Error::Failed(format!(
+          "hyprpaper: {e}")))?;
+    }
+
+    self.unload_unused(&preview.path).await;
+    Ok(())
+  }
+
+  /// Absolute paths of the folders the gallery shows
+  #[zbus(property)]
+  fn folders(&self) -> Vec<String> {
+    self.folders.clone()
+  }
+
+  #[zbus(property)]
+  fn set_folders(&mut self, folders: Vec<String>) ->
+    fdo::Result<()>
+  {
+    if let Some(f) = folders
+      .iter()
+      .find(|f| !Path::new(f).is_absolute())
+    {
+      return Err(fdo::Error::InvalidArgs(format!(
+        "{f}: not an absolute path")))
+    }
+
+    save_json(&self.folders_path(), &folders)
+      .map_err(|e| fdo::Error::IOError(e.to_string()))?;
+
+    self.folders = folders;
+    Ok(())
+  }
+
+  fn rotations(&self) -> Vec<RotationConfig> {
+    let mut rotations: Vec<RotationConfig> = self.slideshows
+      .values()
+      .map(|s| s.config.clone())
+      .collect();
+
+    rotations.sort_by(|a, b| a.monitor.cmp(&b.monitor));
+    rotations
+  }
+
+  /// Starts rotating wallpapers on the config's monitor,
+  /// replacing its rotation if it has one
+  async fn set_rotation(
+    &mut self,
+    #[zbus(signal_emitter)]
+    emitter: SignalEmitter<'_>,
+    config: RotationConfig
+  ) -> fdo::Result<()> {
+    config.validate().map_err(fdo::Error::InvalidArgs)?;
+
+    let monitor = config.monitor.clone();
+    self.slideshows.insert(
+      monitor.clone(), Slideshow::new(config, minute_now()));
+
+    self.save_rotations()?;
+    self.step(&emitter, &monitor, true).await
+  }
+
+  async fn remove_rotation(
+    &mut self,
+    #[zbus(signal_emitter)]
+    emitter: SignalEmitter<'_>,
+    monitor: &str
+  ) -> fdo::Result<()> {
+    if self.slideshows.remove(monitor).is_none() {
+      return Err(fdo::Error::InvalidArgs(format!(
+        "{monitor}: no rotation")))
+    }
+
+    self.save_rotations()?;
+
+    if self.paused.remove(monitor) {
+      self.paused_changed(&emitter).await?;
+    }
+
+    Ok(())
+  }
+
+  /// Skips to the next image of `monitor`'s rotation
+  async fn next(
+    &mut self,
+    #[zbus(signal_emitter)]
+    emitter: SignalEmitter<'_>,
+    monitor: &str
+  ) -> fdo::Result<()> {
+    self.step(&emitter, monitor, true).await
+  }
+
+  /// Goes back to the image before in `monitor`'s rotation
+  async fn previous(
+    &mut self,
+    #[zbus(signal_emitter)]
+    emitter: SignalEmitter<'_>,
+    monitor: &str
+  ) -> fdo::Result<()> {
+    self.step(&emitter, monitor, false).await
+  }
+
+  /// Pauses or resumes `monitor`'s rotation, or every
+  /// rotation when empty
+  async fn pause(
+    &mut self,
+    #[zbus(signal_emitter)]
+    emitter: SignalEmitter<'_>,
+    monitor: &str,
+    paused: bool
+  ) -> fdo::Result<()> {
+    let monitors: Vec<String> = match monitor {
+      "" => self.slideshows.keys().cloned().collect(),
+      m if self.slideshows.contains_key(m) => vec![m.into()],
+      m => return Err(fdo::Error::InvalidArgs(format!(
+        "{m}: no rotation")))
+    };
+
+    for monitor in monitors {
+      match paused {
+        true => self.paused.insert(monitor),
+        false => self.paused.remove(&monitor)
+      };
+    }
+
+    self.paused_changed(&emitter).await?;
+    Ok(())
+  }
+
+  /// Monitors whose rotation is paused
+  #[zbus(property)]
+  fn paused(&self) -> Vec<String> {
+    let mut paused: Vec<String> = self.paused
+      .iter()
+      .cloned()
+      .collect();
+
+    paused.sort();
+    paused
+  }
+
+  /// Dominant colors of the wallpaper shown on `monitor`,
+  /// as `#rrggbb`, the most common first
+  async fn palette(&mut self, monitor: &str) ->
+    fdo::Result<Vec<String>>
+  {
+    Ok(self.swatches(monitor).await?
+      .iter()
+      .map(|s| palette::hex(s.color))
+      .collect())
+  }
+
+  /// The color of `monitor`'s wallpaper themes should
+  /// build on, as `#rrggbb`
+  async fn accent(&mut self, monitor: &str) ->
+    fdo::Result<String>
+  {
+    let swatches = self.swatches(monitor).await?;
+
+    palette::accents(&swatches)
+      .first()
+      .map(|c| palette::hex(*c))
+      .ok_or(fdo::Error::Failed(format!(
+        "{monitor}: wallpaper has no colors")))
+  }
+
+  /// Whether Hyprland's active border follows the accents
+  /// of the focused monitor's wallpaper
+  #[zbus(property)]
+  fn themed_borders(&self) -> bool {
+    self.borders.enabled
+  }
+
+  #[zbus(property)]
+  async fn set_themed_borders(&mut self, themed: bool) ->
+    fdo::Result<()>
+  {
+    if themed == self.borders.enabled { return Ok(()) }
+
+    // Taken before theming touches it, and saved so a
+    // restart still knows what to put back
+    let original = match themed {
+      true => hyprctl::option_custom(
+        "general:col.active_border").await
+        .map(|c| keyword_gradient(&c))
+        .map_err(|e| fdo::Error::IOError(e.to_string()))?,
+      false => String::new()
+    };
+
+    let borders = BorderTheme { enabled: themed, original };
+    save_json(&self.borders_path(), &borders)
+      .map_err(|e| fdo::Error::IOError(e.to_string()))?;
+
+    let previous = std::mem::replace(
+      &mut self.borders, borders);
+
+    match themed {
+      true => self.update_borders().await,
+      false => reset_borders(&previous.original).await
+    }
+
+    Ok(())
+  }
+}
+
+impl Default for WallpaperInterface {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl WallpaperInterface {
+  pub fn new() -> Self {
+    Self::with(Hyprpaper::default(), wallpapers_path())
+  }
+
+  /// Interface talking to `hyprpaper` and keeping its
+  /// assignments at `state_path`
+  pub fn with(hyprpaper: Hyprpaper, state_path: PathBuf) ->
+    Self
+  {
+    let minute = minute_now();
+    let rotations: Vec<RotationConfig> = load_json(&sibling(
+      &state_path, "wallpaper-rotations.json"));
+    let slideshows = rotations
+      .into_iter()
+      .map(|c| (c.monitor.clone(), Slideshow::new(c, minute)))
+      .collect();
+
+    Self {
+      wallpapers: load_json(&state_path),
+      previews: HashMap::new(),
+      folders: load_folders(&sibling(
+        &state_path, "wallpaper-folders.json")),
+      slideshows,
+      paused: HashSet::new(),
+      fullscreen: HashSet::new(),
+      palettes: HashMap::new(),
+      borders: load_json(&sibling(
+        &state_path, "wallpaper-borders.json")),
+      hyprpaper,
+      state_path
+    }
+  }
+
+  fn borders_path(&self) -> PathBuf {
+    sibling(&self.state_path, "wallpaper-borders.json")
+  }
+
+  fn folders_path(&self) -> PathBuf {
+    sibling(&self.state_path, "wallpaper-folders.json")
+  }
+
+  fn save_rotations(&self) -> fdo::Result<()> {
+    let path = sibling(
+      &self.state_path, "wallpaper-rotations.json");
+
+    save_json(&path, &self.rotations())
+      .map_err(|e| fdo::Error::IOError(e.to_string()))
+  }
+
+  /// Shows `wallpaper` on `monitor` and remembers it,
+  /// ending any preview there
+  async fn assign(
+    &mut self,
+    emitter: &SignalEmitter<'_>,
+    monitor: &str,
+    wallpaper: Wallpaper
+  ) -> fdo::Result<()> {
+    self.show(monitor, &wallpaper).await
+      .map_err(|e| fdo::Error::Failed(format!(
+        "hyprpaper: {e}")))?;
+
+    let previous = self.wallpapers
+      .insert(monitor.to_string(), wallpaper);
+    let preview = self.previews.remove(monitor);
+
+    for old in previous.into_iter().chain(preview) {
+      self.unload_unused(&old.path).await;
+    }
+
+    save_json(&self.state_path, &self.wallpapers)
+      .map_err(|e| fdo::Error::IOError(e.to_string()))?;
+
+    self.wallpapers_changed(emitter).await?;
+    self.update_borders().await;
+    Ok(())
+  }
+
+  /// Palette of the wallpaper shown on `monitor`, made once
+  /// per image
+  async fn swatches(&mut self, monitor: &str) ->
+    fdo::Result<Vec<Swatch>>
+  {
+    let path = match self.previews
+      .get(monitor)
+      .or(self.wallpapers.get(monitor))
+      .or(self.wallpapers.get(""))
+    {
+      Some(w) => w.path.clone(),
+      None => return Err(fdo::Error::InvalidArgs(format!(
+        "{monitor}: no wallpaper")))
+    };
+
+    if let Some(swatches) = self.palettes.get(&path) {
+      return Ok(swatches.clone())
+    }
+
+    let image = PathBuf::from(&path);
+    let swatches = tokio::task::spawn_blocking(
+      move || palette::extract(&image, PALETTE_SIZE)).await
+      .map_err(|e| fdo::Error::Failed(e.to_string()))?
+      .map_err(|e| fdo::Error::Failed(format!(
+        "{path}: {e}")))?;
+
+    self.palettes.insert(path, swatches.clone());
+    Ok(swatches)
+  }
+
+  /// Colors the active border after the focused monitor's
+  /// wallpaper, when borders are themed
+  async fn update_borders(&mut self) {
+    if !self.borders.enabled { return }
+
+    let monitor = match hyprctl::active_workspace().await {
+      Ok(w) => w.monitor,
+      Err(e) => {
+        eprintln!("Failed to get workspace: {:#?}", e);
+        return
+      }
+    };
+
+    let accents = match self.swatches(&monitor).await {
+      Ok(s) => palette::accents(&s),
+      Err(e) => {
+        eprintln!("Failed to get palette of {monitor}: {e}");
+        return
+      }
+    };
+
+    // Two accents make the gradient Hyprland's own
+    // default has
+    let value = match accents.as_slice() {
+      [] => return,
+      [one] => border_color(*one),
+      [first, second, ..] => format!(
+        "{} {} 45deg",
+        border_color(*first),
+        border_color(*second))
+    };
+
+    let res = hyprctl::keyword(
+      "general:col.active_border", &value).await;
+
+    if let Err(e) = res {
+      eprintln!("Failed to set border colors: {:#?}", e);
+    }
+  }
+
+  /// Moves `monitor`'s rotation forward or back and shows
+  /// the image it lands on
+  async fn step(
+    &mut self,
+    emitter: &SignalEmitter<'_>,
+    monitor: &str,
+    forward: bool
+  ) -> fdo::Result<()> {
+    let slideshow = match self.slideshows.get_mut(monitor) {
+      Some(s) => s,
+      None => return Err(fdo::Error::InvalidArgs(format!(
+        "{monitor}: no rotation")))
+    };
+
+    let minute = minute_now();
+    let image = match forward {
+      true => slideshow.next(minute),
+      false => slideshow.previous(minute)
+    };
+
+    let wallpaper = match image {
+      Some(path) => Wallpaper::new(
+        &path, slideshow.config.mode)?,
+      None => return Err(fdo::Error::Failed(format!(
+        "{monitor}: no images to rotate")))
+    };
+
+    self.assign(emitter, monitor, wallpaper).await
+  }
+
+  /// Moves on the rotations that are due, leaving paused
+  /// ones, previews and fullscreen windows alone
+  async fn rotate(&mut self, emitter: &SignalEmitter<'_>) {
+    let (now, minute) = (Instant::now(), minute_now());
+
+    let due: Vec<String> = self.slideshows
+      .iter()
+      .filter(|(m, s)| s.is_due(now.into_std(), minute) &&
+        !self.paused.contains(*m) &&
+        !self.fullscreen.contains(*m) &&
+        !self.previews.contains_key(*m))
+      .map(|(m, _)| m.clone())
+      .collect();
+
+    for monitor in due {
+      if let Err(e) = self.step(emitter, &monitor, true).await {
+        eprintln!("Failed to rotate {monitor}: {:#?}", e);
+      }
+    }
+  }
+
+  /// Rotates wallpapers for as long as the daemon runs
+  pub async fn run(conn: &zbus::Connection) ->
+    Result<(), Box<dyn Error>>
+  {
+    let iface = conn.object_server()
+      .interface::<_, WallpaperInterface>("/wallpaper")
+      .await?;
+
+    loop {
+      sleep(TICK).await;
+
+      iface.get_mut().await
+        .rotate(iface.signal_emitter()).await;
+    }
+  }
+
+  /// Tracks whether the focused monitor shows a fullscreen
+  /// window
+  async fn update_fullscreen(&mut self, fullscreen: Option<bool>) {
+    let workspace = match hyprctl::active_workspace().await {
+      Ok(w) => w,
+      Err(e) => {
+        eprintln!("Failed to get workspace: {:#?}", e);
+        return
+      }
+    };
+
+    match fullscreen.unwrap_or(workspace.has_fullscreen) {
+      true => self.fullscreen.insert(workspace.monitor),
+      false => self.fullscreen.remove(&workspace.monitor)
+    };
+  }
+
+  /// Frees `path` in hyprpaper unless a monitor still
+  /// shows it
+  async fn unload_unused(&self, path: &str) {
+    let used = self.wallpapers
+      .values()
+      .chain(self.previews.values())
+      .any(|w| w.path == path);
+
+    if used { return }
+
+    if let Err(e) = self.hyprpaper.unload(path).await {
+      eprintln!("Failed to unload {path}: {e}");
+    }
+  }
+
+  /// Shows the remembered wallpapers again, like after a
+  /// restart
+  pub async fn restore(&self) -> io::Result<()> {
+    // Monitors without their own go first, so they don't
+    // override the others
+    let mut monitors: Vec<&String> = self.wallpapers
+      .keys()
+      .collect();
+    monitors.sort();
+
+    for monitor in monitors {
+      self.show(monitor, &self.wallpapers[monitor]).await?;
+    }
+
+    Ok(())
+  }
+
+  /// Preloads `wallpaper` if needed and shows it on
+  /// `monitor`, starting hyprpaper when it isn't running
+  async fn show(
+    &self,
+    monitor: &str,
+    wallpaper: &Wallpaper
+  ) -> io::Result<()> {
+    let loaded = match self.hyprpaper.loaded().await {
+      Err(e) if matches!(
+        e.kind(),
+        IOErr::NotFound | IOErr::ConnectionRefused
+      ) => self.start_hyprpaper().await?,
+      res => res?
+    };
+
+    if !loaded.contains(&wallpaper.path) {
+      self.hyprpaper.preload(&wallpaper.path).await?;
+    }
+
+    self.hyprpaper
+      .wallpaper(monitor, &wallpaper.path, wallpaper.mode)
+      .await
+  }
+
+  /// Starts hyprpaper and waits for its socket, returning
+  /// what it preloaded from its own config
+  async fn start_hyprpaper(&self) -> io::Result<Vec<String>> {
+    Command::new("hyprpaper")
+      .stdout(Stdio::null())
+      .stderr(Stdio::null())
+      .spawn()?;
+
+    let step = Duration::from_millis(100);
+    let mut waited = Duration::ZERO;
+
+    loop {
+      sleep(step).await;
+      waited += step;
+
+      match self.hyprpaper.loaded().await {
+        Err(_) if waited < STARTUP_TIMEOUT => continue,
+        res => return res
+      }
+    }
+  }
+
+  /// Restores the wallpapers and puts them back on
+  /// monitors plugged in again
+  pub(crate) async fn listen(
+    conn: &zbus::Connection,
+    mut hrx: HyprReceiver
+  ) -> Result<(), Box<dyn Error>> {
+    let iface = conn.object_server()
+      .interface::<_, WallpaperInterface>("/wallpaper")
+      .await?;
+
+    if let Err(e) = iface.get().await.restore().await {
+      eprintln!("Failed to restore wallpapers: {:#?}", e);
+    }
+
+    iface.get_mut().await.update_borders().await;
+
+    loop {
+      match hrx.recv().await {
+        Ok(HyprlandEvent::MonitorAdded {
+          monitor_name, ..
+        }) => {
+          let iface = iface.get().await;
+          let wallpaper = match iface
+            .wallpapers
+            .get(&monitor_name)
+          {
+            Some(w) => w,
+            None => continue
+          };
+
+          if let Err(e) = iface
+            .show(&monitor_name, wallpaper).await
+          {
+            eprintln!(
+              "Failed to set wallpaper of {monitor_name}: {:#?}",
+              e);
+          }
+        },
+        Ok(HyprlandEvent::Fullscreen { is_fullscreen }) => {
+          iface.get_mut().await
+            .update_fullscreen(Some(is_fullscreen)).await;
+        },
+        Ok(HyprlandEvent::WorkspaceChanged { .. }) => {
+          iface.get_mut().await
+            .update_fullscreen(None).await;
+        },
+        Ok(HyprlandEvent::FocusedMonitor { .. }) => {
+          let mut iface = iface.get_mut().await;
+
+          iface.update_fullscreen(None).await;
+          iface.update_borders().await;
+        },
+        Ok(_) | Err(RecvError::Lagged(_)) => continue,
+        Err(RecvError::Closed) => break
+      }
+    }
+
+    Ok(())
+  }
+}
+
+
+
+/// `$XDG_CONFIG_HOME/hyprmaster/wallpapers.json`
+pub fn wallpapers_path() -> PathBuf {
+  let home_dir = var("HOME").unwrap();
+  let xdg_conf_dir = var("XDG_CONFIG_HOME")
+    .unwrap_or(format!("{home_dir}/.config"));
+
+  PathBuf::from(format!(
+    "{xdg_conf_dir}/hyprmaster/wallpapers.json"))
+}
+
+/// Minutes since local midnight
+fn minute_now() -> u32 {
+  let now = Local::now();
+  now.hour() * 60 + now.minute()
+}
+
+/// `name` next to the wallpapers file, where the rest of
+/// the state goes
+fn sibling(state_path: &Path, name: &str) -> PathBuf {
+  state_path.with_file_name(name)
+}
+
+/// The saved folders, `~/Pictures/Wallpapers` at first
+fn load_folders(path: &Path) -> Vec<String> {
+  load_json::<Option<Vec<String>>>(path)
+    .unwrap_or_else(|| {
+      let home_dir = var("HOME").unwrap_or_default();
+      vec![format!("{home_dir}/Pictures/Wallpapers")]
+    })
+}
+
+/// `rgb(rrggbb)`, as Hyprland takes colors
+fn border_color(color: palette::Rgb) -> String {
+  let [r, g, b] = color;
+  format!("rgb({r:02x}{g:02x}{b:02x})")
+}
+
+/// `j/getoption` lists gradients as bare `aarrggbb` colors,
+/// which `keyword` only takes as `0x` hex
+fn keyword_gradient(custom: &str) -> String {
+  custom
+    .split_whitespace()
+    .map(|part| match part.ends_with("deg") {
+      true => part.to_string(),
+      false => format!("0x{part}")
+    })
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
+/// Puts the border colors from before theming back
+async fn reset_borders(original: &str) {
+  if original.is_empty() { return }
+
+  let res = hyprctl::keyword(
+    "general:col.active_border", original).await;
+
+  if let Err(e) = res {
+    eprintln!("Failed to reset border colors: {:#?}", e);
+  }
+}
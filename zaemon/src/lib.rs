@@ -15,3 +15,17 @@ pub mod icons {
 pub mod tablet {
   pub use super::interfaces::tablet::*;
 }
+
+pub mod screen {
+  pub use super::interfaces::screen::*;
+  pub use super::utils::brightness::{Backlight, DdcDisplay};
+}
+
+pub mod nightlight {
+  pub use super::interfaces::nightlight::*;
+}
+
+pub mod wallpaper {
+  pub use super::interfaces::wallpaper::*;
+  pub use super::utils::slideshow::ImageSource;
+}
@@ -10,6 +10,9 @@ use interfaces::{
   AppsObject,
   IconsObject,
   TabletInterface,
+  ScreenInterface,
+  NightLightInterface,
+  WallpaperInterface,
   HyprlandInterface
 };
 
@@ -21,6 +24,9 @@ async fn main() -> Result<(), Box<dyn Error>> {
     .name("org.hypr.Hyprmaster")?
     .serve_at("/hyprland", HyprlandInterface::new())?
     .serve_at("/tablet", TabletInterface::new())?
+    .serve_at("/screen", ScreenInterface::new())?
+    .serve_at("/nightlight", NightLightInterface::new())?
+    .serve_at("/wallpaper", WallpaperInterface::new())?
     .serve_at("/icons", IconsObject::new())?
     .serve_at("/apps", AppsObject::new())?
     .max_queued(300)
@@ -30,8 +36,12 @@ async fn main() -> Result<(), Box<dyn Error>> {
   let (hsx, hrx) = HyprlandInterface::spawn_listener();
 
   _ = tokio::join!(
-    AppsObject::listen(&conn),
+    AppsObject::listen(&conn, hsx.subscribe()),
     TabletInterface::listen(&conn, hsx.subscribe()),
+    ScreenInterface::listen(&conn, hsx.subscribe()),
+    NightLightInterface::run(&conn),
+    WallpaperInterface::listen(&conn, hsx.subscribe()),
+    WallpaperInterface::run(&conn),
     HyprlandInterface::listen(&conn, hrx)
   );
 
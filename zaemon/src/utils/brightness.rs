@@ -0,0 +1,368 @@
+/* -
+ * -> Monitor brightness, through the kernel's backlight
+ *    class for panels and DDC/CI for external monitors
+ * -
+*/
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write, ErrorKind as IOErr};
+use std::os::fd::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use zbus::proxy;
+
+
+
+/// Where sysfs and device nodes live, swapped out by tests
+#[derive(Debug, Clone)]
+pub struct DeviceRoots {
+  pub sys: PathBuf,
+  pub dev: PathBuf,
+}
+
+impl Default for DeviceRoots {
+  fn default() -> Self {
+    Self {
+      sys: PathBuf::from("/sys"),
+      dev: PathBuf::from("/dev")
+    }
+  }
+}
+
+impl DeviceRoots {
+  /// DRM connector dir of `connector`, like
+  /// `/sys/class/drm/card1-eDP-1`
+  fn drm_connector(&self, connector: &str) -> Option<PathBuf> {
+    let suffix = format!("-{connector}");
+
+    fs::read_dir(self.sys.join("class/drm"))
+      .ok()?
+      .flatten()
+      .map(|e| e.path())
+      .find(|p| p
+        .file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| n.starts_with("card") &&
+          n.ends_with(&suffix)))
+  }
+}
+
+
+/// How a monitor's brightness gets set
+#[derive(Debug, Clone)]
+pub enum BrightnessControl {
+  Backlight(Backlight),
+  Ddc(DdcDisplay),
+}
+
+impl BrightnessControl {
+  /// Control for the monitor on `connector`, the panel's
+  /// backlight for built-in ones and DDC/CI for the rest
+  pub fn for_connector(
+    roots: &DeviceRoots,
+    connector: &str
+  ) -> Option<Self> {
+    Backlight::for_connector(roots, connector)
+      .map(Self::Backlight)
+      .or_else(|| DdcDisplay::for_connector(roots, connector)
+        .map(Self::Ddc))
+  }
+
+  /// Brightness from 0 to 100
+  pub async fn percent(&self) -> io::Result<f64> {
+    match self {
+      Self::Backlight(b) => b.percent(),
+      Self::Ddc(d) => {
+        let ddc = d.clone();
+        let (current, max) = tokio::task::spawn_blocking(
+          move || ddc.brightness()).await??;
+
+        Ok(to_percent(current as u32, max as u32))
+      }
+    }
+  }
+
+  /// Sets the brightness, backlights going through logind
+  /// on `system_bus`
+  pub async fn set_percent(
+    &self,
+    system_bus: &zbus::Connection,
+    percent: f64
+  ) -> io::Result<()> {
+    match self {
+      Self::Backlight(b) => b
+        .set_percent(system_bus, percent).await,
+      Self::Ddc(d) => {
+        let ddc = d.clone();
+        tokio::task::spawn_blocking(move || {
+          let (_, max) = ddc.brightness()?;
+          ddc.set_brightness(from_percent(percent, max as u32)
+            as u16)
+        }).await?
+      }
+    }
+  }
+}
+
+
+#[proxy(
+  interface = "org.freedesktop.login1.Session",
+  default_service = "org.freedesktop.login1",
+  default_path = "/org/freedesktop/login1/session/auto"
+)]
+trait LogindSession {
+  fn set_brightness(
+    &self,
+    subsystem: &str,
+    name: &str,
+    brightness: u32
+  ) -> zbus::Result<()>;
+}
+
+
+/// A device of the kernel's backlight class
+#[derive(Debug, Clone)]
+pub struct Backlight {
+  pub name: String,
+  path: PathBuf,
+}
+
+impl Backlight {
+  /// Backlight of the panel on `connector`. The DRM driver
+  /// links it under the connector when it knows, otherwise
+  /// built-in panels get the best one around.
+  pub fn for_connector(
+    roots: &DeviceRoots,
+    connector: &str
+  ) -> Option<Self> {
+    let class_dir = roots.sys.join("class/backlight");
+
+    let linked = roots.drm_connector(connector)
+      .and_then(|dir| fs::read_dir(dir).ok())
+      .into_iter()
+      .flatten()
+      .flatten()
+      .map(|e| e.file_name().to_string_lossy().to_string())
+      .find(|name| class_dir.join(name).is_dir());
+
+    if let Some(name) = linked {
+      return Some(Self { path: class_dir.join(&name), name })
+    }
+
+    if !is_builtin(connector) { return None }
+
+    // The kernel's own preference
+    let rank = |kind: &str| match kind {
+      "firmware" => 0,
+      "platform" => 1,
+      _ => 2
+    };
+
+    fs::read_dir(&class_dir)
+      .ok()?
+      .flatten()
+      .map(|e| e.path())
+      .min_by_key(|p| rank(
+        fs::read_to_string(p.join("type"))
+          .unwrap_or_default()
+          .trim()))
+      .map(|path| Self {
+        name: path
+          .file_name()
+          .map(|n| n.to_string_lossy().to_string())
+          .unwrap_or_default(),
+        path
+      })
+  }
+
+  pub fn brightness(&self) -> io::Result<u32> {
+    read_u32(&self.path.join("brightness"))
+  }
+
+  pub fn max_brightness(&self) -> io::Result<u32> {
+    read_u32(&self.path.join("max_brightness"))
+  }
+
+  pub fn percent(&self) -> io::Result<f64> {
+    Ok(to_percent(self.brightness()?, self.max_brightness()?))
+  }
+
+  /// Sets the brightness through logind, which lets the
+  /// session's user write it. Never goes fully dark, some
+  /// panels turn off at 0.
+  pub async fn set_percent(
+    &self,
+    system_bus: &zbus::Connection,
+    percent: f64
+  ) -> io::Result<()> {
+    let max = self.max_brightness()?;
+    let value = from_percent(percent, max).max(1).min(max);
+
+    LogindSessionProxy::new(system_bus).await
+      .map_err(io::Error::other)?
+      .set_brightness("backlight", &self.name, value).await
+      .map_err(io::Error::other)
+  }
+}
+
+
+/// Slave address of a monitor's DDC/CI interface
+const DDC_ADDR: u16 = 0x37;
+/// Source address of the host in DDC/CI requests
+const DDC_SOURCE: u8 = 0x51;
+/// `ioctl` binding an i2c-dev file to a slave address
+const I2C_SLAVE: u64 = 0x0703;
+
+/// VCP feature code of the luminance
+const VCP_BRIGHTNESS: u8 = 0x10;
+
+const VCP_GET: u8 = 0x01;
+const VCP_REPLY: u8 = 0x02;
+const VCP_SET: u8 = 0x03;
+
+/// What monitors need between a request and its reply
+const DDC_DELAY: Duration = Duration::from_millis(50);
+
+
+/// An external monitor reachable over DDC/CI
+#[derive(Debug, Clone)]
+pub struct DdcDisplay {
+  pub bus: PathBuf,
+}
+
+impl DdcDisplay {
+  /// DDC bus of the monitor on `connector`, linked as `ddc`
+  /// by HDMI and DVI connectors and held as the AUX channel
+  /// by DisplayPort ones
+  pub fn for_connector(
+    roots: &DeviceRoots,
+    connector: &str
+  ) -> Option<Self> {
+    let dir = roots.drm_connector(connector)?;
+
+    let linked = fs::read_link(dir.join("ddc"))
+      .ok()
+      .and_then(|t| t
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string()));
+
+    let aux = || fs::read_dir(&dir)
+      .ok()?
+      .flatten()
+      .map(|e| e.file_name().to_string_lossy().to_string())
+      .find(|n| n.starts_with("i2c-"));
+
+    linked
+      .or_else(aux)
+      .filter(|n| n.starts_with("i2c-"))
+      .map(|n| Self { bus: roots.dev.join(n) })
+  }
+
+  /// Current and maximum brightness
+  pub fn brightness(&self) -> io::Result<(u16, u16)> {
+    let mut bus = self.open()?;
+
+    bus.write_all(&ddc_packet(&[VCP_GET, VCP_BRIGHTNESS]))?;
+    std::thread::sleep(DDC_DELAY);
+
+    let mut reply = [0u8; 11];
+    bus.read_exact(&mut reply)?;
+
+    let checksum = reply[..10]
+      .iter()
+      .fold(0x50, |acc, b| acc ^ b);
+
+    let invalid = |msg: &str| Err(io::Error::new(
+      IOErr::InvalidData, format!("DDC/CI: {msg}")));
+
+    if checksum != reply[10] {
+      return invalid("bad checksum")
+    }
+
+    if reply[2] != VCP_REPLY || reply[4] != VCP_BRIGHTNESS {
+      return invalid("unexpected reply")
+    }
+
+    if reply[3] != 0 {
+      return invalid("brightness not supported")
+    }
+
+    let max = u16::from_be_bytes([reply[6], reply[7]]);
+    let current = u16::from_be_bytes([reply[8], reply[9]]);
+
+    Ok((current, max))
+  }
+
+  pub fn set_brightness(&self, value: u16) -> io::Result<()> {
+    let [hi, lo] = value.to_be_bytes();
+
+    let mut bus = self.open()?;
+    bus.write_all(&ddc_packet(
+      &[VCP_SET, VCP_BRIGHTNESS, hi, lo]))?;
+
+    // Monitors ignore requests coming in too fast
+    std::thread::sleep(DDC_DELAY);
+    Ok(())
+  }
+
+  fn open(&self) -> io::Result<File> {
+    let file = OpenOptions::new()
+      .read(true)
+      .write(true)
+      .open(&self.bus)?;
+
+    let res = unsafe {
+      libc::ioctl(
+        file.as_raw_fd(),
+        I2C_SLAVE as _,
+        DDC_ADDR as libc::c_ulong)
+    };
+
+    match res {
+      0 => Ok(file),
+      _ => Err(io::Error::last_os_error())
+    }
+  }
+}
+
+
+/// Frames a DDC/CI message to the monitor, with the length
+/// and checksum bytes
+fn ddc_packet(payload: &[u8]) -> Vec<u8> {
+  let mut packet = vec![DDC_SOURCE, 0x80 | payload.len() as u8];
+  packet.extend_from_slice(payload);
+
+  let checksum = packet
+    .iter()
+    .fold((DDC_ADDR << 1) as u8, |acc, b| acc ^ b);
+  packet.push(checksum);
+
+  packet
+}
+
+/// Panels built into the device, as opposed to plugged in
+fn is_builtin(connector: &str) -> bool {
+  ["eDP", "LVDS", "DSI"]
+    .iter()
+    .any(|p| connector.starts_with(p))
+}
+
+fn read_u32(path: &Path) -> io::Result<u32> {
+  fs::read_to_string(path)?
+    .trim()
+    .parse()
+    .map_err(|e| io::Error::new(IOErr::InvalidData, e))
+}
+
+fn to_percent(value: u32, max: u32) -> f64 {
+  match max {
+    0 => 0.0,
+    max => value as f64 * 100.0 / max as f64
+  }
+}
+
+fn from_percent(percent: f64, max: u32) -> u32 {
+  (percent.clamp(0.0, 100.0) / 100.0 * max as f64).round()
+    as u32
+}
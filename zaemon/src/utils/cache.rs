@@ -0,0 +1,98 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use serde::{Serialize, de::DeserializeOwned};
+
+use zbus::zvariant::{
+  self,
+  Type,
+  LE,
+  serialized::{Context, Data}
+};
+
+
+
+/// `$XDG_CACHE_HOME/hyprmaster`
+pub fn cache_dir() -> PathBuf {
+  let home_dir = std::env::var("HOME").unwrap();
+  let xdg_cache_dir = std::env::var("XDG_CACHE_HOME")
+    .ok()
+    .filter(|d| !d.trim().is_empty())
+    .unwrap_or(format!("{home_dir}/.cache"));
+
+  PathBuf::from(xdg_cache_dir).join("hyprmaster")
+}
+
+/// Modification time of `path` in nanoseconds, 0 when it
+/// can't be read
+pub fn mtime(path: &Path) -> u64 {
+  std::fs::metadata(path)
+    .and_then(|m| m.modified())
+    .ok()
+    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+    .map(|d| d.as_nanos() as u64)
+    .unwrap_or(0)
+}
+
+/// Reads `name` from the cache dir. Missing, unreadable or
+/// outdated cache files all read as `None`.
+pub fn read<T>(name: &str) -> Option<T>
+where
+  T: DeserializeOwned + Type
+{
+  read_from(&cache_dir().join(name))
+}
+
+/// Writes `value` to `name` in the cache dir
+pub fn write<T>(name: &str, value: &T) -> io::Result<()>
+where
+  T: Serialize + Type
+{
+  write_to(&cache_dir().join(name), value)
+}
+
+/// Reads a value serialized by [`write_to`] from `path`
+pub fn read_from<T>(path: &Path) -> Option<T>
+where
+  T: DeserializeOwned + Type
+{
+  let bytes = std::fs::read(path).ok()?;
+  let ctxt = Context::new_dbus(LE, 0);
+
+  Data::new(bytes, ctxt)
+    .deserialize::<T>()
+    .ok()
+    .map(|(value, _)| value)
+}
+
+/// Serializes `value` to `path`, through a temporary file
+/// so readers never see it half-written
+pub fn write_to<T>(path: &Path, value: &T) -> io::Result<()>
+where
+  T: Serialize + Type
+{
+  if let Some(dir) = path.parent() {
+    std::fs::create_dir_all(dir)?;
+  }
+
+  let ctxt = Context::new_dbus(LE, 0);
+  let data = zvariant::to_bytes(ctxt, value)
+    .map_err(io::Error::other)?;
+
+  write_atomic(path, data.bytes())
+}
+
+/// Writes `contents` to a temporary file next to `path`
+/// and moves it over, so a crash never leaves `path`
+/// half-written
+pub fn write_atomic(
+  path: &Path,
+  contents: impl AsRef<[u8]>
+) -> io::Result<()> {
+  let mut tmp = path.as_os_str().to_owned();
+  tmp.push(".tmp");
+
+  std::fs::write(&tmp, contents)?;
+  std::fs::rename(tmp, path)
+}
@@ -0,0 +1,327 @@
+/* -
+ * -> Desktop Entry `Exec` handling
+ * -
+*/
+
+/// What field codes expand to when launching an entry
+pub struct ExecContext<'a> {
+  pub uris: &'a [String],
+  pub icon: &'a str,
+  pub name: &'a str,
+  pub entry_path: &'a str,
+}
+
+/// Splits an `Exec` value into its arguments, undoing the
+/// string escapes and the quoting rules of the spec.
+/// Returns `None` for unbalanced quotes.
+pub fn split_exec(exec: &str) -> Option<Vec<String>> {
+  let exec = unescape_string(exec);
+
+  let mut args: Vec<String> = vec![];
+  let mut arg = String::new();
+  let mut in_arg = false;
+  let mut quoted = false;
+  let mut chars = exec.chars();
+
+  while let Some(c) = chars.next() {
+    match c {
+      '"' => {
+        quoted = !quoted;
+        in_arg = true;
+      },
+      '\\' if quoted => match chars.next() {
+        Some(e @ ('"' | '`' | '$' | '\\')) => arg.push(e),
+        Some(e) => { arg.push('\\'); arg.push(e) },
+        None => return None
+      },
+      ' ' | '\t' if !quoted => {
+        if in_arg {
+          args.push(std::mem::take(&mut arg));
+          in_arg = false;
+        }
+      },
+      _ => {
+        arg.push(c);
+        in_arg = true;
+      }
+    }
+  }
+
+  if quoted { return None }
+  if in_arg { args.push(arg) }
+
+  Some(args)
+}
+
+// Undoes the escapes any string value of a desktop file
+// may carry
+fn unescape_string(value: &str) -> String {
+  let mut out = String::with_capacity(value.len());
+  let mut chars = value.chars();
+
+  while let Some(c) = chars.next() {
+    if c != '\\' {
+      out.push(c);
+      continue;
+    }
+
+    match chars.next() {
+      Some('s') => out.push(' '),
+      Some('n') => out.push('\n'),
+      Some('t') => out.push('\t'),
+      Some('r') => out.push('\r'),
+      Some('\\') => out.push('\\'),
+      Some(e) => { out.push('\\'); out.push(e) },
+      None => out.push('\\')
+    }
+  }
+
+  out
+}
+
+/// Expands the field codes in `args`, returning one
+/// command line per process that has to be spawned.
+///
+/// Entries that only take a single file or URL (`%f`,
+/// `%u`) are launched once per URI.
+pub fn expand_exec(
+  args: &[String],
+  ctx: &ExecContext
+) -> Vec<Vec<String>> {
+  let single = args
+    .iter()
+    .flat_map(|a| field_codes(a))
+    .any(|c| c == 'f' || c == 'u');
+
+  if single && ctx.uris.len() > 1 {
+    return ctx.uris
+      .iter()
+      .map(|uri| expand_args(args, &ExecContext {
+        uris: std::slice::from_ref(uri),
+        ..*ctx
+      }))
+      .collect()
+  }
+
+  vec![expand_args(args, ctx)]
+}
+
+fn expand_args(
+  args: &[String],
+  ctx: &ExecContext
+) -> Vec<String> {
+  let files: Vec<String> = ctx.uris
+    .iter()
+    .filter_map(|u| uri_to_path(u))
+    .collect();
+
+  let mut out: Vec<String> = vec![];
+
+  for arg in args {
+    match arg.as_str() {
+      "%F" => out.extend(files.iter().cloned()),
+      "%U" => out.extend(ctx.uris.iter().cloned()),
+      "%i" => if !ctx.icon.is_empty() {
+        out.push("--icon".to_string());
+        out.push(ctx.icon.to_string());
+      },
+      _ => {
+        let expanded = expand_codes(arg, ctx, &files);
+        let code_only = arg.len() == 2 && arg.starts_with('%');
+
+        if !(code_only && expanded.is_empty()) {
+          out.push(expanded);
+        }
+      }
+    }
+  }
+
+  out
+}
+
+/// Field codes in `arg`, a `%%` being a plain percent
+fn field_codes(arg: &str) -> Vec<char> {
+  let mut codes = vec![];
+  let mut chars = arg.chars();
+
+  while let Some(c) = chars.next() {
+    if c != '%' { continue }
+
+    match chars.next() {
+      Some('%') | None => (),
+      Some(code) => codes.push(code)
+    }
+  }
+
+  codes
+}
+
+fn expand_codes(
+  arg: &str,
+  ctx: &ExecContext,
+  files: &[String]
+) -> String {
+  let mut out = String::with_capacity(arg.len());
+  let mut chars = arg.chars();
+
+  while let Some(c) = chars.next() {
+    if c != '%' {
+      out.push(c);
+      continue;
+    }
+
+    match chars.next() {
+      Some('%') => out.push('%'),
+      Some('f') => out.push_str(
+        files.first().map(|s| s.as_str()).unwrap_or("")),
+      Some('u') => out.push_str(
+        ctx.uris.first().map(|s| s.as_str()).unwrap_or("")),
+      Some('c') => out.push_str(ctx.name),
+      Some('k') => out.push_str(ctx.entry_path),
+      // Deprecated codes and the list codes, which are
+      // only valid as standalone arguments, are dropped
+      _ => ()
+    }
+  }
+
+  out
+}
+
+/// Local path for a `file://` URI or an absolute path,
+/// `None` for anything that isn't a local file.
+pub fn uri_to_path(uri: &str) -> Option<String> {
+  if uri.starts_with('/') {
+    return Some(uri.to_string())
+  }
+
+  let path = uri.strip_prefix("file://")?;
+  let path = &path[path.find('/')?..];
+
+  Some(percent_decode(path))
+}
+
+fn percent_decode(input: &str) -> String {
+  let bytes = input.as_bytes();
+  let mut out: Vec<u8> = Vec::with_capacity(bytes.len());
+  let mut i = 0;
+
+  while i < bytes.len() {
+    let hex = input.get(i + 1..i + 3)
+      .and_then(|h| u8::from_str_radix(h, 16).ok());
+
+    match (bytes[i], hex) {
+      (b'%', Some(b)) => { out.push(b); i += 3 },
+      (b, _) => { out.push(b); i += 1 }
+    }
+  }
+
+  String::from_utf8_lossy(&out).to_string()
+}
+
+/// Quotes `arg` so `/bin/sh` reads it back verbatim
+pub fn shell_quote(arg: &str) -> String {
+  format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn split(exec: &str) -> Vec<String> {
+    split_exec(exec).unwrap()
+  }
+
+  fn expand(exec: &str, uris: &[&str], icon: &str) ->
+    Vec<Vec<String>>
+  {
+    let uris: Vec<String> = uris
+      .iter()
+      .map(|u| u.to_string())
+      .collect();
+
+    expand_exec(&split(exec), &ExecContext {
+      uris: &uris,
+      icon,
+      name: "Text Editor",
+      entry_path: "/usr/share/applications/editor.desktop"
+    })
+  }
+
+  #[test]
+  fn splits_quoted_arguments() {
+    assert_eq!(
+      split(r#"app  --title "Two Words" "" last"#),
+      ["app", "--title", "Two Words", "", "last"]);
+
+    assert_eq!(
+      split(r#"sh -c "echo \"\$HOME\" \`x\` \\\\ \\q""#),
+      ["sh", "-c", r#"echo "$HOME" `x` \ \q"#]);
+
+    // String escapes go before quoting, so `\s` still splits
+    assert_eq!(split(r"app a\sb"), ["app", "a", "b"]);
+    assert_eq!(split(r#"app "a\sb""#), ["app", "a b"]);
+    assert_eq!(split_exec(r#"app "open"#), None);
+  }
+
+  #[test]
+  fn unescapes_string_values() {
+    assert_eq!(unescape_string(r"a\sb\tc\\d\x"), "a b\tc\\d\\x");
+    assert_eq!(unescape_string(r"end\"), "end\\");
+  }
+
+  #[test]
+  fn escaped_percent_is_not_a_field_code() {
+    assert_eq!(
+      expand("printf 100%%f %%u", &["/a", "/b"], ""),
+      [["printf", "100%f", "%u"]]);
+
+    assert_eq!(field_codes("%%f%u%"), ['u']);
+  }
+
+  #[test]
+  fn single_codes_launch_once_per_uri() {
+    assert_eq!(
+      expand("view %f", &["file:///a%20b", "/c"], ""),
+      [["view", "/a b"], ["view", "/c"]]);
+
+    assert_eq!(
+      expand("open --url=%u", &["https://x", "https://y"], ""),
+      [["open", "--url=https://x"], ["open", "--url=https://y"]]);
+
+    // Nothing to open leaves the code out
+    assert_eq!(expand("view %f", &[], ""), [["view"]]);
+  }
+
+  #[test]
+  fn list_codes_take_every_uri() {
+    assert_eq!(
+      expand("edit %F", &["file:///a", "https://b", "/c"], ""),
+      [["edit", "/a", "/c"]]);
+
+    assert_eq!(
+      expand("open %U", &["file:///a", "https://b"], ""),
+      [["open", "file:///a", "https://b"]]);
+  }
+
+  #[test]
+  fn icon_only_with_an_icon() {
+    assert_eq!(
+      expand("app %i", &[], "editor"),
+      [["app", "--icon", "editor"]]);
+
+    assert_eq!(expand("app %i", &[], ""), [["app"]]);
+  }
+
+  #[test]
+  fn expands_name_and_entry_path() {
+    assert_eq!(expand("app --class=%c %k", &[], ""), [[
+      "app",
+      "--class=Text Editor",
+      "/usr/share/applications/editor.desktop"
+    ]]);
+
+    // Deprecated codes are dropped
+    assert_eq!(expand("app %d %m x%v", &[], ""), [["app", "x"]]);
+  }
+}
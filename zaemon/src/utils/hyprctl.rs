@@ -0,0 +1,305 @@
+use std::io::{self, ErrorKind as IOErr};
+use std::path::{Path, PathBuf};
+use std::env::var;
+
+use tokio::{
+  net::UnixStream,
+  io::{AsyncReadExt, AsyncWriteExt}
+};
+
+use zbus::zvariant;
+
+
+
+#[derive(
+  serde::Deserialize,
+  Default,
+  Debug,
+  Clone
+)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HyprClient {
+  pub address: String,
+  pub class: String,
+  pub title: String,
+  pub initial_class: String,
+  pub initial_title: String,
+  pub pid: i32,
+  pub xwayland: bool,
+}
+
+
+/// A monitor as listed by `j/monitors all`
+#[derive(
+  serde::Deserialize,
+  serde::Serialize,
+  zvariant::Type,
+  Default,
+  Debug,
+  Clone,
+  PartialEq
+)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HyprMonitor {
+  pub id: i64,
+  pub name: String,
+  pub description: String,
+  pub make: String,
+  pub model: String,
+  pub serial: String,
+  pub width: i32,
+  pub height: i32,
+  pub refresh_rate: f64,
+  pub x: i32,
+  pub y: i32,
+  pub scale: f64,
+  pub transform: i32,
+  pub focused: bool,
+  pub dpms_status: bool,
+  pub vrr: bool,
+  pub disabled: bool,
+  /// Name of the mirrored monitor, `none` when not
+  /// mirroring
+  pub mirror_of: String,
+  /// Modes like `1920x1080@60.00Hz`
+  pub available_modes: Vec<String>,
+}
+
+
+/// A workspace as listed by `j/workspaces`
+#[derive(
+  serde::Deserialize,
+  serde::Serialize,
+  zvariant::Type,
+  Default,
+  Debug,
+  Clone,
+  PartialEq
+)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HyprWorkspace {
+  pub id: i64,
+  pub name: String,
+  /// Name of the monitor it's on
+  pub monitor: String,
+  pub windows: i32,
+  #[serde(rename = "hasfullscreen")]
+  pub has_fullscreen: bool,
+}
+
+
+/// A rule as listed by `j/workspacerules`, options that
+/// aren't set are left out of the reply
+#[derive(
+  serde::Deserialize,
+  Default,
+  Debug,
+  Clone
+)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HyprWorkspaceRule {
+  pub workspace_string: String,
+  pub monitor: String,
+  pub default: bool,
+  pub persistent: bool,
+}
+
+
+/// A config option as listed by `j/getoption`
+#[derive(
+  serde::Deserialize,
+  Default,
+  Debug,
+  Clone
+)]
+#[serde(default)]
+struct HyprOption {
+  int: i64,
+  /// Set for gradients and other non-numeric values
+  custom: String,
+}
+
+
+/// Sends `cmd` to Hyprland's request socket (socket1)
+/// and returns its reply.
+pub async fn request(cmd: &str) -> io::Result<String> {
+  request_at(".socket.sock", cmd).await
+}
+
+/// Sends `cmd` to hyprsunset, which listens next to
+/// Hyprland's sockets
+pub async fn hyprsunset(cmd: &str) -> io::Result<String> {
+  request_at(".hyprsunset.sock", cmd).await
+}
+
+async fn request_at(sock: &str, cmd: &str) ->
+  io::Result<String>
+{
+  request_socket(&socket_path(sock)?, cmd).await
+}
+
+/// Path of `sock` among the running instance's sockets
+pub fn socket_path(sock: &str) -> io::Result<PathBuf> {
+  let rtm_dir = var("XDG_RUNTIME_DIR")
+    .map_err(|e| io::Error::new(IOErr::NotFound, e))?;
+  let his_dir = var("HYPRLAND_INSTANCE_SIGNATURE")
+    .map_err(|e| io::Error::new(IOErr::NotFound, e))?;
+
+  Ok(PathBuf::from(format!("{rtm_dir}/hypr/{his_dir}/{sock}")))
+}
+
+/// Sends `cmd` to the socket at `path` and returns its
+/// reply, for tools speaking Hyprland's IPC protocol
+pub async fn request_socket(path: &Path, cmd: &str) ->
+  io::Result<String>
+{
+  let mut sock = UnixStream::connect(path).await?;
+  sock.write_all(cmd.as_bytes()).await?;
+
+  let mut reply = String::new();
+  sock.read_to_string(&mut reply).await?;
+
+  Ok(reply)
+}
+
+/// Runs a dispatcher, failing with Hyprland's reply when
+/// it isn't `ok`
+pub async fn dispatch(
+  dispatcher: &str,
+  args: &str
+) -> io::Result<()> {
+  let reply = request(
+    &format!("dispatch {dispatcher} {args}")).await?;
+
+  match reply.trim() {
+    "ok" => Ok(()),
+    err => Err(io::Error::other(err))
+  }
+}
+
+/// Whether `workspace` selects one the way exec rules take
+/// it: an id, `name:<name>` or `special[:<name>]`, with
+/// nothing that would end the rule early
+pub fn is_workspace_selector(workspace: &str) -> bool {
+  let name = match workspace.split_once(':') {
+    Some(("name" | "special", name)) => name,
+    Some(_) => return false,
+    None if workspace == "special" => return true,
+    None => return workspace
+      .parse::<i32>()
+      .is_ok_and(|id| id > 0)
+  };
+
+  !name.trim().is_empty() &&
+    !name.contains([']', ';', '\n', '\r'])
+}
+
+/// Sets a config keyword at runtime, failing with
+/// Hyprland's reply when it isn't `ok`
+pub async fn keyword(key: &str, value: &str) ->
+  io::Result<()>
+{
+  let reply = request(
+    &format!("keyword {key} {value}")).await?;
+
+  match reply.trim() {
+    "ok" => Ok(()),
+    err => Err(io::Error::other(err))
+  }
+}
+
+/// Sets the cursor theme and size for Hyprland itself
+/// and hyprcursor apps
+pub async fn set_cursor(theme: &str, size: i32) ->
+  io::Result<()>
+{
+  let reply = request(
+    &format!("setcursor {theme} {size}")).await?;
+
+  match reply.trim() {
+    "ok" => Ok(()),
+    err => Err(io::Error::other(err))
+  }
+}
+
+/// Every monitor, disabled ones included
+pub async fn monitors() -> io::Result<Vec<HyprMonitor>> {
+  let reply = request("j/monitors all").await?;
+  Ok(serde_json::from_str(&reply)?)
+}
+
+pub async fn workspaces() -> io::Result<Vec<HyprWorkspace>> {
+  let reply = request("j/workspaces").await?;
+  Ok(serde_json::from_str(&reply)?)
+}
+
+/// Workspace of the focused monitor
+pub async fn active_workspace() -> io::Result<HyprWorkspace> {
+  let reply = request("j/activeworkspace").await?;
+  Ok(serde_json::from_str(&reply)?)
+}
+
+pub async fn workspace_rules() ->
+  io::Result<Vec<HyprWorkspaceRule>>
+{
+  let reply = request("j/workspacerules").await?;
+  Ok(serde_json::from_str(&reply)?)
+}
+
+/// Value of an integer or boolean config option
+pub async fn option_int(name: &str) -> io::Result<i64> {
+  let reply = request(&format!("j/getoption {name}")).await?;
+  let option: HyprOption = serde_json::from_str(&reply)?;
+
+  Ok(option.int)
+}
+
+/// Value of an option Hyprland keeps as text, like
+/// `general:col.active_border`
+pub async fn option_custom(name: &str) -> io::Result<String> {
+  let reply = request(&format!("j/getoption {name}")).await?;
+  let option: HyprOption = serde_json::from_str(&reply)?;
+
+  Ok(option.custom)
+}
+
+pub async fn clients() -> io::Result<Vec<HyprClient>> {
+  let reply = request("j/clients").await?;
+  Ok(serde_json::from_str(&reply)?)
+}
+
+pub async fn client(address: &str) ->
+  io::Result<Option<HyprClient>>
+{
+  Ok(clients().await?
+    .into_iter()
+    .find(|c| same_address(&c.address, address)))
+}
+
+/// Socket2 events carry window addresses without the
+/// `0x` prefix that `j/clients` uses
+pub fn same_address(a: &str, b: &str) -> bool {
+  a.trim_start_matches("0x") == b.trim_start_matches("0x")
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn workspace_selectors() {
+    for ws in ["1", "42", "name:web", "name:my stuff",
+      "special", "special:term"]
+    {
+      assert!(is_workspace_selector(ws), "{ws}");
+    }
+
+    for ws in ["", "0", "-1", "web", "name:", "special:",
+      "1] kitty; [", "name:a]b", "name:a;b", "name:a\nb",
+      "m+1", "id:2"]
+    {
+      assert!(!is_workspace_selector(ws), "{ws}");
+    }
+  }
+}
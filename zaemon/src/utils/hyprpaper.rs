@@ -0,0 +1,115 @@
+/* -
+ * -> Client for hyprpaper's IPC socket, which sits next
+ *    to Hyprland's and speaks the same protocol
+ * -
+*/
+
+use std::io;
+use std::path::PathBuf;
+
+use zbus::zvariant;
+
+use super::hyprctl;
+
+
+/// How an image fills a monitor
+#[derive(
+  serde::Deserialize,
+  serde::Serialize,
+  zvariant::Type,
+  Default,
+  Debug,
+  Clone,
+  Copy,
+  PartialEq,
+  Eq
+)]
+#[serde(rename_all = "lowercase")]
+#[zvariant(signature = "s")]
+pub enum WallpaperMode {
+  /// Scaled to cover the monitor, cropping the overflow
+  #[default]
+  Cover,
+  /// Scaled to fit inside the monitor, with borders
+  Contain,
+  /// Repeated at its own size
+  Tile,
+}
+
+impl WallpaperMode {
+  /// What hyprpaper wants in front of the path
+  fn prefix(&self) -> &'static str {
+    match self {
+      Self::Cover => "",
+      Self::Contain => "contain:",
+      Self::Tile => "tile:"
+    }
+  }
+}
+
+
+#[derive(Debug, Clone)]
+pub struct Hyprpaper {
+  pub socket: PathBuf,
+}
+
+impl Default for Hyprpaper {
+  /// hyprpaper of the running Hyprland instance
+  fn default() -> Self {
+    Self::at(hyprctl::socket_path(".hyprpaper.sock")
+      .unwrap_or_default())
+  }
+}
+
+impl Hyprpaper {
+  pub fn at(socket: impl Into<PathBuf>) -> Self {
+    Self { socket: socket.into() }
+  }
+
+  /// Loads `path` into memory, needed before showing it
+  pub async fn preload(&self, path: &str) -> io::Result<()> {
+    self.command(&format!("preload {path}")).await
+  }
+
+  /// Shows the preloaded `path` on `monitor`, or on every
+  /// monitor without its own when `monitor` is empty
+  pub async fn wallpaper(
+    &self,
+    monitor: &str,
+    path: &str,
+    mode: WallpaperMode
+  ) -> io::Result<()> {
+    self.command(&format!(
+      "wallpaper {monitor},{}{path}", mode.prefix())).await
+  }
+
+  pub async fn unload(&self, path: &str) -> io::Result<()> {
+    self.command(&format!("unload {path}")).await
+  }
+
+  /// Paths of the preloaded images
+  pub async fn loaded(&self) -> io::Result<Vec<String>> {
+    let reply = hyprctl::request_socket(
+      &self.socket, "listloaded").await?;
+
+    // Anything else is "no wallpapers loaded"
+    Ok(reply
+      .lines()
+      .map(str::trim)
+      .filter(|l| l.starts_with('/'))
+      .map(str::to_string)
+      .collect())
+  }
+
+  /// Runs `cmd`, failing with hyprpaper's reply when it
+  /// isn't `ok`
+  async fn command(&self, cmd: &str) -> io::Result<()> {
+    let reply = hyprctl::request_socket(&self.socket, cmd)
+      .await?;
+
+    match reply.trim() {
+      "ok" => Ok(()),
+      err => Err(io::Error::other(err))
+    }
+  }
+}
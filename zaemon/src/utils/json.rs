@@ -0,0 +1,58 @@
+use std::io;
+use std::path::Path;
+
+use serde::{Serialize, de::DeserializeOwned};
+
+use super::cache::write_atomic;
+
+
+
+/// Reads the JSON at `path`, falling back to the default
+/// when it's missing or can't be parsed
+pub fn load_json<T>(path: &Path) -> T
+where
+  T: DeserializeOwned + Default
+{
+  std::fs::read_to_string(path)
+    .ok()
+    .and_then(|s| serde_json::from_str(&s).ok())
+    .unwrap_or_default()
+}
+
+/// Writes `value` to `path` as pretty-printed JSON,
+/// creating its dir first
+pub fn save_json<T>(path: &Path, value: &T) -> io::Result<()>
+where
+  T: Serialize + ?Sized
+{
+  if let Some(dir) = path.parent() {
+    std::fs::create_dir_all(dir)?;
+  }
+
+  let json = serde_json::to_string_pretty(value)?;
+  write_atomic(path, json)
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn round_trips_and_falls_back() {
+    let dir = std::env::temp_dir().join(format!(
+      "zaemon-json-{}", std::process::id()));
+    let path = dir.join("nested/values.json");
+    _ = std::fs::remove_dir_all(&dir);
+
+    assert_eq!(load_json::<Vec<u32>>(&path), Vec::<u32>::new());
+
+    save_json(&path, &[1u32, 2, 3][..]).unwrap();
+    assert_eq!(load_json::<Vec<u32>>(&path), vec![1, 2, 3]);
+
+    std::fs::write(&path, "{ broken").unwrap();
+    assert_eq!(load_json::<Option<Vec<u32>>>(&path), None);
+
+    _ = std::fs::remove_dir_all(&dir);
+  }
+}
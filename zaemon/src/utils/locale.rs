@@ -0,0 +1,82 @@
+use std::env::var;
+
+
+
+/// The locale messages should be shown in, from the
+/// usual `LC_ALL` > `LC_MESSAGES` > `LANG` precedence
+pub fn current_locale() -> String {
+  ["LC_ALL", "LC_MESSAGES", "LANG"]
+    .into_iter()
+    .filter_map(|v| var(v).ok())
+    .find(|l| !l.trim().is_empty())
+    .unwrap_or("C".to_string())
+}
+
+/// Locale keys to try for `locale`, most specific first,
+/// following the Desktop Entry spec's `LC_MESSAGES`
+/// matching: `lang_COUNTRY@MODIFIER`, `lang_COUNTRY`,
+/// `lang@MODIFIER` and then `lang`. The encoding is
+/// never part of the match.
+pub fn locale_variants(locale: &str) -> Vec<String> {
+  let locale = locale.trim();
+  if locale.is_empty() || locale == "C" || locale == "POSIX" {
+    return vec![]
+  }
+
+  let (rest, modifier) = match locale.split_once('@') {
+    Some((r, m)) => (r, Some(m)),
+    None => (locale, None)
+  };
+
+  let rest = match rest.split_once('.') {
+    Some((r, _encoding)) => r,
+    None => rest
+  };
+
+  let (lang, country) = match rest.split_once('_') {
+    Some((l, c)) => (l, Some(c)),
+    None => (rest, None)
+  };
+
+  let mut variants = vec![];
+
+  if let (Some(c), Some(m)) = (country, modifier) {
+    variants.push(format!("{lang}_{c}@{m}"));
+  }
+
+  if let Some(c) = country {
+    variants.push(format!("{lang}_{c}"));
+  }
+
+  if let Some(m) = modifier {
+    variants.push(format!("{lang}@{m}"));
+  }
+
+  variants.push(lang.to_string());
+  variants
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn variants_most_specific_first() {
+    assert_eq!(locale_variants("sr_RS.UTF-8@latin"), [
+      "sr_RS@latin", "sr_RS", "sr@latin", "sr"
+    ]);
+    assert_eq!(locale_variants("pt_BR.UTF-8"), ["pt_BR", "pt"]);
+    assert_eq!(locale_variants("ca@valencia"), [
+      "ca@valencia", "ca"
+    ]);
+    assert_eq!(locale_variants("de"), ["de"]);
+  }
+
+  #[test]
+  fn no_variants_for_the_c_locale() {
+    for locale in ["", " ", "C", "POSIX"] {
+      assert!(locale_variants(locale).is_empty(), "{locale}");
+    }
+  }
+}
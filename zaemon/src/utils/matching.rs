@@ -0,0 +1,328 @@
+use std::path::Path;
+
+use crate::interfaces::apps::DesktopEntry;
+use super::exec::split_exec;
+use super::hyprctl::HyprClient;
+
+
+
+/// Names a window may go by, most reliable first
+#[derive(Default, Debug, Clone)]
+pub struct WindowKeys {
+  keys: Vec<String>,
+}
+
+impl WindowKeys {
+  pub fn from_class(class: &str) -> Self {
+    let mut keys = Self::default();
+    keys.push(class);
+    keys
+  }
+
+  /// Keys of a Hyprland client: its sandbox app ID when
+  /// it runs under Flatpak or Snap, its classes and its
+  /// executable
+  pub fn from_client(client: &HyprClient) -> Self {
+    let mut keys = Self::default();
+
+    if client.pid > 0 {
+      if let Some(id) = flatpak_app_id(client.pid) {
+        keys.push(&id);
+      }
+
+      if let Some(name) = snap_name(client.pid) {
+        keys.push(&name);
+      }
+    }
+
+    keys.push(&client.class);
+    keys.push(&client.initial_class);
+
+    if client.pid > 0 {
+      let exe = std::fs::read_link(
+        format!("/proc/{}/exe", client.pid));
+
+      if let Ok(exe) = exe {
+        keys.push(&exe
+          .file_name()
+          .unwrap_or_default()
+          .to_string_lossy());
+      }
+    }
+
+    keys
+  }
+
+  fn push(&mut self, key: &str) {
+    let key = key.trim().to_lowercase();
+
+    if !key.is_empty() && !self.keys.contains(&key) {
+      self.keys.push(key);
+    }
+  }
+}
+
+
+/// Finds the entry of a window. Each key, in order, is
+/// tried against StartupWMClass, then the desktop-file ID
+/// (whole or its last reverse-DNS part), then the names
+/// the entry runs under.
+pub fn match_entry<'a>(
+  entries: &'a [DesktopEntry],
+  keys: &WindowKeys
+) -> Option<&'a DesktopEntry> {
+  let tiers: [fn(&DesktopEntry, &str) -> bool; 4] = [
+    |e, k| e.startup_wm_class == k,
+    |e, k| id_stem(e) == k,
+    |e, k| last_part(&id_stem(e)) == last_part(k),
+    |e, k| e.exec_names.iter().any(|n| n == k),
+  ];
+
+  keys.keys
+    .iter()
+    .find_map(|key| tiers
+      .iter()
+      .find_map(|tier| entries
+        .iter()
+        .find(|e| tier(e, key))))
+}
+
+/// Names an entry's Exec line runs under: the program
+/// itself, or the app ID / snap name it hands to
+/// `flatpak run` and `snap run`. Lowercased.
+pub fn exec_names(exec: &str) -> Vec<String> {
+  let args = split_exec(exec).unwrap_or_default();
+
+  // Skip `env VAR=value ...` prefixes
+  let mut args = args
+    .iter()
+    .skip_while(|a| *a == "env" || a.contains('='))
+    .map(String::as_str);
+
+  let program = match args.next() {
+    Some(p) => p,
+    None => return vec![]
+  };
+
+  let base = file_name(program);
+  let mut names = vec![base.to_lowercase()];
+
+  let rest: Vec<&str> = args.collect();
+  let run_target = || rest
+    .iter()
+    .skip_while(|a| **a != "run")
+    .skip(1)
+    .find(|a| !a.starts_with('-'))
+    .map(|a| a.to_string());
+
+  // The launchers themselves say nothing about the app
+  match base.as_str() {
+    "flatpak" => {
+      names.clear();
+      names.extend(run_target());
+      names.extend(rest
+        .iter()
+        .filter_map(|a| a.strip_prefix("--command="))
+        .map(file_name));
+    },
+    "snap" => {
+      names.clear();
+      names.extend(run_target().map(|t| snap_app(&t)));
+    },
+    _ if program.starts_with("/snap/bin/") => {
+      names.push(snap_app(&base));
+    },
+    _ => ()
+  }
+
+  names
+    .into_iter()
+    .map(|n| n.to_lowercase())
+    .filter(|n| !n.is_empty())
+    .collect()
+}
+
+
+fn id_stem(entry: &DesktopEntry) -> String {
+  entry.desktop_id
+    .trim_end_matches(".desktop")
+    .to_lowercase()
+}
+
+/// `org.kde.krita` -> `krita`
+fn last_part(id: &str) -> &str {
+  id.rsplit('.').next().unwrap_or(id)
+}
+
+fn file_name(path: &str) -> String {
+  Path::new(path)
+    .file_name()
+    .map(|f| f.to_string_lossy().to_string())
+    .unwrap_or_default()
+}
+
+/// `firefox.geckodriver` -> `firefox`
+fn snap_app(name: &str) -> String {
+  name.split('.').next().unwrap_or(name).to_string()
+}
+
+/// App ID of a sandboxed Flatpak process, as recorded in
+/// its `.flatpak-info`
+fn flatpak_app_id(pid: i32) -> Option<String> {
+  let info = std::fs::read_to_string(
+    format!("/proc/{pid}/root/.flatpak-info")).ok()?;
+
+  info
+    .lines()
+    .skip_while(|l| l.trim() != "[Application]")
+    .find_map(|l| l.trim().strip_prefix("name="))
+    .map(str::to_string)
+}
+
+/// Snap name of a confined process, from its
+/// `snap.<name>.<app>` cgroup
+fn snap_name(pid: i32) -> Option<String> {
+  let cgroup = std::fs::read_to_string(
+    format!("/proc/{pid}/cgroup")).ok()?;
+
+  cgroup
+    .split(['/', '\n'])
+    .find_map(|part| part.strip_prefix("snap."))
+    .map(snap_app)
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn entry(id: &str) -> DesktopEntry {
+    DesktopEntry {
+      desktop_id: id.into(),
+      ..Default::default()
+    }
+  }
+
+  fn matched(entries: &[DesktopEntry], keys: &WindowKeys) ->
+    Option<String>
+  {
+    match_entry(entries, keys).map(|e| e.desktop_id.clone())
+  }
+
+  fn class(class: &str) -> WindowKeys {
+    WindowKeys::from_class(class)
+  }
+
+  #[test]
+  fn client_keys_in_order() {
+    let client = HyprClient {
+      class: "Code".into(),
+      initial_class: " code ".into(),
+      ..Default::default()
+    };
+    let keys = WindowKeys::from_client(&client).keys;
+    assert_eq!(keys, ["code"]);
+
+    // The executable comes last
+    let client = HyprClient {
+      class: "Tester".into(),
+      pid: std::process::id() as i32,
+      ..Default::default()
+    };
+    let keys = WindowKeys::from_client(&client).keys;
+    assert_eq!(keys[0], "tester");
+    assert_eq!(keys.len(), 2);
+  }
+
+  #[test]
+  fn startup_wm_class() {
+    let entries = [DesktopEntry {
+      startup_wm_class: "code-oss".into(),
+      ..entry("code.desktop")
+    }];
+
+    assert_eq!(matched(&entries, &class("Code-OSS")),
+      Some("code.desktop".into()));
+  }
+
+  #[test]
+  fn desktop_id() {
+    let entries = [entry("org.gnome.Nautilus.desktop")];
+
+    for key in ["org.gnome.Nautilus", "nautilus"] {
+      assert_eq!(matched(&entries, &class(key)),
+        Some("org.gnome.Nautilus.desktop".into()), "{key}");
+    }
+
+    assert_eq!(matched(&entries, &class("files")), None);
+  }
+
+  #[test]
+  fn exec_name() {
+    let entries = [DesktopEntry {
+      exec_names: exec_names(
+        "env GDK_BACKEND=x11 /usr/bin/foot -s"),
+      ..entry("terminal.desktop")
+    }];
+
+    assert_eq!(matched(&entries, &class("foot")),
+      Some("terminal.desktop".into()));
+  }
+
+  #[test]
+  fn sandboxed_apps() {
+    assert_eq!(
+      exec_names("flatpak run --branch=stable \
+        --command=krita org.kde.krita %F"),
+      ["org.kde.krita", "krita"]);
+    assert_eq!(exec_names("snap run firefox.geckodriver"),
+      ["firefox"]);
+    assert_eq!(exec_names("/snap/bin/spotify"),
+      ["spotify", "spotify"]);
+
+    let entries = [DesktopEntry {
+      exec_names: exec_names("flatpak run com.spotify.Client"),
+      ..entry("spotify-launcher.desktop")
+    }];
+
+    // Flatpak's app ID comes first among a client's keys
+    let mut keys = class("Spotify");
+    keys.keys.insert(0, "com.spotify.client".into());
+
+    assert_eq!(matched(&entries, &keys),
+      Some("spotify-launcher.desktop".into()));
+  }
+
+  #[test]
+  fn earlier_tiers_win() {
+    let entries = [
+      entry("kitty.desktop"),
+      DesktopEntry {
+        startup_wm_class: "kitty".into(),
+        ..entry("kitty-quick.desktop")
+      },
+      DesktopEntry {
+        exec_names: vec!["kitty".into()],
+        ..entry("other.desktop")
+      },
+    ];
+
+    assert_eq!(matched(&entries, &class("kitty")),
+      Some("kitty-quick.desktop".into()));
+
+    // But keys come first: a more reliable key matching a
+    // later tier beats a less reliable one
+    let mut keys = class("kitty");
+    keys.keys.insert(0, "kitty-launcher".into());
+    let entries = [
+      entries[1].clone(),
+      DesktopEntry {
+        exec_names: vec!["kitty-launcher".into()],
+        ..entry("launcher.desktop")
+      },
+    ];
+
+    assert_eq!(matched(&entries, &keys),
+      Some("launcher.desktop".into()));
+  }
+}
@@ -1 +1,16 @@
 pub mod notify;
+pub mod hyprctl;
+pub mod exec;
+pub mod locale;
+pub mod cache;
+pub mod json;
+pub mod search;
+pub mod usage;
+pub mod matching;
+pub mod profiles;
+pub mod sun;
+pub mod brightness;
+pub mod scaling;
+pub mod hyprpaper;
+pub mod slideshow;
+pub mod palette;
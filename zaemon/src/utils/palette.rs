@@ -0,0 +1,190 @@
+/* -
+ * -> Dominant colors of an image by median cut, and the
+ *    accent themes build on, picked like Material You does
+ * -
+*/
+
+use std::io;
+use std::path::Path;
+
+
+/// Colors in a palette, before close ones get merged
+pub const PALETTE_SIZE: usize = 8;
+
+/// Edge images are scaled down to before sampling
+const SAMPLE_EDGE: u32 = 128;
+
+/// Colors no further apart on any channel count as one
+const MERGE_DISTANCE: u8 = 12;
+
+/// Share of the pixels a color needs, below it's mostly
+/// blending between others
+const MIN_POPULATION: f64 = 0.01;
+
+/// Lightness an accent stays within, readable on both
+/// dark and light backgrounds
+const ACCENT_LIGHTNESS: (f64, f64) = (0.2, 0.85);
+
+
+pub type Rgb = [u8; 3];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Swatch {
+  pub color: Rgb,
+  /// Share of the image's pixels, from 0 to 1
+  pub population: f64,
+}
+
+
+/// Palette of the image at `path`, most common color first
+pub fn extract(path: &Path, size: usize) ->
+  io::Result<Vec<Swatch>>
+{
+  let image = image::open(path).map_err(io::Error::other)?;
+
+  let image = match image.width().max(image.height()) {
+    edge if edge <= SAMPLE_EDGE => image.to_rgba8(),
+    _ => image.thumbnail(SAMPLE_EDGE, SAMPLE_EDGE).to_rgba8()
+  };
+
+  // Transparent parts never show behind windows
+  let pixels: Vec<Rgb> = image
+    .pixels()
+    .filter(|p| p[3] >= 128)
+    .map(|p| [p[0], p[1], p[2]])
+    .collect();
+
+  Ok(median_cut(pixels, size))
+}
+
+/// Splits `pixels` in up to `size` boxes of similar colors,
+/// cutting the widest, most crowded box at its median
+/// value
+pub fn median_cut(pixels: Vec<Rgb>, size: usize) -> Vec<Swatch> {
+  let total = pixels.len() as f64;
+  if pixels.is_empty() || size == 0 { return vec![] }
+
+  let mut boxes = vec![pixels];
+
+  while boxes.len() < size {
+    let widest = boxes
+      .iter()
+      .enumerate()
+      .map(|(i, b)| (i, widest_channel(b), b.len()))
+      .max_by_key(|(_, (_, range), len)| *range as usize * len);
+
+    let (i, channel) = match widest {
+      Some((i, (channel, range), _)) if range > 0 => (i, channel),
+      // Every box holds a single color
+      _ => break
+    };
+
+    let mut lower = boxes.swap_remove(i);
+    lower.sort_unstable_by_key(|p| p[channel]);
+
+    // Cut where the median value starts, so no color ends
+    // up on both sides
+    let median = lower[lower.len() / 2][channel];
+    let cut = match lower.partition_point(|p| p[channel] < median) {
+      0 => lower.partition_point(|p| p[channel] <= median),
+      cut => cut
+    };
+
+    let upper = lower.split_off(cut);
+    boxes.push(lower);
+    boxes.push(upper);
+  }
+
+  boxes.sort_by_key(|b| std::cmp::Reverse(b.len()));
+
+  let mut swatches: Vec<Swatch> = vec![];
+  for pixels in boxes {
+    let swatch = Swatch {
+      color: average(&pixels),
+      population: pixels.len() as f64 / total
+    };
+
+    // Merged into the more common one
+    match swatches
+      .iter_mut()
+      .find(|s| is_close(s.color, swatch.color))
+    {
+      Some(s) => s.population += swatch.population,
+      None => swatches.push(swatch)
+    }
+  }
+
+  swatches.retain(|s| s.population >= MIN_POPULATION);
+  swatches.sort_by(|a, b| b.population.total_cmp(&a.population));
+  swatches
+}
+
+/// Colors of `palette` by how well they'd do as an accent,
+/// the colorful and common ones first
+pub fn accents(palette: &[Swatch]) -> Vec<Rgb> {
+  let (min, max) = ACCENT_LIGHTNESS;
+
+  let mut scored: Vec<(f64, Rgb)> = palette
+    .iter()
+    .filter(|s| (min..=max).contains(&lightness(s.color)))
+    .map(|s| (chroma(s.color) * s.population.sqrt(), s.color))
+    .collect();
+
+  scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+  match scored.is_empty() {
+    // All too dark or light, the most common it is
+    true => palette.iter().map(|s| s.color).take(1).collect(),
+    false => scored.into_iter().map(|(_, c)| c).collect()
+  }
+}
+
+pub fn hex(color: Rgb) -> String {
+  let [r, g, b] = color;
+  format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+/// Channel `pixels` spread the most on, and how far
+fn widest_channel(pixels: &[Rgb]) -> (usize, u8) {
+  (0..3)
+    .map(|c| {
+      let values = pixels.iter().map(|p| p[c]);
+      let min = values.clone().min().unwrap_or(0);
+      let max = values.max().unwrap_or(0);
+
+      (c, max - min)
+    })
+    .max_by_key(|(_, range)| *range)
+    .unwrap_or((0, 0))
+}
+
+fn average(pixels: &[Rgb]) -> Rgb {
+  let len = pixels.len().max(1) as u64;
+  let mut sums = [0u64; 3];
+
+  for pixel in pixels {
+    for (sum, value) in sums.iter_mut().zip(pixel) {
+      *sum += *value as u64;
+    }
+  }
+
+  sums.map(|s| (s / len) as u8)
+}
+
+fn is_close(a: Rgb, b: Rgb) -> bool {
+  (0..3).all(|c| a[c].abs_diff(b[c]) <= MERGE_DISTANCE)
+}
+
+fn chroma(color: Rgb) -> f64 {
+  let max = color.iter().max().copied().unwrap_or(0);
+  let min = color.iter().min().copied().unwrap_or(0);
+
+  (max - min) as f64 / 255.0
+}
+
+fn lightness(color: Rgb) -> f64 {
+  let max = color.iter().max().copied().unwrap_or(0);
+  let min = color.iter().min().copied().unwrap_or(0);
+
+  (max as f64 + min as f64) / 2.0 / 255.0
+}
@@ -0,0 +1,278 @@
+use std::path::PathBuf;
+use std::env::var;
+use std::io;
+
+use crate::interfaces::screen::{HyprMonitor, MonitorConfig};
+use super::json::{load_json, save_json};
+
+
+
+/// Settings of one monitor in a profile, identified by
+/// its make, model and serial instead of its connector
+#[derive(
+  serde::Deserialize,
+  serde::Serialize,
+  Default,
+  Debug,
+  Clone
+)]
+pub struct ProfileOutput {
+  pub monitor: String,
+  /// `name` is ignored and `mirror` holds the mirrored
+  /// monitor's identity, connectors change across plugs
+  pub config: MonitorConfig,
+}
+
+/// A named monitor layout, picked when exactly its
+/// monitors are connected
+#[derive(
+  serde::Deserialize,
+  serde::Serialize,
+  Default,
+  Debug,
+  Clone
+)]
+pub struct LayoutProfile {
+  pub name: String,
+  pub outputs: Vec<ProfileOutput>,
+}
+
+impl LayoutProfile {
+  /// Profile of the layout `monitors` are in right now,
+  /// with `configs` being theirs in the same order
+  pub fn capture(
+    name: &str,
+    monitors: &[HyprMonitor],
+    configs: Vec<MonitorConfig>
+  ) -> Self {
+    let outputs = monitors
+      .iter()
+      .zip(configs)
+      .map(|(m, mut config)| {
+
+        config.mirror = monitors
+          .iter()
+          .find(|o| o.name == config.mirror)
+          .map(monitor_id)
+          .unwrap_or_default();
+
+        ProfileOutput { monitor: monitor_id(m), config }
+      })
+      .collect();
+
+    Self { name: name.to_string(), outputs }
+  }
+
+  /// Whether `monitors` are exactly the profile's, as
+  /// many of each when identical ones lack a serial
+  pub fn matches(&self, monitors: &[HyprMonitor]) -> bool {
+    let mut wanted: Vec<&str> = self.outputs
+      .iter()
+      .map(|o| o.monitor.as_str())
+      .collect();
+    wanted.sort_unstable();
+
+    let mut connected: Vec<String> = monitors
+      .iter()
+      .map(monitor_id)
+      .collect();
+    connected.sort_unstable();
+
+    wanted == connected
+  }
+
+  /// The profile's configs, addressed to the connectors
+  /// its monitors are plugged into now
+  pub fn configs(&self, monitors: &[HyprMonitor]) ->
+    Vec<MonitorConfig>
+  {
+    let connectors = self.connectors(monitors);
+
+    // Mirrors can only go by identity, the first of
+    // identical monitors it is
+    let mirrored = |id: &str| self.outputs
+      .iter()
+      .zip(&connectors)
+      .find(|(o, _)| o.monitor == id)
+      .and_then(|(_, c)| c.clone());
+
+    self.outputs
+      .iter()
+      .zip(&connectors)
+      .filter_map(|(o, connector)| {
+        let mut config = o.config.clone();
+        config.name = connector.clone()?;
+
+        if !config.mirror.is_empty() {
+          config.mirror = mirrored(&config.mirror)
+            .unwrap_or_default();
+        }
+
+        Some(config)
+      })
+      .collect()
+  }
+
+  /// Connector of each output, none used twice. Identical
+  /// monitors keep the connector they were saved on, or
+  /// else go by the closest position.
+  fn connectors(&self, monitors: &[HyprMonitor]) ->
+    Vec<Option<String>>
+  {
+    let ids: Vec<String> = monitors
+      .iter()
+      .map(monitor_id)
+      .collect();
+
+    let mut taken = vec![false; monitors.len()];
+    let mut connectors: Vec<Option<usize>> = self.outputs
+      .iter()
+      .map(|o| {
+        let i = monitors
+          .iter()
+          .zip(&ids)
+          .position(|(m, id)| *id == o.monitor &&
+            m.name == o.config.name)?;
+
+        taken[i] = true;
+        Some(i)
+      })
+      .collect();
+
+    for (o, connector) in self.outputs
+      .iter()
+      .zip(&mut connectors)
+      .filter(|(_, c)| c.is_none())
+    {
+      let closest = monitors
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !taken[*i] && ids[*i] == o.monitor)
+        .min_by_key(|(_, m)| (m.x - o.config.x).abs() +
+          (m.y - o.config.y).abs())
+        .map(|(i, _)| i);
+
+      if let Some(i) = closest {
+        taken[i] = true;
+        *connector = Some(i);
+      }
+    }
+
+    connectors
+      .into_iter()
+      .map(|c| c.map(|i| monitors[i].name.clone()))
+      .collect()
+  }
+}
+
+
+/// Identity of a monitor that survives replugging
+pub fn monitor_id(monitor: &HyprMonitor) -> String {
+  let id = [&monitor.make, &monitor.model, &monitor.serial]
+    .iter()
+    .map(|s| s.trim())
+    .filter(|s| !s.is_empty())
+    .collect::<Vec<&str>>()
+    .join(" ");
+
+  match id.is_empty() {
+    true => monitor.description.clone(),
+    false => id
+  }
+}
+
+/// `$XDG_CONFIG_HOME/hyprmaster/monitor-profiles.json`
+pub fn profiles_path() -> PathBuf {
+  let home_dir = var("HOME").unwrap();
+  let xdg_conf_dir = var("XDG_CONFIG_HOME")
+    .unwrap_or(format!("{home_dir}/.config"));
+
+  PathBuf::from(format!(
+    "{xdg_conf_dir}/hyprmaster/monitor-profiles.json"))
+}
+
+pub fn load_profiles() -> Vec<LayoutProfile> {
+  load_json(&profiles_path())
+}
+
+pub fn save_profiles(profiles: &[LayoutProfile]) ->
+  io::Result<()>
+{
+  save_json(&profiles_path(), profiles)
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn monitor(name: &str, x: i32) -> HyprMonitor {
+    HyprMonitor {
+      name: name.into(),
+      make: "Dell".into(),
+      model: "U2720Q".into(),
+      x,
+      ..Default::default()
+    }
+  }
+
+  fn capture(monitors: &[HyprMonitor]) -> LayoutProfile {
+    let configs = monitors
+      .iter()
+      .map(MonitorConfig::from)
+      .collect();
+
+    LayoutProfile::capture("desk", monitors, configs)
+  }
+
+  #[test]
+  fn identical_monitors_get_a_connector_each() {
+    let profile = capture(&[
+      monitor("DP-1", 0),
+      monitor("DP-2", 3840)
+    ]);
+
+    // Swapped around and plugged into other ports
+    let monitors = [monitor("DP-5", 3840), monitor("DP-4", 0)];
+    assert!(profile.matches(&monitors));
+
+    let configs = profile.configs(&monitors);
+    let placed: Vec<(&str, i32)> = configs
+      .iter()
+      .map(|c| (c.name.as_str(), c.x))
+      .collect();
+
+    assert_eq!(placed, [("DP-4", 0), ("DP-5", 3840)]);
+  }
+
+  #[test]
+  fn saved_connector_wins_over_position() {
+    let profile = capture(&[
+      monitor("DP-1", 0),
+      monitor("DP-2", 3840)
+    ]);
+
+    let monitors = [monitor("DP-2", 0), monitor("DP-1", 3840)];
+    let names: Vec<String> = profile.configs(&monitors)
+      .into_iter()
+      .map(|c| c.name)
+      .collect();
+
+    assert_eq!(names, ["DP-1", "DP-2"]);
+  }
+
+  #[test]
+  fn counts_identical_monitors() {
+    let profile = capture(&[
+      monitor("DP-1", 0),
+      monitor("DP-2", 3840)
+    ]);
+
+    assert!(!profile.matches(&[monitor("DP-1", 0)]));
+    assert!(!profile.matches(&[
+      monitor("DP-1", 0),
+      monitor("DP-2", 3840),
+      monitor("DP-3", 7680)
+    ]));
+  }
+}
@@ -0,0 +1,216 @@
+/* -
+ * -> One scale for everything: the monitors, XWayland and
+ *    the toolkits and cursors of X11 apps
+ * -
+*/
+
+use zbus::zvariant;
+
+use crate::interfaces::screen::{HyprMonitor, MonitorConfig};
+
+
+/// Cursor size at scale 1
+pub const BASE_CURSOR_SIZE: i32 = 24;
+
+
+/// Everything set when scaling a monitor consistently
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScalingPlan {
+  pub monitor: MonitorConfig,
+  /// Lets X11 apps render at native resolution instead
+  /// of being upscaled, their toolkits scale instead
+  pub force_zero_scaling: bool,
+  /// Whole scale for X11 toolkits, which can't do
+  /// fractions
+  pub toolkit_scale: i32,
+  /// Cursor size for X11 apps, which Hyprland doesn't
+  /// scale once they render at native resolution
+  pub xcursor_size: i32,
+}
+
+impl ScalingPlan {
+  /// Plan for setting `name` to `scale`. X11 apps get the
+  /// largest scale among enabled monitors, so they're
+  /// never too small to read.
+  pub fn new(
+    monitors: &[HyprMonitor],
+    name: &str,
+    scale: f64
+  ) -> Option<Self> {
+    let mut monitor = monitors
+      .iter()
+      .find(|m| m.name == name)
+      .map(MonitorConfig::from)?;
+    monitor.scale = scale;
+
+    let max_scale = monitors
+      .iter()
+      .filter(|m| !m.disabled)
+      .map(|m| match m.name == name {
+        true => scale,
+        false => m.scale
+      })
+      .fold(1.0, f64::max);
+
+    let toolkit_scale = max_scale.round().max(1.0) as i32;
+
+    Some(Self {
+      monitor,
+      force_zero_scaling: toolkit_scale > 1,
+      toolkit_scale,
+      xcursor_size: BASE_CURSOR_SIZE * toolkit_scale
+    })
+  }
+
+  /// `env` entries for apps started from now on. Qt on
+  /// Wayland follows the monitor's scale by itself, any
+  /// factor would multiply it. Hyprland scales its own
+  /// cursors per monitor, so they stay at the base size.
+  pub fn env(&self) -> Vec<(&'static str, String)> {
+    vec![
+      ("GDK_SCALE", self.toolkit_scale.to_string()),
+      ("QT_SCALE_FACTOR", "1".to_string()),
+      ("XCURSOR_SIZE", self.xcursor_size.to_string()),
+      ("HYPRCURSOR_SIZE", BASE_CURSOR_SIZE.to_string()),
+    ]
+  }
+
+  /// Everything besides the monitor, as the plan sets it
+  pub fn settings(&self) -> ScalingSettings {
+    ScalingSettings {
+      force_zero_scaling: self.force_zero_scaling,
+      env: self.env()
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v))
+        .collect()
+    }
+  }
+}
+
+
+/// XWayland and env settings that go along with a scale,
+/// kept to go back to when a scale isn't confirmed
+#[derive(
+  serde::Deserialize,
+  serde::Serialize,
+  zvariant::Type,
+  Default,
+  Debug,
+  Clone,
+  PartialEq
+)]
+pub struct ScalingSettings {
+  pub force_zero_scaling: bool,
+  /// Values by key, empty for ones not set
+  pub env: Vec<(String, String)>,
+}
+
+impl ScalingSettings {
+  /// Size of Hyprland's own cursor, for `setcursor`
+  pub fn cursor_size(&self) -> Option<i32> {
+    self.env
+      .iter()
+      .find(|(k, _)| k == "HYPRCURSOR_SIZE")
+      .and_then(|(_, v)| v.parse().ok())
+  }
+}
+
+
+/// A setting a `ScalingPlan` changes, for previews
+#[derive(
+  serde::Deserialize,
+  serde::Serialize,
+  zvariant::Type,
+  Default,
+  Debug,
+  Clone,
+  PartialEq
+)]
+pub struct ScalingChange {
+  pub setting: String,
+  /// Empty when not set
+  pub current: String,
+  pub planned: String,
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn monitor(name: &str, scale: f64) -> HyprMonitor {
+    HyprMonitor {
+      name: name.into(),
+      scale,
+      ..Default::default()
+    }
+  }
+
+  fn env(plan: &ScalingPlan, key: &str) -> String {
+    plan.env()
+      .into_iter()
+      .find(|(k, _)| *k == key)
+      .map(|(_, v)| v)
+      .unwrap_or_default()
+  }
+
+  #[test]
+  fn integer_scale() {
+    let monitors = [monitor("eDP-1", 1.0)];
+    let plan = ScalingPlan::new(&monitors, "eDP-1", 2.0)
+      .unwrap();
+
+    assert_eq!(plan.monitor.scale, 2.0);
+    assert!(plan.force_zero_scaling);
+    assert_eq!(plan.toolkit_scale, 2);
+    assert_eq!(plan.xcursor_size, 48);
+
+    assert_eq!(env(&plan, "GDK_SCALE"), "2");
+    assert_eq!(env(&plan, "XCURSOR_SIZE"), "48");
+    assert_eq!(env(&plan, "HYPRCURSOR_SIZE"), "24");
+  }
+
+  #[test]
+  fn fractional_scale() {
+    let monitors = [monitor("eDP-1", 1.0)];
+
+    let plan = ScalingPlan::new(&monitors, "eDP-1", 1.25)
+      .unwrap();
+    assert!(!plan.force_zero_scaling);
+    assert_eq!(plan.toolkit_scale, 1);
+    assert_eq!(plan.xcursor_size, BASE_CURSOR_SIZE);
+
+    let plan = ScalingPlan::new(&monitors, "eDP-1", 1.5)
+      .unwrap();
+    assert!(plan.force_zero_scaling);
+    assert_eq!(plan.toolkit_scale, 2);
+  }
+
+  #[test]
+  fn largest_enabled_scale_wins() {
+    let mut off = monitor("HDMI-A-1", 3.0);
+    off.disabled = true;
+
+    let monitors = [
+      monitor("eDP-1", 1.0),
+      monitor("DP-1", 2.0),
+      off
+    ];
+    let plan = ScalingPlan::new(&monitors, "eDP-1", 1.0)
+      .unwrap();
+
+    assert_eq!(plan.toolkit_scale, 2);
+    assert_eq!(ScalingPlan::new(&monitors, "DP-9", 1.0), None);
+  }
+
+  #[test]
+  fn cursor_size_round_trip() {
+    let monitors = [monitor("eDP-1", 1.0)];
+    let settings = ScalingPlan::new(&monitors, "eDP-1", 3.0)
+      .unwrap()
+      .settings();
+
+    assert_eq!(settings.cursor_size(), Some(BASE_CURSOR_SIZE));
+    assert_eq!(ScalingSettings::default().cursor_size(), None);
+  }
+}
@@ -0,0 +1,106 @@
+use unicode_normalization::{
+  char::is_combining_mark,
+  UnicodeNormalization
+};
+
+
+
+/// Strips accents and other combining marks off `input`
+pub fn unaccent<T: AsRef<str>>(input: T) -> String {
+  input
+    .as_ref()
+    .nfd()
+    .filter(|c| !is_combining_mark(*c))
+    .collect()
+}
+
+/// Lowercased, accent-free words of `input`
+pub fn search_words(input: &str) -> Vec<String> {
+  unaccent(input)
+    .to_lowercase()
+    .split(|c: char| !c.is_alphanumeric())
+    .filter(|w| !w.is_empty())
+    .map(str::to_string)
+    .collect()
+}
+
+/// Scores `fields`, given as (text, weight) pairs, against
+/// the words of a query. Every query word has to hit some
+/// field, otherwise there's no match at all.
+///
+/// A word is worth its best field's weight, tripled for a
+/// whole-word hit and doubled for a prefix hit.
+pub fn score_fields(
+  query: &[String],
+  fields: &[(&str, u32)]
+) -> Option<u32> {
+  if query.is_empty() { return None }
+
+  let fields: Vec<(Vec<String>, u32)> = fields
+    .iter()
+    .map(|(text, weight)| (search_words(text), *weight))
+    .collect();
+
+  let mut score = 0;
+
+  for word in query {
+    let best = fields
+      .iter()
+      .flat_map(|(words, weight)| words
+        .iter()
+        .map(move |w| match w {
+          w if w == word => weight * 3,
+          w if w.starts_with(word.as_str()) => weight * 2,
+          w if w.contains(word.as_str()) => *weight,
+          _ => 0
+        }))
+      .max()
+      .unwrap_or(0);
+
+    if best == 0 { return None }
+    score += best;
+  }
+
+  Some(score)
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn score(query: &str, fields: &[(&str, u32)]) -> Option<u32> {
+    score_fields(&search_words(query), fields)
+  }
+
+  #[test]
+  fn splits_and_normalizes_words() {
+    assert_eq!(search_words("Éditeur de Texte-2"), [
+      "editeur", "de", "texte", "2"
+    ]);
+  }
+
+  #[test]
+  fn whole_words_beat_prefixes_beat_substrings() {
+    let fields = [("Text Editor", 10)];
+
+    assert_eq!(score("editor", &fields), Some(30));
+    assert_eq!(score("edit", &fields), Some(20));
+    assert_eq!(score("dit", &fields), Some(10));
+  }
+
+  #[test]
+  fn every_word_has_to_match() {
+    let fields = [("Text Editor", 10), ("notes", 4)];
+
+    assert_eq!(score("text notes", &fields), Some(30 + 12));
+    assert_eq!(score("text music", &fields), None);
+    assert_eq!(score("", &fields), None);
+  }
+
+  #[test]
+  fn words_take_their_best_field() {
+    let fields = [("Writer", 10), ("writer office", 4)];
+    assert_eq!(score("writer", &fields), Some(30));
+  }
+}
@@ -0,0 +1,309 @@
+/* -
+ * -> Wallpapers rotating on a monitor, from a folder or
+ *    playlist that can change with the part of the day
+ * -
+*/
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use walkdir::WalkDir;
+use zbus::zvariant;
+
+use super::hyprpaper::WallpaperMode;
+
+
+/// Formats hyprpaper reads
+const IMAGE_EXTENSIONS: [&str; 4] = ["png", "jpg", "jpeg", "webp"];
+
+/// Shortest time an image stays, in seconds
+pub const MIN_INTERVAL: u32 = 10;
+
+/// Minutes since midnight each part of the day starts at
+const MORNING_START: u32 = 6 * 60;
+const DAY_START: u32 = 10 * 60;
+const EVENING_START: u32 = 17 * 60;
+const NIGHT_START: u32 = 21 * 60;
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DayPart {
+  Morning,
+  Day,
+  Evening,
+  Night,
+}
+
+impl DayPart {
+  /// Part of the day `minute` minutes after midnight is in
+  pub fn at(minute: u32) -> Self {
+    match minute {
+      m if m < MORNING_START => Self::Night,
+      m if m < DAY_START => Self::Morning,
+      m if m < EVENING_START => Self::Day,
+      m if m < NIGHT_START => Self::Evening,
+      _ => Self::Night
+    }
+  }
+}
+
+
+/// Where a rotation takes its images from, the playlist
+/// when it has any and the folder otherwise
+#[derive(
+  serde::Deserialize,
+  serde::Serialize,
+  zvariant::Type,
+  Default,
+  Debug,
+  Clone,
+  PartialEq
+)]
+#[serde(default)]
+pub struct ImageSource {
+  pub folder: String,
+  pub playlist: Vec<String>,
+}
+
+impl ImageSource {
+  pub fn is_empty(&self) -> bool {
+    self.folder.is_empty() && self.playlist.is_empty()
+  }
+
+  /// Paths of the images, the folder's sorted by path
+  pub fn images(&self) -> Vec<String> {
+    if !self.playlist.is_empty() {
+      return self.playlist.clone()
+    }
+
+    if self.folder.is_empty() { return vec![] }
+
+    let mut images: Vec<String> = WalkDir::new(&self.folder)
+      .follow_links(true)
+      .into_iter()
+      .flatten()
+      .filter(|e| e.file_type().is_file())
+      .filter(|e| is_image(e.path()))
+      .map(|e| e.path().to_string_lossy().to_string())
+      .collect();
+
+    images.sort();
+    images
+  }
+
+  fn validate(&self) -> Result<(), String> {
+    let relative = std::iter::once(&self.folder)
+      .filter(|f| !f.is_empty())
+      .chain(&self.playlist)
+      .find(|p| !Path::new(p).is_absolute());
+
+    match relative {
+      Some(p) => Err(format!("{p}: not an absolute path")),
+      None => Ok(())
+    }
+  }
+}
+
+
+/// How wallpapers rotate on a monitor
+#[derive(
+  serde::Deserialize,
+  serde::Serialize,
+  zvariant::Type,
+  Default,
+  Debug,
+  Clone,
+  PartialEq
+)]
+#[serde(default)]
+pub struct RotationConfig {
+  pub monitor: String,
+  pub source: ImageSource,
+  /// Take over from `source` in their part of the day,
+  /// when set
+  pub morning: ImageSource,
+  pub day: ImageSource,
+  pub evening: ImageSource,
+  pub night: ImageSource,
+  /// Seconds each image stays
+  pub interval: u32,
+  pub shuffle: bool,
+  pub mode: WallpaperMode,
+}
+
+impl RotationConfig {
+  pub fn source_at(&self, part: DayPart) -> &ImageSource {
+    let source = match part {
+      DayPart::Morning => &self.morning,
+      DayPart::Day => &self.day,
+      DayPart::Evening => &self.evening,
+      DayPart::Night => &self.night
+    };
+
+    match source.is_empty() {
+      true => &self.source,
+      false => source
+    }
+  }
+
+  pub fn validate(&self) -> Result<(), String> {
+    if self.monitor.is_empty() {
+      return Err("A rotation needs a monitor".into())
+    }
+
+    if self.interval < MIN_INTERVAL {
+      return Err(format!(
+        "interval must be at least {MIN_INTERVAL} seconds"))
+    }
+
+    let sources = [
+      &self.source,
+      &self.morning,
+      &self.day,
+      &self.evening,
+      &self.night
+    ];
+
+    if sources.iter().all(|s| s.is_empty()) {
+      return Err("A rotation needs images".into())
+    }
+
+    sources.iter().try_for_each(|s| s.validate())
+  }
+}
+
+
+/// A rotation running on a monitor
+#[derive(Debug, Clone)]
+pub struct Slideshow {
+  pub config: RotationConfig,
+  part: DayPart,
+  queue: Vec<String>,
+  /// Index in `queue` of the image shown
+  position: Option<usize>,
+  /// When the image shown is due for a change
+  due: Instant,
+}
+
+impl Slideshow {
+  /// A rotation due right away, for the part of the day
+  /// `minute` is in
+  pub fn new(config: RotationConfig, minute: u32) -> Self {
+    let mut slideshow = Self {
+      part: DayPart::at(minute),
+      config,
+      queue: vec![],
+      position: None,
+      due: Instant::now()
+    };
+
+    slideshow.reload();
+    slideshow
+  }
+
+  /// Whether the image shown should change, its time being
+  /// up or the part of the day having changed
+  pub fn is_due(&self, now: Instant, minute: u32) -> bool {
+    now >= self.due || DayPart::at(minute) != self.part
+  }
+
+  /// The image after the one shown, starting over with a
+  /// fresh order after the last
+  pub fn next(&mut self, minute: u32) -> Option<String> {
+    self.follow_day(minute);
+
+    if self.queue.is_empty() {
+      self.reload();
+    }
+
+    let len = self.queue.len();
+    let position = match self.position {
+      Some(p) if p + 1 < len => p + 1,
+      Some(_) => {
+        let last = self.queue.get(len.wrapping_sub(1)).cloned();
+        self.reload();
+
+        // Not the same image twice in a row
+        if self.queue.len() > 1 &&
+          self.queue.first() == last.as_ref()
+        {
+          self.queue.swap(0, 1);
+        }
+
+        0
+      },
+      None => 0
+    };
+
+    self.show(position)
+  }
+
+  /// The image before the one shown
+  pub fn previous(&mut self, minute: u32) -> Option<String> {
+    self.follow_day(minute);
+
+    let len = self.queue.len();
+    let position = match self.position {
+      _ if len == 0 => return None,
+      Some(0) | None => len - 1,
+      Some(p) => p - 1
+    };
+
+    self.show(position)
+  }
+
+  fn show(&mut self, position: usize) -> Option<String> {
+    let image = self.queue.get(position).cloned()?;
+
+    self.position = Some(position);
+    self.due = Instant::now() +
+      Duration::from_secs(self.config.interval as u64);
+
+    Some(image)
+  }
+
+  /// Switches to the images of the part of the day
+  /// `minute` is in
+  fn follow_day(&mut self, minute: u32) {
+    let part = DayPart::at(minute);
+
+    if part != self.part {
+      self.part = part;
+      self.reload();
+    }
+  }
+
+  fn reload(&mut self) {
+    self.queue = self.config.source_at(self.part).images();
+    self.position = None;
+
+    if self.config.shuffle {
+      shuffle(&mut self.queue);
+    }
+  }
+}
+
+
+/// Fisher-Yates with xorshift, seeded by the hasher std
+/// randomizes for every map
+fn shuffle(items: &mut [String]) {
+  let mut seed = RandomState::new().build_hasher().finish() | 1;
+
+  for i in (1..items.len()).rev() {
+    seed ^= seed << 13;
+    seed ^= seed >> 7;
+    seed ^= seed << 17;
+
+    items.swap(i, (seed % (i as u64 + 1)) as usize);
+  }
+}
+
+fn is_image(path: &Path) -> bool {
+  path
+    .extension()
+    .and_then(|e| e.to_str())
+    .is_some_and(|e| IMAGE_EXTENSIONS
+      .contains(&e.to_lowercase().as_str()))
+}
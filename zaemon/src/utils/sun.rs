@@ -0,0 +1,120 @@
+/* -
+ * -> Sunrise and sunset times, NOAA's general solar
+ *    position equations, good to a few minutes
+ * -
+*/
+
+use std::f64::consts::PI;
+
+/// Zenith of the sun at sunrise and sunset, with the
+/// refraction near the horizon and the sun's radius
+const SUN_ZENITH: f64 = 90.833;
+
+
+/// Sunrise and sunset of a day, in minutes since UTC
+/// midnight
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SunTimes {
+  Rises { sunrise: f64, sunset: f64 },
+  /// Polar day, the sun doesn't set
+  AlwaysUp,
+  /// Polar night, the sun doesn't rise
+  AlwaysDown,
+}
+
+/// Sun times at `latitude` and `longitude` (degrees, north
+/// and east positive) on day `ordinal` of the year, 1 for
+/// January 1st
+pub fn sun_times(
+  latitude: f64,
+  longitude: f64,
+  ordinal: u32
+) -> SunTimes {
+  // Fractional year at noon, in radians
+  let g = 2.0 * PI / 365.0 * (ordinal as f64 - 0.5);
+
+  // Minutes the solar clock runs ahead of mean time
+  let eqtime = 229.18 * (
+    0.000075
+    + 0.001868 * g.cos()
+    - 0.032077 * g.sin()
+    - 0.014615 * (2.0 * g).cos()
+    - 0.040849 * (2.0 * g).sin());
+
+  let decl =
+    0.006918
+    - 0.399912 * g.cos()
+    + 0.070257 * g.sin()
+    - 0.006758 * (2.0 * g).cos()
+    + 0.000907 * (2.0 * g).sin()
+    - 0.002697 * (3.0 * g).cos()
+    + 0.00148 * (3.0 * g).sin();
+
+  let lat = latitude.to_radians();
+  let cos_ha = SUN_ZENITH.to_radians().cos()
+    / (lat.cos() * decl.cos())
+    - lat.tan() * decl.tan();
+
+  if cos_ha > 1.0 { return SunTimes::AlwaysDown }
+  if cos_ha < -1.0 { return SunTimes::AlwaysUp }
+
+  let ha = cos_ha.acos().to_degrees();
+
+  SunTimes::Rises {
+    sunrise: 720.0 - 4.0 * (longitude + ha) - eqtime,
+    sunset: 720.0 - 4.0 * (longitude - ha) - eqtime
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Minutes since UTC midnight of `hh:mm`
+  fn utc(hour: i32, minute: i32) -> f64 {
+    (hour * 60 + minute) as f64
+  }
+
+  #[test]
+  fn matches_published_times() {
+    // Place, day of the year, and sunrise and sunset in
+    // UTC as published for 2023
+    let table = [
+      ("London", 51.5074, -0.1278, 172,
+        utc(3, 43), utc(20, 21)),
+      ("New York", 40.7128, -74.006, 355,
+        utc(12, 16), utc(21, 32)),
+      ("Sydney", -33.8688, 151.2093, 172,
+        utc(-3, 0), utc(6, 54)),
+      ("Quito", -0.1807, -78.4678, 79,
+        utc(11, 17), utc(23, 23)),
+    ];
+
+    for (place, lat, lon, day, rise, set) in table {
+      let (sunrise, sunset) = match sun_times(lat, lon, day) {
+        SunTimes::Rises { sunrise, sunset } =>
+          (sunrise, sunset),
+        other => panic!("{place}: {other:?}")
+      };
+
+      assert!((sunrise - rise).abs() < 3.0,
+        "{place}: sunrise {sunrise}, expected {rise}");
+      assert!((sunset - set).abs() < 3.0,
+        "{place}: sunset {sunset}, expected {set}");
+    }
+  }
+
+  #[test]
+  fn polar_day_and_night() {
+    // Tromsø at midsummer and midwinter
+    let tromso = |day| sun_times(69.65, 18.96, day);
+    assert_eq!(tromso(172), SunTimes::AlwaysUp);
+    assert_eq!(tromso(355), SunTimes::AlwaysDown);
+
+    // And the other way around south of the polar circle
+    let antarctic = |day| sun_times(-75.0, 0.0, day);
+    assert_eq!(antarctic(172), SunTimes::AlwaysDown);
+    assert_eq!(antarctic(355), SunTimes::AlwaysUp);
+  }
+}
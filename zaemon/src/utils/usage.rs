@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use zbus::zvariant;
+
+use super::cache;
+
+
+
+/// Launch timestamps kept per app for frecency
+const HISTORY_LEN: usize = 10;
+
+const DAY: u64 = 24 * 60 * 60;
+
+
+/// Usage of a single app, keyed by desktop ID in
+/// [`UsageDb`]
+#[derive(
+  serde::Deserialize,
+  serde::Serialize,
+  zvariant::Type,
+  Default,
+  Debug,
+  Clone
+)]
+pub struct AppUsage {
+  pub launches    :u32,
+  pub last_launch :u64,
+  pub focus_secs  :u64,
+  pub last_focus  :u64,
+  /// Most recent launch times, newest last
+  pub history     :Vec<u64>,
+}
+
+impl AppUsage {
+  /// Last time the app was launched or focused
+  pub fn last_used(&self) -> u64 {
+    self.last_launch.max(self.last_focus)
+  }
+
+  /// Recent launches weighted by age, plus a bonus for
+  /// every hour the app spent focused
+  pub fn frecency(&self, now: u64) -> u32 {
+    let visits: u32 = self.history
+      .iter()
+      .map(|t| match now.saturating_sub(*t) / DAY {
+        0..=3   => 100,
+        4..=14  => 70,
+        15..=31 => 50,
+        32..=90 => 30,
+        _       => 10
+      })
+      .sum();
+
+    let focus_hours = (self.focus_secs / 3600).min(100);
+
+    visits + focus_hours as u32 * 5
+  }
+}
+
+
+/// Launch and focus statistics, stored at
+/// `$XDG_DATA_HOME/hyprmaster/usage.bin`
+#[derive(
+  serde::Deserialize,
+  serde::Serialize,
+  zvariant::Type,
+  Default,
+  Debug
+)]
+pub struct UsageDb {
+  apps: HashMap<String, AppUsage>,
+}
+
+impl UsageDb {
+  pub fn load() -> Self {
+    Self::load_from(&usage_db_path())
+  }
+
+  pub fn save(&self) -> std::io::Result<()> {
+    self.save_to(&usage_db_path())
+  }
+
+  pub fn load_from(path: &Path) -> Self {
+    cache::read_from(path).unwrap_or_default()
+  }
+
+  pub fn save_to(&self, path: &Path) -> std::io::Result<()> {
+    cache::write_to(path, self)
+  }
+
+  pub fn get(&self, desktop_id: &str) -> Option<&AppUsage> {
+    self.apps.get(desktop_id)
+  }
+
+  pub fn iter(&self) ->
+    impl Iterator<Item = (&String, &AppUsage)>
+  {
+    self.apps.iter()
+  }
+
+  pub fn frecency(&self, desktop_id: &str) -> u32 {
+    self.get(desktop_id)
+      .map(|u| u.frecency(now()))
+      .unwrap_or(0)
+  }
+
+  pub fn record_launch(&mut self, desktop_id: &str) {
+    let now = now();
+    let usage = self.apps
+      .entry(desktop_id.to_string())
+      .or_default();
+
+    usage.launches += 1;
+    usage.last_launch = now;
+    usage.history.push(now);
+
+    if usage.history.len() > HISTORY_LEN {
+      usage.history.remove(0);
+    }
+  }
+
+  /// Adds `secs` of focus time, ending now
+  pub fn record_focus(&mut self, desktop_id: &str, secs: u64) {
+    let usage = self.apps
+      .entry(desktop_id.to_string())
+      .or_default();
+
+    usage.focus_secs += secs;
+    usage.last_focus = now();
+  }
+}
+
+
+pub fn now() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_secs())
+    .unwrap_or(0)
+}
+
+fn usage_db_path() -> PathBuf {
+  let home_dir = std::env::var("HOME").unwrap();
+  let data_home = std::env::var("XDG_DATA_HOME")
+    .ok()
+    .filter(|d| !d.trim().is_empty())
+    .unwrap_or(format!("{home_dir}/.local/share"));
+
+  PathBuf::from(data_home)
+    .join("hyprmaster")
+    .join("usage.bin")
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const NOW: u64 = 1_700_000_000;
+
+  fn launched(days_ago: &[u64]) -> AppUsage {
+    AppUsage {
+      history: days_ago.iter().map(|d| NOW - d * DAY).collect(),
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn launches_decay_by_age() {
+    let table = [
+      (0, 100), (3, 100),
+      (4, 70), (14, 70),
+      (15, 50), (31, 50),
+      (32, 30), (90, 30),
+      (91, 10), (1000, 10),
+    ];
+
+    for (days, score) in table {
+      assert_eq!(launched(&[days]).frecency(NOW), score,
+        "{days} days ago");
+    }
+
+    assert_eq!(launched(&[0, 5, 100]).frecency(NOW), 180);
+  }
+
+  #[test]
+  fn focus_counts_whole_hours_up_to_100() {
+    let focused = |secs| AppUsage {
+      focus_secs: secs,
+      ..Default::default()
+    };
+
+    assert_eq!(focused(3599).frecency(NOW), 0);
+    assert_eq!(focused(2 * 3600 + 59).frecency(NOW), 10);
+    assert_eq!(focused(500 * 3600).frecency(NOW), 500);
+  }
+
+  #[test]
+  fn keeps_the_latest_launches() {
+    let mut db = UsageDb::default();
+    for _ in 0..HISTORY_LEN + 3 {
+      db.record_launch("a.desktop");
+    }
+    db.record_focus("a.desktop", 90);
+    db.record_focus("a.desktop", 30);
+
+    let usage = db.get("a.desktop").unwrap();
+    assert_eq!(usage.launches, HISTORY_LEN as u32 + 3);
+    assert_eq!(usage.history.len(), HISTORY_LEN);
+    assert_eq!(usage.focus_secs, 120);
+    assert!(usage.last_used() > 0);
+  }
+
+  #[test]
+  fn round_trips_through_disk() {
+    let path = std::env::temp_dir().join(format!(
+      "zaemon-usage-{}/usage.bin", std::process::id()));
+
+    let mut db = UsageDb::default();
+    db.record_launch("a.desktop");
+    db.record_focus("b.desktop", 7200);
+    db.save_to(&path).unwrap();
+
+    let loaded = UsageDb::load_from(&path);
+    _ = std::fs::remove_dir_all(path.parent().unwrap());
+
+    let a = loaded.get("a.desktop").unwrap();
+    assert_eq!(a.launches, 1);
+    assert_eq!(a.history, db.get("a.desktop").unwrap().history);
+    assert_eq!(loaded.get("b.desktop").unwrap().focus_secs, 7200);
+    assert_eq!(loaded.frecency("b.desktop"), 10);
+
+    // Missing or broken files start over
+    assert!(UsageDb::load_from(&path).get("a.desktop").is_none());
+  }
+}
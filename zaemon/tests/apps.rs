@@ -0,0 +1,218 @@
+use std::fs;
+use std::slice;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+use zaemon::apps::{
+  AppCache,
+  AppUpdate,
+  desktop_file_id,
+  entry_paths
+};
+
+mod common;
+use common::TempDir;
+
+
+
+// Writes an entry named `name` at `rel` under `dir`, with
+// its mtime set to `secs` past the epoch
+fn write_entry(dir: &Path, rel: &str, name: &str, secs: u64)
+  -> PathBuf
+{
+  let path = dir.join(rel);
+  fs::create_dir_all(path.parent().unwrap()).unwrap();
+  fs::write(&path, format!("[Desktop Entry]\n\
+    Type=Application\nName={name}\nExec=true\n")).unwrap();
+
+  touch(&path, secs);
+  path
+}
+
+fn touch(path: &Path, secs: u64) {
+  fs::File::options()
+    .write(true)
+    .open(path)
+    .unwrap()
+    .set_modified(UNIX_EPOCH + Duration::from_secs(secs))
+    .unwrap();
+}
+
+// A user and a system `applications` dir, in precedence
+// order
+fn lookup_dirs(tmp: &TempDir) -> Vec<PathBuf> {
+  let dirs = vec![
+    tmp.path.join("home/applications"),
+    tmp.path.join("usr/applications")
+  ];
+
+  dirs.iter().for_each(|d| fs::create_dir_all(d).unwrap());
+  dirs
+}
+
+fn names(cache: &AppCache) -> Vec<(String, String)> {
+  cache.entries
+    .iter()
+    .map(|e| (e.desktop_id.clone(), e.name.clone()))
+    .collect()
+}
+
+
+#[test]
+fn desktop_file_ids() {
+  let dir = Path::new("/usr/share/applications");
+
+  assert_eq!(
+    desktop_file_id(dir, &dir.join("firefox.desktop")),
+    Some("firefox.desktop".to_string())
+  );
+  assert_eq!(
+    desktop_file_id(dir, &dir.join("kde4/dolphin.desktop")),
+    Some("kde4-dolphin.desktop".to_string())
+  );
+  assert_eq!(
+    desktop_file_id(dir, Path::new("/opt/x.desktop")),
+    None
+  );
+}
+
+#[test]
+fn earlier_dirs_win() {
+  let tmp = TempDir::new("apps-precedence");
+  let [home, usr] = &lookup_dirs(&tmp)[..] else {
+    unreachable!()
+  };
+
+  let mine = write_entry(home, "editor.desktop", "Mine", 1);
+  write_entry(usr, "editor.desktop", "System", 1);
+  let nested = write_entry(
+    usr, "kde4/files.desktop", "Files", 1);
+  fs::write(usr.join("notes.txt"), "").unwrap();
+
+  assert_eq!(entry_paths(&[home.clone(), usr.clone()]), vec![
+    ("editor.desktop".to_string(), mine),
+    ("kde4-files.desktop".to_string(), nested)
+  ]);
+}
+
+#[tokio::test]
+async fn hidden_entries_mask_later_dirs() {
+  let tmp = TempDir::new("apps-hidden");
+  let dirs = lookup_dirs(&tmp);
+
+  let hidden = dirs[0].join("editor.desktop");
+  fs::write(&hidden, "[Desktop Entry]\n\
+    Type=Application\nName=Mine\nHidden=true\n").unwrap();
+  write_entry(&dirs[1], "editor.desktop", "System", 1);
+  write_entry(&dirs[1], "term.desktop", "Term", 1);
+
+  let (scan, _) = AppCache::default().rescan(&dirs).await;
+  let key = hidden.to_string_lossy().to_string();
+
+  assert_eq!(names(&scan), vec![
+    ("term.desktop".to_string(), "Term".to_string())
+  ]);
+  assert!(scan.mtimes.contains_key(&key));
+}
+
+#[tokio::test]
+async fn rescans_only_changed_files() {
+  let tmp = TempDir::new("apps-rescan");
+  let dirs = lookup_dirs(&tmp);
+
+  let editor = write_entry(
+    &dirs[0], "editor.desktop", "Editor", 1);
+  write_entry(&dirs[1], "term.desktop", "Term", 1);
+
+  let (mut scan, parsed) = AppCache::default()
+    .rescan(&dirs)
+    .await;
+  assert_eq!(parsed.len(), 2);
+
+  // Stored entries are reused while their file is the same
+  scan.entries[0].name = "Stored".to_string();
+  let (scan, parsed) = scan.rescan(&dirs).await;
+
+  assert!(parsed.is_empty());
+  assert_eq!(scan.entries[0].name, "Stored");
+
+  touch(&editor, 2);
+  let (scan, parsed) = scan.rescan(&dirs).await;
+
+  assert_eq!(parsed, vec!["editor.desktop".to_string()]);
+  assert_eq!(names(&scan), vec![
+    ("editor.desktop".to_string(), "Editor".to_string()),
+    ("term.desktop".to_string(), "Term".to_string())
+  ]);
+}
+
+#[tokio::test]
+async fn outdated_caches_are_dropped() {
+  let tmp = TempDir::new("apps-cache");
+  let dirs = lookup_dirs(&tmp);
+  let file = tmp.path.join("apps.bin");
+
+  write_entry(&dirs[0], "editor.desktop", "Editor", 1);
+  let (mut scan, _) = AppCache::default().rescan(&dirs).await;
+
+  scan.save_to(&file).unwrap();
+  let stored = AppCache::load_from(&file);
+  assert_eq!(names(&stored), names(&scan));
+  assert_eq!(stored.mtimes, scan.mtimes);
+
+  scan.version += 1;
+  scan.save_to(&file).unwrap();
+  assert!(AppCache::load_from(&file).entries.is_empty());
+
+  assert!(AppCache::load_from(&tmp.path.join("none.bin"))
+    .entries
+    .is_empty());
+}
+
+#[tokio::test]
+async fn updates_touch_only_affected_ids() {
+  let tmp = TempDir::new("apps-update");
+  let dirs = lookup_dirs(&tmp);
+
+  write_entry(&dirs[1], "editor.desktop", "Editor", 1);
+  write_entry(&dirs[1], "kde4/files.desktop", "Files", 1);
+  let (mut cache, _) = AppCache::default().rescan(&dirs).await;
+
+  let term = write_entry(&dirs[1], "term.desktop", "Term", 1);
+  assert_eq!(
+    cache.update_paths(&dirs, slice::from_ref(&term)).await,
+    vec![AppUpdate::Added("term.desktop".to_string())]
+  );
+
+  // A user copy shadows the system entry
+  let mine = write_entry(&dirs[0], "editor.desktop", "Mine", 1);
+  assert_eq!(
+    cache.update_paths(&dirs, slice::from_ref(&mine)).await,
+    vec![AppUpdate::Changed("editor.desktop".to_string())]
+  );
+
+  fs::remove_file(&term).unwrap();
+  assert_eq!(
+    cache.update_paths(&dirs, &[term]).await,
+    vec![AppUpdate::Removed("term.desktop".to_string())]
+  );
+
+  // Removed dirs take every entry beneath them along
+  let kde4 = dirs[1].join("kde4");
+  fs::remove_dir_all(&kde4).unwrap();
+  assert_eq!(
+    cache.update_paths(&dirs, &[kde4]).await,
+    vec![AppUpdate::Removed("kde4-files.desktop".to_string())]
+  );
+
+  assert_eq!(names(&cache), vec![
+    ("editor.desktop".to_string(), "Mine".to_string())
+  ]);
+
+  // Shadowed files are no longer tracked
+  let key = mine.to_string_lossy().to_string();
+  assert_eq!(
+    cache.mtimes.keys().collect::<Vec<_>>(),
+    vec![&key]
+  );
+}
@@ -0,0 +1,211 @@
+//! Brightness controls against a fake sysfs tree, with a
+//! stub logind on a private bus
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use zbus::{connection, fdo, interface};
+
+use zaemon::screen::{
+  Backlight,
+  BrightnessControl,
+  DdcDisplay,
+  DeviceRoots
+};
+
+mod common;
+use common::{TempDir, TestBus};
+
+
+
+/// A sysfs tree with an Intel panel, an ACPI backlight, a
+/// DisplayPort and an HDMI monitor. Removed on drop.
+struct FakeSysfs {
+  dir: TempDir,
+}
+
+impl FakeSysfs {
+  fn new(name: &str) -> Self {
+    let dir = TempDir::new(name);
+    let root = &dir.path;
+
+    let backlights = [
+      ("intel_backlight", "400", "1000", "raw"),
+      ("acpi_video0", "5", "10", "firmware"),
+    ];
+
+    for (name, value, max, kind) in backlights {
+      let dir = root.join("sys/class/backlight").join(name);
+      write(&dir.join("brightness"), value);
+      write(&dir.join("max_brightness"), max);
+      write(&dir.join("type"), kind);
+    }
+
+    let drm = root.join("sys/class/drm");
+    mkdir(&drm.join("card1-eDP-1/intel_backlight"));
+    mkdir(&drm.join("card1-DP-1/i2c-7"));
+    mkdir(&drm.join("card1-HDMI-A-1"));
+    std::os::unix::fs::symlink(
+      "../../../i2c-4",
+      drm.join("card1-HDMI-A-1/ddc")
+    ).unwrap();
+
+    Self { dir }
+  }
+
+  fn roots(&self) -> DeviceRoots {
+    DeviceRoots {
+      sys: self.dir.path.join("sys"),
+      dev: self.dir.path.join("dev")
+    }
+  }
+
+  fn brightness(&self, name: &str) -> String {
+    fs::read_to_string(self.dir.path.join(format!(
+      "sys/class/backlight/{name}/brightness"))).unwrap()
+  }
+}
+
+fn write(path: &Path, value: &str) {
+  mkdir(path.parent().unwrap());
+  fs::write(path, value).unwrap();
+}
+
+fn mkdir(path: &Path) {
+  fs::create_dir_all(path).unwrap();
+}
+
+
+type Calls = Arc<Mutex<Vec<(String, String, u32)>>>;
+
+/// logind's session object, writing to the fake sysfs
+/// like logind does to the real one
+struct StubSession {
+  sys: PathBuf,
+  calls: Calls,
+}
+
+#[interface(name = "org.freedesktop.login1.Session")]
+impl StubSession {
+  fn set_brightness(
+    &self,
+    subsystem: &str,
+    name: &str,
+    brightness: u32
+  ) -> fdo::Result<()> {
+    self.calls.lock().unwrap().push((
+      subsystem.to_string(), name.to_string(), brightness));
+
+    let path = self.sys
+      .join("class")
+      .join(subsystem)
+      .join(name)
+      .join("brightness");
+
+    fs::write(path, brightness.to_string())
+      .map_err(|e| fdo::Error::IOError(e.to_string()))
+  }
+}
+
+
+
+#[test]
+fn backlight_of_panel() {
+  let sysfs = FakeSysfs::new("panel");
+  let roots = sysfs.roots();
+
+  // Linked under the connector by the driver
+  let linked = Backlight::for_connector(&roots, "eDP-1")
+    .unwrap();
+  assert_eq!(linked.name, "intel_backlight");
+  assert_eq!(linked.percent().unwrap(), 40.0);
+
+  // Not linked, the kernel's preferred type wins
+  let best = Backlight::for_connector(&roots, "LVDS-1")
+    .unwrap();
+  assert_eq!(best.name, "acpi_video0");
+  assert_eq!(best.percent().unwrap(), 50.0);
+
+  // External monitors have no backlight
+  assert!(Backlight::for_connector(&roots, "DP-1").is_none());
+  assert!(Backlight::for_connector(&roots, "HDMI-A-1")
+    .is_none());
+}
+
+#[test]
+fn ddc_bus_of_monitor() {
+  let sysfs = FakeSysfs::new("ddc");
+  let roots = sysfs.roots();
+
+  let dp = DdcDisplay::for_connector(&roots, "DP-1").unwrap();
+  assert_eq!(dp.bus, roots.dev.join("i2c-7"));
+
+  let hdmi = DdcDisplay::for_connector(&roots, "HDMI-A-1")
+    .unwrap();
+  assert_eq!(hdmi.bus, roots.dev.join("i2c-4"));
+
+  assert!(DdcDisplay::for_connector(&roots, "DP-2").is_none());
+
+  assert!(matches!(
+    BrightnessControl::for_connector(&roots, "HDMI-A-1"),
+    Some(BrightnessControl::Ddc(_))));
+  assert!(matches!(
+    BrightnessControl::for_connector(&roots, "eDP-1"),
+    Some(BrightnessControl::Backlight(_))));
+}
+
+#[tokio::test]
+async fn backlight_set_through_logind() {
+  let bus = match TestBus::start() {
+    Some(b) => b,
+    None => {
+      eprintln!("No dbus-daemon, skipping");
+      return
+    }
+  };
+
+  let sysfs = FakeSysfs::new("logind");
+  let calls = Calls::default();
+
+  let _logind = connection::Builder::address(
+    bus.address.as_str()
+  )
+    .unwrap()
+    .name("org.freedesktop.login1")
+    .unwrap()
+    .serve_at(
+      "/org/freedesktop/login1/session/auto",
+      StubSession {
+        sys: sysfs.roots().sys,
+        calls: calls.clone()
+      })
+    .unwrap()
+    .build()
+    .await
+    .unwrap();
+
+  let client = bus.connect().await;
+  let control = BrightnessControl::for_connector(
+    &sysfs.roots(), "eDP-1").unwrap();
+
+  control.set_percent(&client, 75.0).await.unwrap();
+  assert_eq!(sysfs.brightness("intel_backlight"), "750");
+  assert_eq!(control.percent().await.unwrap(), 75.0);
+
+  // Never fully dark
+  control.set_percent(&client, 0.0).await.unwrap();
+  assert_eq!(sysfs.brightness("intel_backlight"), "1");
+
+  let calls: Vec<(String, String, u32)> = calls
+    .lock()
+    .unwrap()
+    .clone();
+  let call = |value| (
+    "backlight".to_string(),
+    "intel_backlight".to_string(),
+    value
+  );
+
+  assert_eq!(calls, vec![call(750), call(1)]);
+}
@@ -0,0 +1,105 @@
+//! Fixtures shared by the integration tests
+
+// Every test crate uses only part of them
+#![allow(dead_code)]
+
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+
+use zbus::connection;
+
+
+/// A private bus, killed on drop
+pub struct TestBus {
+  daemon: Child,
+  pub address: String,
+}
+
+impl TestBus {
+  pub fn start() -> Option<Self> {
+    let mut daemon = Command::new("dbus-daemon")
+      .args(["--session", "--nofork", "--print-address=1"])
+      .stdout(Stdio::piped())
+      .spawn()
+      .ok()?;
+
+    let mut address = String::new();
+    BufReader::new(daemon.stdout.as_mut()?)
+      .read_line(&mut address)
+      .ok()?;
+
+    Some(Self {
+      daemon,
+      address: address.trim().to_string()
+    })
+  }
+
+  pub async fn connect(&self) -> zbus::Connection {
+    connection::Builder::address(self.address.as_str())
+      .unwrap()
+      .build()
+      .await
+      .unwrap()
+  }
+}
+
+impl Drop for TestBus {
+  fn drop(&mut self) {
+    _ = self.daemon.kill();
+    _ = self.daemon.wait();
+  }
+}
+
+
+/// A temp dir, removed on drop
+pub struct TempDir {
+  pub path: PathBuf,
+}
+
+impl TempDir {
+  pub fn new(name: &str) -> Self {
+    let path = std::env::temp_dir().join(format!(
+      "zaemon-{name}-{}", std::process::id()));
+    _ = fs::remove_dir_all(&path);
+    fs::create_dir_all(&path).unwrap();
+
+    Self { path }
+  }
+
+  /// An empty file standing in for an image
+  pub fn image(&self, name: &str) -> String {
+    let path = self.path.join(name);
+    fs::write(&path, "").unwrap();
+
+    path.to_string_lossy().to_string()
+  }
+
+  /// A PNG in stripes of `colors`, as wide as their weight
+  pub fn striped(
+    &self,
+    name: &str,
+    colors: &[([u8; 3], u32)]
+  ) -> String {
+    let stripes: Vec<[u8; 3]> = colors
+      .iter()
+      .flat_map(|(c, w)| std::iter::repeat_n(*c, *w as usize))
+      .collect();
+
+    let path = self.path.join(name);
+    image::RgbImage::from_fn(
+      stripes.len() as u32, 16,
+      |x, _| image::Rgb(stripes[x as usize]))
+      .save(&path)
+      .unwrap();
+
+    path.to_string_lossy().to_string()
+  }
+}
+
+impl Drop for TempDir {
+  fn drop(&mut self) {
+    _ = fs::remove_dir_all(&self.path);
+  }
+}
@@ -0,0 +1,176 @@
+use std::path::Path;
+use std::time::{Duration, Instant};
+use std::fs;
+use std::slice;
+
+use zaemon::icons::{icon_lookup, ICON_SIZE};
+
+mod common;
+use common::TempDir;
+
+
+
+const SIZES: [&str; 8] = [
+  "16x16", "22x22", "24x24", "32x32",
+  "48x48", "64x64", "128x128", "256x256"
+];
+
+const CONTEXTS: [&str; 4] = [
+  "actions", "apps", "devices", "status"
+];
+
+// Builds a hicolor-like tree with `per_dir` icons in every
+// size/context pair, plus a scalable SVG for every icon
+fn make_icon_tree(root: &Path, per_dir: usize) {
+  for ctx in CONTEXTS {
+    for size in SIZES {
+      let dir = root.join(size).join(ctx);
+      fs::create_dir_all(&dir).unwrap();
+
+      for i in 0..per_dir {
+        fs::write(dir.join(format!("{ctx}-{i}.png")), "")
+          .unwrap();
+      }
+    }
+
+    let dir = root.join("scalable").join(ctx);
+    fs::create_dir_all(&dir).unwrap();
+
+    for i in 0..per_dir {
+      fs::write(dir.join(format!("{ctx}-{i}.svg")), "")
+        .unwrap();
+    }
+  }
+}
+
+fn names(list: &[&str]) -> Vec<String> {
+  list.iter().map(|s| s.to_string()).collect()
+}
+
+
+#[tokio::test]
+async fn prefers_svg_over_png() {
+  let dir = TempDir::new("svg-over-png");
+  let root = &dir.path;
+  make_icon_tree(root, 5);
+
+  let icon = icon_lookup(&names(&["apps-3"]), vec![
+    root.clone()
+  ], ICON_SIZE).await;
+
+  assert_eq!(icon, Some(root
+    .join("scalable/apps/apps-3.svg")
+    .to_string_lossy()
+    .to_string()));
+}
+
+#[tokio::test]
+async fn prefers_theme_over_fallback() {
+  let theme_dir = TempDir::new("theme-first");
+  let fallback_dir = TempDir::new("fallback-last");
+  let theme = &theme_dir.path;
+  let fallback = &fallback_dir.path;
+
+  let theme_icon = theme.join("48x48/apps/firefox.png");
+  fs::create_dir_all(theme_icon.parent().unwrap()).unwrap();
+  fs::write(&theme_icon, "").unwrap();
+
+  let fallback_icon = fallback.join("scalable/firefox.svg");
+  fs::create_dir_all(fallback_icon.parent().unwrap())
+    .unwrap();
+  fs::write(&fallback_icon, "").unwrap();
+
+  let icon = icon_lookup(&names(&["firefox"]), vec![
+    theme.clone(),
+    fallback.clone()
+  ], ICON_SIZE).await;
+
+  assert_eq!(icon, Some(theme_icon
+    .to_string_lossy()
+    .to_string()));
+}
+
+#[tokio::test]
+async fn prefers_earlier_names() {
+  let dir = TempDir::new("name-order");
+  let root = &dir.path;
+
+  let generic = root.join("scalable/edit.svg");
+  let specific = root.join("16x16/edit-copy.png");
+  fs::create_dir_all(generic.parent().unwrap()).unwrap();
+  fs::create_dir_all(specific.parent().unwrap()).unwrap();
+  fs::write(&generic, "").unwrap();
+  fs::write(&specific, "").unwrap();
+
+  let icon = icon_lookup(
+    &names(&["edit-copy", "edit"]),
+    vec![root.clone()],
+    ICON_SIZE
+  ).await;
+
+  assert_eq!(icon, Some(specific
+    .to_string_lossy()
+    .to_string()));
+}
+
+#[tokio::test]
+async fn prefers_closest_png_size() {
+  let dir = TempDir::new("png-size");
+  let root = &dir.path;
+  make_icon_tree(root, 1);
+  fs::remove_dir_all(root.join("scalable")).unwrap();
+
+  let name = names(&["apps-0"]);
+  let lookup = |size| {
+    icon_lookup(&name, vec![root.clone()], size)
+  };
+
+  let at = |size: &str| Some(root
+    .join(format!("{size}/apps/apps-0.png"))
+    .to_string_lossy()
+    .to_string());
+
+  assert_eq!(lookup(48).await, at("48x48"));
+  assert_eq!(lookup(20).await, at("22x22"));
+  assert_eq!(lookup(1000).await, at("256x256"));
+
+  // As far from 28 as 32, the larger one scales down
+  assert_eq!(lookup(28).await, at("32x32"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "benchmark, run with --ignored"]
+async fn bench_synthetic_tree() {
+  let dir = TempDir::new("bench");
+  let root = &dir.path;
+  let per_dir = 250;
+  make_icon_tree(root, per_dir);
+
+  let lookups = 20;
+  let started = Instant::now();
+
+  for i in 0..lookups {
+    let name = format!("status-{}", (i * 7) % per_dir);
+    let icon = icon_lookup(slice::from_ref(&name), vec![
+      root.clone()
+    ], ICON_SIZE).await;
+
+    assert!(icon.unwrap().ends_with(&format!("{name}.svg")));
+  }
+
+  let missing = icon_lookup(&names(&["nope"]), vec![
+    root.clone()
+  ], ICON_SIZE).await;
+  assert_eq!(missing, None);
+
+  let elapsed = started.elapsed();
+  let files = per_dir * CONTEXTS.len() * (SIZES.len() + 1);
+  eprintln!(
+    "icon_lookup: {} lookups over {} files in {:?}",
+    lookups + 1,
+    files,
+    elapsed
+  );
+
+  assert!(elapsed < Duration::from_secs(10));
+}
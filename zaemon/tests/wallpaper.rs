@@ -0,0 +1,466 @@
+//! Wallpapers set over D-Bus against a fake hyprpaper
+//! socket, served on a private bus
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::os::unix::net::UnixListener;
+use std::sync::{Arc, Mutex};
+
+use zbus::{connection, proxy, proxy::CacheProperties};
+
+use zaemon::wallpaper::{
+  Hyprpaper,
+  ImageSource,
+  RotationConfig,
+  WallpaperInterface,
+  WallpaperMode
+};
+
+mod common;
+use common::{TempDir, TestBus};
+
+
+
+/// Answers like hyprpaper does and logs every command but
+/// `listloaded`
+struct FakeHyprpaper {
+  dir: TempDir,
+  commands: Arc<Mutex<Vec<String>>>,
+}
+
+impl FakeHyprpaper {
+  fn start(name: &str) -> Self {
+    let dir = TempDir::new(name);
+    let listener = UnixListener::bind(
+      dir.path.join(".hyprpaper.sock")).unwrap();
+
+    let commands = Arc::new(Mutex::new(vec![]));
+    let log = commands.clone();
+
+    std::thread::spawn(move || {
+      let mut loaded = vec![];
+
+      for stream in listener.incoming() {
+        let mut stream = match stream {
+          Ok(s) => s,
+          Err(_) => break
+        };
+
+        let mut buf = [0u8; 4096];
+        let len = stream.read(&mut buf).unwrap_or(0);
+        let cmd = String::from_utf8_lossy(&buf[..len])
+          .to_string();
+
+        let reply = reply(&cmd, &mut loaded);
+        if cmd != "listloaded" {
+          log.lock().unwrap().push(cmd);
+        }
+
+        _ = stream.write_all(reply.as_bytes());
+      }
+    });
+
+    Self { dir, commands }
+  }
+
+  fn hyprpaper(&self) -> Hyprpaper {
+    Hyprpaper::at(self.dir.path.join(".hyprpaper.sock"))
+  }
+
+  /// Commands received since the last call
+  fn commands(&self) -> Vec<String> {
+    std::mem::take(&mut *self.commands.lock().unwrap())
+  }
+}
+
+fn reply(cmd: &str, loaded: &mut Vec<String>) -> String {
+  let (name, args) = cmd.split_once(' ').unwrap_or((cmd, ""));
+
+  match name {
+    "listloaded" if loaded.is_empty() =>
+      "no wallpapers loaded".into(),
+    "listloaded" => loaded.join("\n"),
+    "preload" => {
+      loaded.push(args.to_string());
+      "ok".into()
+    },
+    "unload" => {
+      loaded.retain(|p| p != args);
+      "ok".into()
+    },
+    "wallpaper" => {
+      let path = args
+        .split_once(',')
+        .map(|(_, p)| p)
+        .unwrap_or_default()
+        .trim_start_matches("contain:")
+        .trim_start_matches("tile:");
+
+      match loaded.iter().any(|p| p == path) {
+        true => "ok".into(),
+        false => "wallpaper failed (not preloaded)".into()
+      }
+    },
+    _ => "invalid hyprpaper request".into()
+  }
+}
+
+
+#[proxy(
+  interface = "org.hypr.Hyprmaster.Wallpaper",
+  default_service = "org.hypr.Hyprmaster",
+  default_path = "/wallpaper"
+)]
+trait Wallpaper {
+  #[zbus(property)]
+  fn wallpapers(&self) -> zbus::Result<HashMap<String, String>>;
+
+  fn set_wallpaper(
+    &self,
+    monitor: &str,
+    path: &str,
+    mode: &str
+  ) -> zbus::Result<()>;
+
+  fn preview_wallpaper(
+    &self,
+    monitor: &str,
+    path: &str,
+    mode: &str
+  ) -> zbus::Result<()>;
+
+  fn end_preview(&self, monitor: &str) -> zbus::Result<()>;
+
+  fn set_rotation(&self, config: RotationConfig) ->
+    zbus::Result<()>;
+
+  fn next(&self, monitor: &str) -> zbus::Result<()>;
+
+  fn previous(&self, monitor: &str) -> zbus::Result<()>;
+
+  fn pause(&self, monitor: &str, paused: bool) ->
+    zbus::Result<()>;
+
+  #[zbus(property)]
+  fn paused(&self) -> zbus::Result<Vec<String>>;
+
+  fn palette(&self, monitor: &str) -> zbus::Result<Vec<String>>;
+
+  fn accent(&self, monitor: &str) -> zbus::Result<String>;
+}
+
+/// Serves `iface` on `bus` and returns a client for it,
+/// along with the serving connection
+async fn serve(
+  bus: &TestBus,
+  iface: WallpaperInterface
+) -> (zbus::Connection, WallpaperProxy<'static>) {
+  let server = connection::Builder::address(
+    bus.address.as_str()
+  )
+    .unwrap()
+    .name("org.hypr.Hyprmaster")
+    .unwrap()
+    .serve_at("/wallpaper", iface)
+    .unwrap()
+    .build()
+    .await
+    .unwrap();
+
+  let client = WallpaperProxy::builder(&bus.connect().await)
+    .cache_properties(CacheProperties::No)
+    .build()
+    .await
+    .unwrap();
+
+  (server, client)
+}
+
+macro_rules! test_bus {
+  () => {
+    match TestBus::start() {
+      Some(b) => b,
+      None => {
+        eprintln!("No dbus-daemon, skipping");
+        return
+      }
+    }
+  };
+}
+
+
+
+#[tokio::test]
+async fn set_through_hyprpaper() {
+  let bus = test_bus!();
+  let hyprpaper = FakeHyprpaper::start("hyprpaper-set");
+  let state = TempDir::new("wallpapers-set");
+
+  let (a, b) = (
+    hyprpaper.dir.image("a.png"),
+    hyprpaper.dir.image("b.png")
+  );
+
+  let (_server, client) = serve(&bus, WallpaperInterface::with(
+    hyprpaper.hyprpaper(),
+    state.path.join("wallpapers.json")
+  )).await;
+
+  client.set_wallpaper("DP-1", &a, "cover").await.unwrap();
+  assert_eq!(hyprpaper.commands(), [
+    format!("preload {a}"),
+    format!("wallpaper DP-1,{a}")
+  ]);
+
+  // Already loaded
+  client.set_wallpaper("HDMI-A-1", &a, "tile").await.unwrap();
+  assert_eq!(hyprpaper.commands(), [
+    format!("wallpaper HDMI-A-1,tile:{a}")
+  ]);
+
+  // Still on the other monitor, so kept loaded
+  client.set_wallpaper("DP-1", &b, "contain").await.unwrap();
+  assert_eq!(hyprpaper.commands(), [
+    format!("preload {b}"),
+    format!("wallpaper DP-1,contain:{b}")
+  ]);
+
+  client.set_wallpaper("HDMI-A-1", &b, "cover").await.unwrap();
+  assert_eq!(hyprpaper.commands(), [
+    format!("wallpaper HDMI-A-1,{b}"),
+    format!("unload {a}")
+  ]);
+
+  assert_eq!(client.wallpapers().await.unwrap(), HashMap::from([
+    ("DP-1".to_string(), b.clone()),
+    ("HDMI-A-1".to_string(), b.clone())
+  ]));
+}
+
+#[tokio::test]
+async fn restored_after_restart() {
+  let bus = test_bus!();
+  let hyprpaper = FakeHyprpaper::start("hyprpaper-restart");
+  let state = TempDir::new("wallpapers-restart");
+  let state_path = state.path.join("wallpapers.json");
+
+  let (a, b) = (
+    hyprpaper.dir.image("a.png"),
+    hyprpaper.dir.image("b.png")
+  );
+
+  {
+    let (_server, client) = serve(&bus, WallpaperInterface::with(
+      hyprpaper.hyprpaper(),
+      state_path.clone()
+    )).await;
+
+    client.set_wallpaper("DP-1", &b, "tile").await.unwrap();
+    client.set_wallpaper("", &a, "cover").await.unwrap();
+  }
+
+  // A new hyprpaper, with nothing loaded
+  let restarted = FakeHyprpaper::start("hyprpaper-restarted");
+
+  WallpaperInterface::with(restarted.hyprpaper(), state_path)
+    .restore()
+    .await
+    .unwrap();
+
+  // Monitors without their own first
+  assert_eq!(restarted.commands(), [
+    format!("preload {a}"),
+    format!("wallpaper ,{a}"),
+    format!("preload {b}"),
+    format!("wallpaper DP-1,tile:{b}")
+  ]);
+}
+
+#[tokio::test]
+async fn preview_not_remembered() {
+  let bus = test_bus!();
+  let hyprpaper = FakeHyprpaper::start("hyprpaper-preview");
+  let state = TempDir::new("wallpapers-preview");
+
+  let (a, b) = (
+    hyprpaper.dir.image("a.png"),
+    hyprpaper.dir.image("b.png")
+  );
+
+  let (_server, client) = serve(&bus, WallpaperInterface::with(
+    hyprpaper.hyprpaper(),
+    state.path.join("wallpapers.json")
+  )).await;
+
+  client.set_wallpaper("", &a, "cover").await.unwrap();
+  client.preview_wallpaper("DP-1", &b, "contain").await
+    .unwrap();
+
+  assert_eq!(client.wallpapers().await.unwrap(), HashMap::from([
+    ("".to_string(), a.clone())
+  ]));
+
+  hyprpaper.commands();
+  client.end_preview("DP-1").await.unwrap();
+
+  // Back to the one for all monitors
+  assert_eq!(hyprpaper.commands(), [
+    format!("wallpaper DP-1,{a}"),
+    format!("unload {b}")
+  ]);
+
+  // Kept once set for real
+  client.preview_wallpaper("DP-1", &b, "tile").await.unwrap();
+  client.set_wallpaper("DP-1", &b, "tile").await.unwrap();
+  client.end_preview("DP-1").await.unwrap();
+
+  assert_eq!(hyprpaper.commands(), [
+    format!("preload {b}"),
+    format!("wallpaper DP-1,tile:{b}"),
+    format!("wallpaper DP-1,tile:{b}")
+  ]);
+}
+
+#[tokio::test]
+async fn rejects_invalid_requests() {
+  let bus = test_bus!();
+  let hyprpaper = FakeHyprpaper::start("hyprpaper-invalid");
+  let state = TempDir::new("wallpapers-invalid");
+  let image = hyprpaper.dir.image("a.png");
+
+  let (_server, client) = serve(&bus, WallpaperInterface::with(
+    hyprpaper.hyprpaper(),
+    state.path.join("wallpapers.json")
+  )).await;
+
+  let missing = hyprpaper.dir.path.join("missing.png");
+  let requests = [
+    ("DP-1", missing.to_str().unwrap(), "cover"),
+    ("DP-1", "a.png", "cover"),
+    ("DP-1", image.as_str(), "stretch")
+  ];
+
+  for (monitor, path, mode) in requests {
+    assert!(client.set_wallpaper(monitor, path, mode).await
+      .is_err());
+  }
+
+  assert!(hyprpaper.commands().is_empty());
+  assert!(client.wallpapers().await.unwrap().is_empty());
+  assert!(!state.path.join("wallpapers.json").exists());
+}
+
+#[tokio::test]
+async fn rotates_playlist() {
+  let bus = test_bus!();
+  let hyprpaper = FakeHyprpaper::start("hyprpaper-rotate");
+  let state = TempDir::new("wallpapers-rotate");
+
+  let images: Vec<String> = ["a.png", "b.png", "c.png"]
+    .iter()
+    .map(|n| hyprpaper.dir.image(n))
+    .collect();
+
+  let (_server, client) = serve(&bus, WallpaperInterface::with(
+    hyprpaper.hyprpaper(),
+    state.path.join("wallpapers.json")
+  )).await;
+
+  let config = RotationConfig {
+    monitor: "DP-1".into(),
+    source: ImageSource {
+      playlist: images.clone(),
+      ..Default::default()
+    },
+    interval: 600,
+    mode: WallpaperMode::Tile,
+    ..Default::default()
+  };
+
+  // Starts on the first image
+  client.set_rotation(config.clone()).await.unwrap();
+  client.next("DP-1").await.unwrap();
+  client.next("DP-1").await.unwrap();
+
+  // Wraps around both ways
+  client.next("DP-1").await.unwrap();
+  client.previous("DP-1").await.unwrap();
+
+  let commands = hyprpaper.commands();
+  let shown: Vec<&str> = commands
+    .iter()
+    .filter_map(|c| c.strip_prefix("wallpaper DP-1,tile:"))
+    .collect();
+
+  assert_eq!(shown, [
+    &images[0], &images[1], &images[2], &images[0], &images[2]
+  ]);
+
+  client.pause("", true).await.unwrap();
+  assert_eq!(client.paused().await.unwrap(), ["DP-1"]);
+
+  client.pause("DP-1", false).await.unwrap();
+  assert!(client.paused().await.unwrap().is_empty());
+
+  // Kept across restarts
+  let rotations = state.path.join("wallpaper-rotations.json");
+  assert!(rotations.exists());
+
+  let invalid = [
+    RotationConfig { interval: 1, ..config.clone() },
+    RotationConfig { monitor: "".into(), ..config.clone() },
+    RotationConfig { source: ImageSource::default(), ..config }
+  ];
+
+  for config in invalid {
+    assert!(client.set_rotation(config).await.is_err());
+  }
+
+  assert!(client.next("HDMI-A-1").await.is_err());
+  assert!(client.pause("HDMI-A-1", true).await.is_err());
+}
+
+#[tokio::test]
+async fn palette_of_shown_wallpaper() {
+  let bus = test_bus!();
+  let hyprpaper = FakeHyprpaper::start("hyprpaper-palette");
+  let state = TempDir::new("wallpapers-palette");
+
+  let (navy, orange, white) =
+    ([20, 30, 90], [240, 140, 20], [250, 250, 250]);
+
+  let striped = hyprpaper.dir.striped("striped.png", &[
+    (navy, 60),
+    (white, 30),
+    (orange, 10)
+  ]);
+
+  let (_server, client) = serve(&bus, WallpaperInterface::with(
+    hyprpaper.hyprpaper(),
+    state.path.join("wallpapers.json")
+  )).await;
+
+  assert!(client.palette("DP-1").await.is_err());
+
+  // Monitors without their own show the one for all
+  client.set_wallpaper("", &striped, "cover").await.unwrap();
+
+  assert_eq!(client.palette("DP-1").await.unwrap(), [
+    "#141e5a", "#fafafa", "#f08c14"
+  ]);
+
+  // Colorful beats common, and white is too light
+  assert_eq!(client.accent("DP-1").await.unwrap(), "#f08c14");
+
+  // Previews count too
+  let plain = hyprpaper.dir.striped("plain.png", &[
+    (navy, 10)
+  ]);
+  client.preview_wallpaper("DP-1", &plain, "cover").await
+    .unwrap();
+
+  assert_eq!(client.palette("DP-1").await.unwrap(), [
+    "#141e5a"
+  ]);
+  assert_eq!(client.accent("DP-1").await.unwrap(), "#141e5a");
+}